@@ -0,0 +1,96 @@
+//! Helpers for other programs to invoke this program through CPI.
+use crate::{
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::AccountMeta,
+    program::invoke_signed, program_error::ProgramError,
+};
+
+/// The accounts and options used by `validate_cpi`.  Optional accounts that are `None` are
+/// passed to the `validate` instruction as the program ID, the same as `ValidateBuilder` does.
+pub struct ValidateCpiContext<'a, 'info> {
+    /// This program.
+    pub auth_rules_program: &'a AccountInfo<'info>,
+    /// The PDA account where the `RuleSet` is stored.
+    pub rule_set_pda: &'a AccountInfo<'info>,
+    /// Mint of token asset.
+    pub mint: &'a AccountInfo<'info>,
+    /// System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Payer for the `RuleSet` state PDA account, needed if `update_rule_state` is true.
+    pub payer: Option<&'a AccountInfo<'info>>,
+    /// Signing authority for any `Rule` state updates, needed if `update_rule_state` is true.
+    pub rule_authority: Option<&'a AccountInfo<'info>>,
+    /// The PDA account where any `RuleSet` state is stored.
+    pub rule_set_state_pda: Option<&'a AccountInfo<'info>>,
+    /// Update any relevant state stored in Rule, such as the Frequency `last_update` time value.
+    pub update_rule_state: bool,
+    /// Optional revision of the `RuleSet` to use.  If `None`, the latest revision is used.
+    pub rule_set_revision: Option<usize>,
+}
+
+/// Validate `operation` and `payload` against a `RuleSet` by invoking this program's `validate`
+/// instruction with `invoke_signed`.  The `AccountMeta`s are assembled from the account infos,
+/// keeping their signer and writable flags, and `additional_rule_accounts` are passed after the
+/// accounts in `ctx`.  `signer_seeds` are used to sign for any PDAs of the calling program; to
+/// sign for a PDA in `additional_rule_accounts`, pass a clone of its account info with
+/// `is_signer` set.
+pub fn validate_cpi<'info>(
+    ctx: &ValidateCpiContext<'_, 'info>,
+    additional_rule_accounts: &[AccountInfo<'info>],
+    operation: String,
+    payload: Payload,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut builder = ValidateBuilder::new();
+    builder
+        .rule_set_pda(*ctx.rule_set_pda.key)
+        .mint(*ctx.mint.key)
+        .additional_rule_accounts(
+            additional_rule_accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: *account.key,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+        );
+    if let Some(payer) = ctx.payer {
+        builder.payer(*payer.key);
+    }
+    if let Some(rule_authority) = ctx.rule_authority {
+        builder.rule_authority(*rule_authority.key);
+    }
+    if let Some(rule_set_state_pda) = ctx.rule_set_state_pda {
+        builder.rule_set_state_pda(*rule_set_state_pda.key);
+    }
+
+    let instruction = builder
+        .build(ValidateArgs::V1 {
+            operation,
+            payload,
+            update_rule_state: ctx.update_rule_state,
+            rule_set_revision: ctx.rule_set_revision,
+        })
+        .map_err(|_| ProgramError::InvalidArgument)?
+        .instruction();
+
+    // Optional accounts that are `None` are filled in by the program account.
+    let mut account_infos = vec![
+        ctx.rule_set_pda.clone(),
+        ctx.mint.clone(),
+        ctx.system_program.clone(),
+        ctx.payer.unwrap_or(ctx.auth_rules_program).clone(),
+        ctx.rule_authority.unwrap_or(ctx.auth_rules_program).clone(),
+        ctx.rule_set_state_pda
+            .unwrap_or(ctx.auth_rules_program)
+            .clone(),
+    ];
+    account_infos.extend_from_slice(additional_rule_accounts);
+    account_infos.push(ctx.auth_rules_program.clone());
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
@@ -4,11 +4,15 @@ use solana_program::{
     decode_error::DecodeError,
     msg,
     program_error::{PrintProgramError, ProgramError},
+    pubkey::Pubkey,
 };
 use thiserror::Error;
 
 #[derive(Error, Clone, Debug, Eq, PartialEq, FromPrimitive)]
-/// The various errors that can be returned by the Rule Set program instructions.
+/// The various errors that can be returned by the Rule Set program instructions.  Each variant is
+/// returned on-chain as `ProgramError::Custom` with the code in its doc comment.  These codes are
+/// part of the program interface: new variants must be added at the end, and existing variants
+/// must never be reordered or removed.
 pub enum RuleSetError {
     /// 0 - Numerical Overflow
     #[error("Numerical Overflow")]
@@ -22,16 +26,16 @@ pub enum RuleSetError {
     #[error("Data slice unexpected index error")]
     DataSliceUnexpectedIndexError,
 
-    /// 3 - Incorrect account owner
-    #[error("Incorrect account owner")]
+    /// 3 - Account is not owned by the expected program
+    #[error("Account is not owned by the expected program")]
     IncorrectOwner,
 
     /// 4 - PayloadVec Index error.
     #[error("Could not index into PayloadVec")]
     PayloadVecIndexError,
 
-    /// 5 - Derived key invalid
-    #[error("Derived key invalid")]
+    /// 5 - Account address does not match the PDA derived from its seeds
+    #[error("Account address does not match the PDA derived from its seeds")]
     DerivedKeyInvalid,
 
     /// 6 - Payer is not a signer
@@ -66,12 +70,12 @@ pub enum RuleSetError {
     #[error("MessagePack deserialization error")]
     MessagePackDeserializationError,
 
-    /// 14 - Missing account
-    #[error("Missing account")]
+    /// 14 - An account needed by a rule was not passed in
+    #[error("An account needed by a rule was not passed in")]
     MissingAccount,
 
-    /// 15 - Missing Payload value
-    #[error("Missing Payload value")]
+    /// 15 - A value needed by a rule is missing from the Payload
+    #[error("A value needed by a rule is missing from the Payload")]
     MissingPayloadValue,
 
     /// 16 - RuleSet owner must be payer
@@ -106,53 +110,176 @@ pub enum RuleSetError {
     #[error("RuleSet revision not available")]
     RuleSetRevisionNotAvailable,
 
-    /// 24 - Additional Signer check failed
-    #[error("Additional Signer check failed")]
+    /// 24 - Additional signer check failed: the account did not sign
+    #[error("Additional signer check failed: the account did not sign")]
     AdditionalSignerCheckFailed,
 
-    /// 25 - Pubkey Match check failed
-    #[error("Pubkey Match check failed")]
+    /// 25 - Pubkey match check failed: the Pubkey is not the one in the rule
+    #[error("Pubkey match check failed: the Pubkey is not the one in the rule")]
     PubkeyMatchCheckFailed,
 
-    /// 26 - Pubkey List Match check failed
-    #[error("Pubkey List Match check failed")]
+    /// 26 - Pubkey list match check failed: the Pubkey is not on the list
+    #[error("Pubkey list match check failed: the Pubkey is not on the list")]
     PubkeyListMatchCheckFailed,
 
-    /// 27 - Pubkey Tree Match check failed
-    #[error("Pubkey Tree Match check failed")]
+    /// 27 - Pubkey tree match check failed: the Pubkey is not in the Merkle tree
+    #[error("Pubkey tree match check failed: the Pubkey is not in the Merkle tree")]
     PubkeyTreeMatchCheckFailed,
 
-    /// 28 - PDA Match check failed
-    #[error("PDA Match check failed")]
+    /// 28 - PDA match check failed: the account is not derived from the seeds
+    #[error("PDA match check failed: the account is not derived from the seeds")]
     PDAMatchCheckFailed,
 
-    /// 29 - Program Owned check failed
-    #[error("Program Owned check failed")]
+    /// 29 - Program owned check failed: the target account is not owned by the expected program
+    #[error("Program owned check failed: the target account is not owned by the expected program")]
     ProgramOwnedCheckFailed,
 
-    /// 30 - Program Owned List check failed
-    #[error("Program Owned List check failed")]
+    /// 30 - Program owned list check failed: the account is not owned by a listed program
+    #[error("Program owned list check failed: the account is not owned by a listed program")]
     ProgramOwnedListCheckFailed,
 
-    /// 31 - Program Owned Tree check failed
-    #[error("Program Owned Tree check failed")]
+    /// 31 - Program owned tree check failed: the target account owner is not in the Merkle tree
+    #[error("Program owned tree check failed: the target account owner is not in the Merkle tree")]
     ProgramOwnedTreeCheckFailed,
 
-    /// 32 - Amount checked failed
-    #[error("Amount checked failed")]
+    /// 32 - Amount check failed: the amount is outside the allowed range
+    #[error("Amount check failed: the amount is outside the allowed range")]
     AmountCheckFailed,
 
-    /// 33 - Frequency check failed
-    #[error("Frequency check failed")]
+    /// 33 - Frequency check failed: the rule was used again before its period passed
+    #[error("Frequency check failed: the rule was used again before its period passed")]
     FrequencyCheckFailed,
 
-    /// 34 - IsWallet check failed
-    #[error("IsWallet check failed")]
+    /// 34 - IsWallet check failed: the account is not a wallet
+    #[error("IsWallet check failed: the account is not a wallet")]
     IsWalletCheckFailed,
 
-    /// 35 - Program Owned Set check failed
-    #[error("Program Owned Set check failed")]
+    /// 35 - Program owned set check failed: the account is not owned by a program in the set
+    #[error("Program owned set check failed: the account is not owned by a program in the set")]
     ProgramOwnedSetCheckFailed,
+
+    /// 36 - Any rule must contain at least one rule
+    #[error("Any rule must contain at least one rule")]
+    EmptyAnyRule,
+
+    /// 37 - Not check failed: the negated rule passed
+    #[error("Not check failed: the negated rule passed")]
+    NotCheckFailed,
+
+    /// 38 - Pubkey list exceeds the maximum length
+    #[error("Pubkey list exceeds the maximum length")]
+    PubkeyListTooLong,
+
+    /// 39 - RuleSet owner is not a signer
+    #[error("RuleSet owner is not a signer")]
+    OwnerIsNotSigner,
+
+    /// 40 - Failed to serialize the RuleSet to JSON
+    #[error("Failed to serialize the RuleSet to JSON")]
+    JsonSerializationError,
+
+    /// 41 - Failed to deserialize the RuleSet from JSON
+    #[error("Failed to deserialize the RuleSet from JSON")]
+    JsonDeserializationError,
+
+    /// 42 - RuleSet is frozen
+    #[error("RuleSet is frozen")]
+    RuleSetIsFrozen,
+
+    /// 43 - New RuleSet owner cannot be the default pubkey
+    #[error("New RuleSet owner cannot be the default pubkey")]
+    InvalidNewOwner,
+
+    /// 44 - Timestamp check failed: the current time is outside the allowed window
+    #[error("Timestamp check failed: the current time is outside the allowed window")]
+    TimestampCheckFailed,
+
+    /// 45 - Slot check failed: the current slot is outside the allowed range
+    #[error("Slot check failed: the current slot is outside the allowed range")]
+    SlotCheckFailed,
+
+    /// 46 - Name contains control characters
+    #[error("Name contains control characters")]
+    InvalidName,
+
+    /// 47 - RuleSet account is uninitialized
+    #[error("RuleSet account is uninitialized")]
+    Uninitialized,
+
+    /// 48 - Merkle proof exceeds the maximum depth
+    #[error("Merkle proof exceeds the maximum depth")]
+    ProofTooLong,
+
+    /// 49 - RuleSet exceeds the maximum number of operations or serialized size
+    #[error("RuleSet exceeds the maximum number of operations or serialized size")]
+    RuleSetTooLarge,
+
+    /// 50 - Rule tree exceeds the maximum nesting depth
+    #[error("Rule tree exceeds the maximum nesting depth")]
+    RuleTreeTooDeep,
+
+    /// 51 - Last update check failed: the account was last updated too long ago
+    #[error("Last update check failed: the account was last updated too long ago")]
+    LastUpdateCheckFailed,
+
+    /// 52 - Additional signer list check failed: too few listed signers signed
+    #[error("Additional signer list check failed: too few listed signers signed")]
+    AdditionalSignerListCheckFailed,
+
+    /// 53 - Signer threshold must be between one and the number of signers
+    #[error("Signer threshold must be between one and the number of signers")]
+    InvalidSignerThreshold,
+
+    /// 54 - Data match check failed: the account data does not match the Payload bytes
+    #[error("Data match check failed: the account data does not match the Payload bytes")]
+    DataMatchCheckFailed,
+
+    /// 55 - RuleSet name is empty
+    #[error("RuleSet name is empty")]
+    EmptyName,
+
+    /// 56 - RuleSet owner is missing
+    #[error("RuleSet owner is missing")]
+    MissingOwner,
+
+    /// 57 - RuleSet account is already initialized
+    #[error("RuleSet account is already initialized")]
+    AlreadyInitialized,
+
+    /// 58 - Pubkey list deny check failed: the Pubkey is on the deny list
+    #[error("Pubkey list deny check failed: the Pubkey is on the deny list")]
+    PubkeyListDenyCheckFailed,
+
+    /// 59 - Payload exceeds the maximum number of entries or value size
+    #[error("Payload exceeds the maximum number of entries or value size")]
+    PayloadTooLarge,
+
+    /// 60 - PubkeyTreeMatchAny holds more than the maximum number of roots
+    #[error("PubkeyTreeMatchAny holds more than the maximum number of roots")]
+    TooManyTreeRoots,
+
+    /// 61 - Instruction data has an unknown instruction or argument version
+    #[error("Instruction data has an unknown instruction or argument version")]
+    InvalidInstructionData,
+
+    /// 62 - RuleSet must be migrated to the current header first
+    #[error("RuleSet must be migrated to the current header first")]
+    RuleSetMigrationRequired,
+
+    /// 63 - Payload already holds a value for this key
+    #[error("Payload already holds a value for this key")]
+    DuplicatePayloadKey,
+
+    /// 64 - RuleSet account holds a revision with an unknown lib version
+    #[error("RuleSet account holds a revision with an unknown lib version")]
+    InvalidVersion,
+}
+
+impl RuleSetError {
+    /// The stable custom program error code for this error.
+    pub fn code(&self) -> u32 {
+        self.clone() as u32
+    }
 }
 
 impl PrintProgramError for RuleSetError {
@@ -163,7 +290,74 @@ impl PrintProgramError for RuleSetError {
 
 impl From<RuleSetError> for ProgramError {
     fn from(e: RuleSetError) -> Self {
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
+    }
+}
+
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+/// Why a `Rule` failed validation.  Off-chain callers get the full detail from `Rule::validate`
+/// and `RuleSetV1::validate`, while on-chain it is flattened to a `ProgramError` at the
+/// instruction boundary so the program's error codes are unchanged.
+pub enum RuleError {
+    /// A value the rule reads from the `Payload` was not present.
+    #[error("Missing payload value {field}")]
+    MissingPayloadValue {
+        /// The payload field that was looked up.
+        field: String,
+    },
+
+    /// An account the rule checks was not provided.
+    #[error("Missing account {key}")]
+    MissingAccount {
+        /// The account that was looked up.
+        key: Pubkey,
+    },
+
+    /// An account was not owned by the program the rule requires.
+    #[error("Account {key} is owned by {actual}, expected {expected}")]
+    ProgramOwnerMismatch {
+        /// The account that was checked.
+        key: Pubkey,
+        /// The program the rule requires to own the account.
+        expected: Pubkey,
+        /// The program that actually owns the account.
+        actual: Pubkey,
+    },
+
+    /// Any other failure, carrying the error returned on-chain.
+    #[error("{0}")]
+    Other(ProgramError),
+}
+
+impl RuleError {
+    /// Create a `RuleError::MissingPayloadValue` for the given payload field.
+    pub fn missing_payload_value(field: impl Into<String>) -> Self {
+        RuleError::MissingPayloadValue {
+            field: field.into(),
+        }
+    }
+}
+
+impl From<ProgramError> for RuleError {
+    fn from(e: ProgramError) -> Self {
+        RuleError::Other(e)
+    }
+}
+
+impl From<RuleSetError> for RuleError {
+    fn from(e: RuleSetError) -> Self {
+        RuleError::Other(e.into())
+    }
+}
+
+impl From<RuleError> for ProgramError {
+    fn from(e: RuleError) -> Self {
+        match e {
+            RuleError::MissingPayloadValue { .. } => RuleSetError::MissingPayloadValue.into(),
+            RuleError::MissingAccount { .. } => RuleSetError::MissingAccount.into(),
+            RuleError::ProgramOwnerMismatch { .. } => RuleSetError::ProgramOwnedCheckFailed.into(),
+            RuleError::Other(e) => e,
+        }
     }
 }
 
@@ -1,10 +1,13 @@
-use crate::payload::Payload;
+use crate::{error::RuleSetError, payload::Payload, state::RuleSetV1};
 use borsh::{BorshDeserialize, BorshSerialize};
 use mpl_token_metadata_context_derive::AccountContext;
+use rmp_serde::Serializer;
+use serde::Serialize;
 use shank::ShankInstruction;
 use solana_program::{
     account_info::AccountInfo,
     instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
 };
 
 #[repr(C)]
@@ -18,6 +21,31 @@ pub enum CreateOrUpdateArgs {
     },
 }
 
+impl CreateOrUpdateArgs {
+    /// Create V1 `create` instruction args by serializing a `RuleSetV1` into the MessagePack
+    /// format.
+    pub fn from_rule_set(rule_set: &RuleSetV1) -> Result<Self, RuleSetError> {
+        let mut serialized_rule_set = Vec::new();
+        rule_set
+            .serialize(&mut Serializer::new(&mut serialized_rule_set))
+            .map_err(|_| RuleSetError::MessagePackSerializationError)?;
+
+        Ok(Self::V1 {
+            serialized_rule_set,
+        })
+    }
+
+    /// Get the length in bytes of the pre-serialized `RuleSet`.  This can be used to check whether
+    /// the `RuleSet` fits in a single transaction or must first be written to a buffer.
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            Self::V1 {
+                serialized_rule_set,
+            } => serialized_rule_set.len(),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
 /// Args for `validate` instruction.
@@ -33,6 +61,50 @@ pub enum ValidateArgs {
         /// Optional revision of the `RuleSet` to use.  If `None`, the latest revision is used.
         rule_set_revision: Option<usize>,
     },
+    /// V2 implementation of the `validate` instruction arguments, adding a simulate mode.
+    V2 {
+        /// `Operation` to validate.
+        operation: String,
+        /// `Payload` data used for rule validation.
+        payload: Payload,
+        /// Update any relevant state stored in Rule, such as the Frequency `last_update` time value.
+        /// Ignored when `simulate` is true.
+        update_rule_state: bool,
+        /// Optional revision of the `RuleSet` to use.  If `None`, the latest revision is used.
+        rule_set_revision: Option<usize>,
+        /// Run every rule in the tree and log whether each one passed or failed, then succeed
+        /// regardless of the result.  No rule state is updated.  Intended for tooling that needs a
+        /// full diagnostic of why an operation would fail.
+        simulate: bool,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `validate_batch` instruction.
+pub enum ValidateBatchArgs {
+    /// V1 implementation of the `validate_batch` instruction arguments.
+    V1 {
+        /// `Operation`s to validate, each with the `Payload` data used for its rule validation.
+        operations: Vec<(String, Payload)>,
+        /// Update any relevant state stored in Rule, such as the Frequency `last_update` time value.
+        update_rule_state: bool,
+        /// Optional revision of the `RuleSet` to use.  If `None`, the latest revision is used.
+        rule_set_revision: Option<usize>,
+    },
+    /// V2 implementation of the `validate_batch` instruction arguments, adding a simulate mode.
+    V2 {
+        /// `Operation`s to validate, each with the `Payload` data used for its rule validation.
+        operations: Vec<(String, Payload)>,
+        /// Update any relevant state stored in Rule, such as the Frequency `last_update` time value.
+        /// Ignored when `simulate` is true.
+        update_rule_state: bool,
+        /// Optional revision of the `RuleSet` to use.  If `None`, the latest revision is used.
+        rule_set_revision: Option<usize>,
+        /// Validate every operation, logging each rule result and the result of each operation,
+        /// then succeed regardless of the results.  No rule state is updated.
+        simulate: bool,
+    },
 }
 
 #[repr(C)]
@@ -59,11 +131,93 @@ pub enum PuffRuleSetArgs {
     },
 }
 
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `delete` instruction.
+pub enum DeleteArgs {
+    /// V1 implementation of the `delete` instruction arguments.
+    V1 {
+        /// RuleSet name.
+        rule_set_name: String,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `freeze` instruction.
+pub enum FreezeArgs {
+    /// V1 implementation of the `freeze` instruction arguments.
+    V1 {
+        /// RuleSet name.
+        rule_set_name: String,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `thaw` instruction.
+pub enum ThawArgs {
+    /// V1 implementation of the `thaw` instruction arguments.
+    V1 {
+        /// RuleSet name.
+        rule_set_name: String,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `set_owner` instruction.
+pub enum SetOwnerArgs {
+    /// V1 implementation of the `set_owner` instruction arguments.
+    V1 {
+        /// The new owner of the RuleSet.
+        new_owner: Pubkey,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `migrate` instruction.
+pub enum MigrateArgs {
+    /// V1 implementation of the `migrate` instruction arguments.
+    V1 {
+        /// RuleSet name.
+        rule_set_name: String,
+    },
+}
+
+/// The maximum length of the serialized `RuleSet` passed to the `validate_inline` instruction.
+pub const MAX_INLINE_RULE_SET_LEN: usize = 1024;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Args for `validate_inline` instruction.
+pub enum ValidateInlineArgs {
+    /// V1 implementation of the `validate_inline` instruction arguments.
+    V1 {
+        /// RuleSet pre-serialized by caller into the MessagePack format, at most
+        /// `MAX_INLINE_RULE_SET_LEN` bytes long.
+        serialized_rule_set: Vec<u8>,
+        /// `Operation` to validate.
+        operation: String,
+        /// `Payload` data used for rule validation.
+        payload: Payload,
+    },
+}
+
 #[derive(Debug, Clone, ShankInstruction, AccountContext, BorshSerialize, BorshDeserialize)]
 #[rustfmt::skip]
 /// Instructions available in this program.
+///
+/// Instruction data is the Borsh serialized instruction: a one byte instruction tag followed by
+/// the instruction's arguments.  Every arguments type is an enum with one variant per version,
+/// so the arguments start with a one byte version tag.  Instruction data with an unknown
+/// instruction or version tag, or with bytes left over after the arguments, is rejected with
+/// `RuleSetError::InvalidInstructionData` rather than being read with the wrong layout.
 pub enum RuleSetInstruction {
     /// This instruction stores a caller-pre-serialized `RuleSet` into the rule_set PDA account.
+    /// If the rule_set PDA account already exists, the `RuleSet` is appended as a new revision,
+    /// which is how an existing `RuleSet` is updated.  The payer must be the `RuleSet` owner.
     #[account(0, signer, writable, name="payer", desc="Payer and creator of the RuleSet")]
     #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
     #[account(2, name = "system_program", desc = "System program")]
@@ -100,6 +254,73 @@ pub enum RuleSetInstruction {
     #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
     #[account(2, name = "system_program", desc = "System program")]
     PuffRuleSet(PuffRuleSetArgs),
+
+    /// Close a rule set account, zeroing its data and sending its lamports to the refund
+    /// destination.  Only the owner of the `RuleSet` can delete it.
+    #[account(0, signer, name="owner", desc="Owner of the RuleSet")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    #[account(2, writable, name="refund_destination", desc = "The account receiving the RuleSet PDA lamports")]
+    Delete(DeleteArgs),
+
+    /// This instruction validates multiple operations against the RuleSet stored in the rule_set
+    /// PDA account, as if `Validate` was called once per operation.  It takes the same accounts as
+    /// `Validate` and fails on the first operation that does not validate.
+    #[account(0, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    #[account(1, name="mint", desc="Mint of token asset")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, optional, signer, writable, name="payer", desc="Payer for RuleSet state PDA account")]
+    #[account(4, optional, signer, name="rule_authority", desc="Signing authority for any Rule state updates")]
+    #[account(5, optional, writable, name="rule_set_state_pda", desc = "The PDA account where any RuleSet state is stored")]
+    #[args(additional_rule_accounts: Vec<AccountMeta>)]
+    #[default_optional_accounts]
+    ValidateBatch(ValidateBatchArgs),
+
+    /// Freeze a rule set account so that it can no longer be updated or deleted.  Only the owner
+    /// of the `RuleSet` can freeze it.
+    #[account(0, signer, name="owner", desc="Owner of the RuleSet")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    Freeze(FreezeArgs),
+
+    /// Thaw a frozen rule set account so that it can be updated or deleted again.  Only the owner
+    /// of the `RuleSet` can thaw it.
+    #[account(0, signer, name="owner", desc="Owner of the RuleSet")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    Thaw(ThawArgs),
+
+    /// Transfer ownership of a rule set account to a new owner.  Only the current owner of the
+    /// `RuleSet` can set a new owner.  The PDA and any rule set state PDAs are still derived from
+    /// the creator, so their addresses do not change.
+    #[account(0, signer, name="owner", desc="Current owner of the RuleSet")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    SetOwner(SetOwnerArgs),
+
+    /// Migrate a rule set account written with the legacy header, which holds only the key and
+    /// the revision map location, to the current header and stored bump.  Its revisions are kept.
+    /// Migrating an account that already has the current header does nothing.  Only the owner of
+    /// the `RuleSet` can migrate it.
+    #[account(0, signer, writable, name="owner", desc="Owner of the RuleSet, paying for any extra space")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    #[account(2, name = "system_program", desc = "System program")]
+    Migrate(MigrateArgs),
+
+    /// This instruction validates an operation against a `RuleSet` passed in the instruction data
+    /// instead of one stored in a rule_set PDA account, for one-off checks where no persistent
+    /// `RuleSet` is wanted.  Since there is no RuleSet state PDA, `Rule`s that use state, such as
+    /// Frequency, do not pass.
+    #[account(0, name = "system_program", desc = "System program")]
+    #[args(additional_rule_accounts: Vec<AccountMeta>)]
+    ValidateInline(ValidateInlineArgs),
+
+    /// This instruction stores a caller-pre-serialized `RuleSet` into a new rule_set PDA account,
+    /// the same as `CreateOrUpdate`, but fails if the rule_set PDA account already holds a
+    /// `RuleSet`.  Use it when reusing a name by mistake would otherwise update an existing
+    /// `RuleSet`.
+    #[account(0, signer, writable, name="payer", desc="Payer and creator of the RuleSet")]
+    #[account(1, writable, name="rule_set_pda", desc = "The PDA account where the RuleSet is stored")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, optional, name="buffer_pda", desc = "The buffer to copy a complete ruleset from")]
+    #[default_optional_accounts]
+    Create(CreateOrUpdateArgs),
 }
 
 /// Builds a `CreateOrUpdate` instruction.
@@ -127,9 +348,43 @@ impl InstructionBuilder for builders::CreateOrUpdate {
     }
 }
 
+/// Builds a `Create` instruction.
+impl InstructionBuilder for builders::Create {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new(self.rule_set_pda, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ];
+
+        if let Some(buffer_pda) = self.buffer_pda {
+            accounts.push(AccountMeta::new_readonly(buffer_pda, false));
+        } else {
+            accounts.push(AccountMeta::new_readonly(crate::ID, false));
+        }
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::Create(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
 /// Builds a `Validate` instruction.
 impl InstructionBuilder for builders::Validate {
     fn instruction(&self) -> solana_program::instruction::Instruction {
+        self.instruction_with_program_id(crate::ID)
+    }
+}
+
+impl builders::Validate {
+    /// Build the `Validate` instruction for the Rule Set program deployed at `program_id`, for
+    /// example a non-canonical deployment on a local validator or a devnet fork.  Only the
+    /// instruction's target changes; unused optional accounts are still passed as `crate::ID`.
+    pub fn instruction_with_program_id(&self, program_id: Pubkey) -> Instruction {
         let mut accounts = vec![
             AccountMeta::new_readonly(self.rule_set_pda, false),
             AccountMeta::new_readonly(self.mint, false),
@@ -160,7 +415,7 @@ impl InstructionBuilder for builders::Validate {
         accounts.extend(self.additional_rule_accounts.clone());
 
         Instruction {
-            program_id: crate::ID,
+            program_id,
             accounts,
             data: RuleSetInstruction::Validate(self.args.clone())
                 .try_to_vec()
@@ -207,6 +462,348 @@ impl InstructionBuilder for builders::PuffRuleSet {
     }
 }
 
+/// Builds a `Delete` instruction.
+impl InstructionBuilder for builders::Delete {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new(self.rule_set_pda, false),
+            AccountMeta::new(self.refund_destination, false),
+        ];
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::Delete(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `ValidateBatch` instruction.
+impl InstructionBuilder for builders::ValidateBatch {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.rule_set_pda, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ];
+
+        // Add optional account or `crate::ID`.
+        if let Some(payer) = self.payer {
+            accounts.push(AccountMeta::new(payer, true));
+        } else {
+            accounts.push(AccountMeta::new_readonly(crate::ID, false));
+        }
+
+        // Add optional account or `crate::ID`.
+        if let Some(rule_authority) = self.rule_authority {
+            accounts.push(AccountMeta::new_readonly(rule_authority, true));
+        } else {
+            accounts.push(AccountMeta::new_readonly(crate::ID, false));
+        }
+
+        // Add optional account or `crate::ID`.
+        if let Some(rule_set_state_pda) = self.rule_set_state_pda {
+            accounts.push(AccountMeta::new(rule_set_state_pda, false));
+        } else {
+            accounts.push(AccountMeta::new_readonly(crate::ID, false));
+        }
+
+        accounts.extend(self.additional_rule_accounts.clone());
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::ValidateBatch(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `Freeze` instruction.
+impl InstructionBuilder for builders::Freeze {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new(self.rule_set_pda, false),
+        ];
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::Freeze(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `Thaw` instruction.
+impl InstructionBuilder for builders::Thaw {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new(self.rule_set_pda, false),
+        ];
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::Thaw(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `SetOwner` instruction.
+impl InstructionBuilder for builders::SetOwner {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new(self.rule_set_pda, false),
+        ];
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::SetOwner(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `Migrate` instruction.
+impl InstructionBuilder for builders::Migrate {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let accounts = vec![
+            AccountMeta::new(self.owner, true),
+            AccountMeta::new(self.rule_set_pda, false),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ];
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::Migrate(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds a `ValidateInline` instruction.
+impl InstructionBuilder for builders::ValidateInline {
+    fn instruction(&self) -> solana_program::instruction::Instruction {
+        let mut accounts = vec![AccountMeta::new_readonly(
+            solana_program::system_program::id(),
+            false,
+        )];
+
+        accounts.extend(self.additional_rule_accounts.clone());
+
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: RuleSetInstruction::ValidateInline(self.args.clone())
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+}
+
+/// Create a `CreateOrUpdate` instruction storing `serialized_rule_set` in `rule_set_pda`.  The
+/// program creates the PDA if it does not exist yet and otherwise appends a new revision after
+/// checking that `payer` owns the `RuleSet`, so the same instruction can be sent on every
+/// deployment.  If `buffer_pda` is given, the `RuleSet` is read from the buffer instead and
+/// `serialized_rule_set` should be empty.
+pub fn create_or_update(
+    payer: Pubkey,
+    rule_set_pda: Pubkey,
+    serialized_rule_set: Vec<u8>,
+    buffer_pda: Option<Pubkey>,
+) -> Instruction {
+    let mut builder = builders::CreateOrUpdateBuilder::new();
+    builder.payer(payer).rule_set_pda(rule_set_pda);
+
+    if let Some(buffer_pda) = buffer_pda {
+        builder.buffer_pda(buffer_pda);
+    }
+
+    builder
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction()
+}
+
+/// Create a `Validate` instruction for `rule_set_pda` and `mint`, with no payer, rule authority
+/// or `RuleSet` state PDA.  `program_id` overrides the Rule Set program the instruction is sent
+/// to, and defaults to `crate::ID`.  Use `ValidateInstructionBuilder` to pass the optional
+/// accounts.
+pub fn validate(
+    rule_set_pda: Pubkey,
+    mint: Pubkey,
+    additional_rule_accounts: Vec<AccountMeta>,
+    args: ValidateArgs,
+    program_id: Option<Pubkey>,
+) -> Instruction {
+    builders::ValidateBuilder::new()
+        .rule_set_pda(rule_set_pda)
+        .mint(mint)
+        .additional_rule_accounts(additional_rule_accounts)
+        .build(args)
+        .unwrap()
+        .instruction_with_program_id(program_id.unwrap_or(crate::ID))
+}
+
+/// Builds a `Validate` instruction from the values it needs, without assembling `ValidateArgs`
+/// first.  The rule set, mint and operation must be set.  The optional accounts default to
+/// `None`, the payload to an empty `Payload`, and the Rule Set program to `crate::ID`.
+#[derive(Debug, Clone, Default)]
+pub struct ValidateInstructionBuilder {
+    rule_set: Option<Pubkey>,
+    mint: Option<Pubkey>,
+    operation: Option<String>,
+    payload: Payload,
+    additional_rule_accounts: Vec<AccountMeta>,
+    payer: Option<Pubkey>,
+    rule_authority: Option<Pubkey>,
+    rule_set_state_pda: Option<Pubkey>,
+    update_rule_state: bool,
+    rule_set_revision: Option<usize>,
+    auth_rules_program: Option<Pubkey>,
+}
+
+impl ValidateInstructionBuilder {
+    /// Create a builder with nothing set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `RuleSet` PDA to validate against.
+    pub fn rule_set(&mut self, rule_set: Pubkey) -> &mut Self {
+        self.rule_set = Some(rule_set);
+        self
+    }
+
+    /// Set the mint of the token being operated on.
+    pub fn mint(&mut self, mint: Pubkey) -> &mut Self {
+        self.mint = Some(mint);
+        self
+    }
+
+    /// Set the operation whose `Rule` is validated.
+    pub fn operation(&mut self, operation: String) -> &mut Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Set the `Payload` the `Rule` is validated with.
+    pub fn payload(&mut self, payload: Payload) -> &mut Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Set the accounts needed by the `Rule`, such as additional signers.
+    pub fn additional_rule_accounts(&mut self, accounts: Vec<AccountMeta>) -> &mut Self {
+        self.additional_rule_accounts = accounts;
+        self
+    }
+
+    /// Set the payer, needed when rule state is updated.
+    pub fn payer(&mut self, payer: Pubkey) -> &mut Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    /// Set the rule authority, needed by `Frequency` rules.
+    pub fn rule_authority(&mut self, rule_authority: Pubkey) -> &mut Self {
+        self.rule_authority = Some(rule_authority);
+        self
+    }
+
+    /// Set the `RuleSet` state PDA, needed by `Frequency` rules.
+    pub fn rule_set_state_pda(&mut self, rule_set_state_pda: Pubkey) -> &mut Self {
+        self.rule_set_state_pda = Some(rule_set_state_pda);
+        self
+    }
+
+    /// Set whether rule state is updated.  Defaults to `false`.
+    pub fn update_rule_state(&mut self, update_rule_state: bool) -> &mut Self {
+        self.update_rule_state = update_rule_state;
+        self
+    }
+
+    /// Set the `RuleSet` revision to validate against.  Defaults to the latest revision.
+    pub fn rule_set_revision(&mut self, rule_set_revision: usize) -> &mut Self {
+        self.rule_set_revision = Some(rule_set_revision);
+        self
+    }
+
+    /// Set the Rule Set program the instruction is sent to, for example a non-canonical
+    /// deployment.  Defaults to `crate::ID`.
+    pub fn auth_rules_program(&mut self, auth_rules_program: Pubkey) -> &mut Self {
+        self.auth_rules_program = Some(auth_rules_program);
+        self
+    }
+
+    /// Build the `Validate` instruction.  Fails if the rule set, mint or operation is not set.
+    pub fn build(&self) -> Result<Instruction, Box<dyn std::error::Error>> {
+        let mut builder = builders::ValidateBuilder::new();
+        builder
+            .rule_set_pda(self.rule_set.ok_or("rule_set is not set")?)
+            .mint(self.mint.ok_or("mint is not set")?)
+            .additional_rule_accounts(self.additional_rule_accounts.clone());
+
+        if let Some(payer) = self.payer {
+            builder.payer(payer);
+        }
+        if let Some(rule_authority) = self.rule_authority {
+            builder.rule_authority(rule_authority);
+        }
+        if let Some(rule_set_state_pda) = self.rule_set_state_pda {
+            builder.rule_set_state_pda(rule_set_state_pda);
+        }
+
+        let args = ValidateArgs::V1 {
+            operation: self.operation.clone().ok_or("operation is not set")?,
+            payload: self.payload.clone(),
+            update_rule_state: self.update_rule_state,
+            rule_set_revision: self.rule_set_revision,
+        };
+
+        Ok(builder
+            .build(args)?
+            .instruction_with_program_id(self.auth_rules_program.unwrap_or(crate::ID)))
+    }
+}
+
+/// Create a `ValidateInline` instruction validating `operation` and `payload` against the
+/// MessagePack-serialized `rule_set_bytes`.  `additional_accounts` are the accounts needed by the
+/// `Rule`s, the same as `additional_rule_accounts` for `Validate`.
+pub fn validate_inline(
+    rule_set_bytes: Vec<u8>,
+    operation: String,
+    payload: Payload,
+    additional_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    builders::ValidateInlineBuilder::new()
+        .additional_rule_accounts(additional_accounts)
+        .build(ValidateInlineArgs::V1 {
+            serialized_rule_set: rule_set_bytes,
+            operation,
+            payload,
+        })
+        .unwrap()
+        .instruction()
+}
+
 /// Account context holding the accounts used by various instructions.
 pub struct Context<'a, T> {
     /// The struct holding the named accounts used by an instruction.
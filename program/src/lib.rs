@@ -1,9 +1,13 @@
 #[deny(missing_docs)]
+pub mod cpi;
+#[deny(missing_docs)]
 pub mod entrypoint;
 #[deny(missing_docs)]
 pub mod error;
 pub mod instruction;
 #[deny(missing_docs)]
+pub mod merkle;
+#[deny(missing_docs)]
 pub mod payload;
 #[deny(missing_docs)]
 pub mod pda;
@@ -11,6 +15,9 @@ pub mod pda;
 pub mod processor;
 #[deny(missing_docs)]
 pub mod state;
+#[cfg(feature = "test-utils")]
+#[deny(missing_docs)]
+pub mod test_utils;
 #[deny(missing_docs)]
 pub mod utils;
 
@@ -19,4 +26,33 @@ pub use solana_program;
 /// Max name length for any of the names used in this crate.
 pub const MAX_NAME_LENGTH: usize = 32;
 
+// Operation names used by token-metadata when validating against a `RuleSet`.  An operation is a
+// namespace, optionally followed by `:` and a scenario.  A `RuleSet` must store its `Rule`s under
+// these names for token-metadata to find them.  They are not the generic names of
+// `state::Operation`, such as `SaleTransfer`, which token-metadata does not use.
+
+/// The namespace of all transfer operations.
+pub const TRANSFER: &str = "Transfer";
+
+/// The namespace of all update operations.
+pub const UPDATE: &str = "Update";
+
+/// The namespace of all delegate operations.
+pub const DELEGATE: &str = "Delegate";
+
+/// A transfer by the token owner.
+pub const OWNER_TRANSFER: &str = "Transfer:Owner";
+
+/// A transfer by a transfer delegate.
+pub const DELEGATE_TRANSFER: &str = "Transfer:TransferDelegate";
+
+/// A transfer by a sale delegate.
+pub const SALE_TRANSFER: &str = "Transfer:SaleDelegate";
+
+/// A transfer by a migration delegate.
+pub const MIGRATION_TRANSFER: &str = "Transfer:MigrationDelegate";
+
+/// A transfer between two wallets.
+pub const WALLET_TO_WALLET_TRANSFER: &str = "Transfer:WalletToWallet";
+
 solana_program::declare_id!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
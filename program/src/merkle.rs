@@ -0,0 +1,59 @@
+//! Merkle proof verification used by the `PubkeyTreeMatch` and `ProgramOwnedTree` rules.
+//!
+//! Off-chain code building trees for these rules must follow the same conventions:
+//! * Leaves are the raw 32 bytes of a `Pubkey`.  They are not hashed before being placed in the
+//!   tree.  `PubkeyTreeMatch` can instead be set to expect hashed leaves, where a leaf is
+//!   `keccak256(0x00 || pubkey)`.
+//! * A parent node is `keccak256(0x01 || a || b)`, where `a` and `b` are the two child nodes
+//!   sorted so that `a <= b` (byte-wise).  The `0x01` prefix separates node hashes from leaves.
+//! * Because pairs are sorted, a proof is only the list of sibling nodes from the leaf up to
+//!   (but not including) the root; no left/right position information is needed.
+use crate::payload::ProofInfo;
+use solana_program::{keccak, pubkey::Pubkey};
+
+/// Domain separation prefix used when hashing a `Pubkey` into a leaf.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// Domain separation prefix used when hashing two nodes into their parent.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Hash a `Pubkey` into a leaf node, for trees that store hashed leaves.
+pub fn hash_leaf(leaf: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[&[LEAF_PREFIX], leaf.as_ref()]).0
+}
+
+/// Hash two nodes into their parent node using sorted concatenation.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        keccak::hashv(&[&[NODE_PREFIX], a, b]).0
+    } else {
+        keccak::hashv(&[&[NODE_PREFIX], b, a]).0
+    }
+}
+
+/// Compute the Merkle root implied by a leaf and its proof.
+pub fn compute_merkle_root(leaf: &Pubkey, merkle_proof: &ProofInfo) -> [u8; 32] {
+    compute_merkle_root_from_node(leaf.to_bytes(), merkle_proof)
+}
+
+/// Compute the Merkle root implied by a node, such as a hashed leaf, and its proof.
+pub fn compute_merkle_root_from_node(node: [u8; 32], merkle_proof: &ProofInfo) -> [u8; 32] {
+    fold_proof(node, merkle_proof.proof())
+}
+
+/// Verify that a leaf node is a member of the tree with the given root.  The leaf is the node as
+/// stored in the tree: the raw `Pubkey` bytes, or the output of `hash_leaf` for hashed trees.
+pub fn verify(leaf: &[u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    fold_proof(*leaf, proof) == *root
+}
+
+/// Verify that a leaf is a member of the tree with the given root.
+pub fn verify_proof(leaf: &Pubkey, merkle_proof: &ProofInfo, root: &[u8; 32]) -> bool {
+    verify(&leaf.to_bytes(), merkle_proof.proof(), root)
+}
+
+fn fold_proof(node: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(node, |computed_hash, proof_element| {
+        hash_pair(&computed_hash, proof_element)
+    })
+}
@@ -2,8 +2,8 @@
 use crate::error::RuleSetError;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
-use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
-use std::collections::HashMap;
+use solana_program::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use std::collections::{hash_map::Entry, HashMap};
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -20,6 +20,10 @@ impl SeedsVec {
     }
 }
 
+/// The maximum number of hashes allowed in a `ProofInfo` proof.  This bounds the compute used to
+/// verify a proof, and is enough for a tree with 2^32 leaves.
+pub const MAX_PROOF_DEPTH: usize = 32;
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 /// A proof type used by the `PubkeyTreeMatch` rule.
@@ -33,20 +37,134 @@ impl ProofInfo {
     pub fn new(proof: Vec<[u8; 32]>) -> Self {
         Self { proof }
     }
+
+    /// Create a new `ProofInfo`, failing if the proof is longer than `MAX_PROOF_DEPTH`.
+    pub fn try_new(proof: Vec<[u8; 32]>) -> Result<Self, RuleSetError> {
+        if proof.len() > MAX_PROOF_DEPTH {
+            return Err(RuleSetError::ProofTooLong);
+        }
+
+        Ok(Self { proof })
+    }
+
+    /// The proof hashes, ordered from the leaf up to the root.
+    pub fn proof(&self) -> &[[u8; 32]] {
+        &self.proof
+    }
 }
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
-/// Variants representing the different types represented in a payload.
+/// Variants representing the different types represented in a payload.  Each variant's
+/// serialized name is set explicitly, so renaming a variant does not change the serialized format.
 pub enum PayloadType {
     /// A plain `Pubkey`.
+    #[serde(rename = "Pubkey")]
     Pubkey(Pubkey),
     /// PDA derivation seeds.
+    #[serde(rename = "Seeds")]
     Seeds(SeedsVec),
     /// A merkle proof.
+    #[serde(rename = "MerkleProof")]
     MerkleProof(ProofInfo),
-    /// A plain `u64` used for `Amount`.
+    /// A plain `u64` used for `Amount`.  `Amount` is also accepted as its name when
+    /// deserializing, to match the `Rule`.
+    #[serde(rename = "Number", alias = "Amount")]
     Number(u64),
+    /// Arbitrary bytes, used for `DataMatch`.
+    #[serde(rename = "Bytes")]
+    Bytes(Vec<u8>),
+}
+
+impl PayloadType {
+    /// The maximum length of a `Bytes` value.
+    pub const MAX_BYTES_LEN: usize = 1024;
+
+    /// Check that the value is within the size limit for its type: a `Seeds` value can hold at
+    /// most `MAX_SEEDS` seeds of at most `MAX_SEED_LEN` bytes, a `MerkleProof` at most
+    /// `MAX_PROOF_DEPTH` hashes and a `Bytes` value at most `MAX_BYTES_LEN` bytes.
+    pub fn check_size(&self) -> Result<(), RuleSetError> {
+        let within_limit = match self {
+            PayloadType::Pubkey(_) | PayloadType::Number(_) => true,
+            PayloadType::Seeds(seeds) => {
+                seeds.seeds.len() <= MAX_SEEDS
+                    && seeds.seeds.iter().all(|seed| seed.len() <= MAX_SEED_LEN)
+            }
+            PayloadType::MerkleProof(proof_info) => {
+                if proof_info.proof.len() > MAX_PROOF_DEPTH {
+                    return Err(RuleSetError::ProofTooLong);
+                }
+                true
+            }
+            PayloadType::Bytes(bytes) => bytes.len() <= Self::MAX_BYTES_LEN,
+        };
+
+        if within_limit {
+            Ok(())
+        } else {
+            Err(RuleSetError::PayloadTooLarge)
+        }
+    }
+}
+
+/// The keys token-metadata uses for the values in a `Payload`.  A `Payload` stores its keys as
+/// `String`s, so these are converted with `to_string`.
+#[repr(C)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum PayloadKey {
+    /// The amount being transferred.
+    Amount,
+    /// The authority of an operation, e.g. the delegate of token.
+    Authority,
+    /// Seeds for a PDA authority of the operation, e.g. when the authority is a PDA.
+    AuthoritySeeds,
+    /// Merkle proof for the source of the operation, e.g. when the authority is a member
+    /// of a Merkle tree.
+    AuthorityProof,
+    /// Delegate for an operation.
+    Delegate,
+    /// Seeds for a PDA delegate of the operation.
+    DelegateSeeds,
+    /// The destination of the operation, e.g. the recipient of a transfer.
+    Destination,
+    /// Seeds for a PDA destination of the operation, e.g. when the recipient is a PDA.
+    DestinationSeeds,
+    /// A token holder.
+    Holder,
+    /// The source of the operation, e.g. the owner initiating a transfer.
+    Source,
+    /// Seeds for a PDA source of the operation, e.g. when the source is a PDA.
+    SourceSeeds,
+    /// Merkle proof for the source of the operation, e.g. when the source is a member
+    /// of a Merkle tree.
+    SourceProof,
+    /// Merkle proof for the destination of the operation, e.g. when the distination
+    /// is a member of a Merkle tree.
+    DestinationProof,
+    /// The index of the Merkle tree root that a proof is for, when a rule holds several roots.
+    RootIndex,
+}
+
+impl ToString for PayloadKey {
+    fn to_string(&self) -> String {
+        match self {
+            PayloadKey::Amount => "Amount",
+            PayloadKey::Authority => "Authority",
+            PayloadKey::AuthoritySeeds => "AuthoritySeeds",
+            PayloadKey::AuthorityProof => "AuthorityProof",
+            PayloadKey::Delegate => "Delegate",
+            PayloadKey::DelegateSeeds => "DelegateSeeds",
+            PayloadKey::SourceProof => "SourceProof",
+            PayloadKey::Destination => "Destination",
+            PayloadKey::DestinationSeeds => "DestinationSeeds",
+            PayloadKey::DestinationProof => "DestinationProof",
+            PayloadKey::Holder => "Holder",
+            PayloadKey::Source => "Source",
+            PayloadKey::SourceSeeds => "SourceSeeds",
+            PayloadKey::RootIndex => "RootIndex",
+        }
+        .to_string()
+    }
 }
 
 #[repr(C)]
@@ -59,6 +177,9 @@ pub struct Payload {
 }
 
 impl Payload {
+    /// The maximum number of key-value pairs in a `Payload` passed to `validate`.
+    pub const MAX_ENTRIES: usize = 32;
+
     /// Create a new empty `Payload`.
     pub fn new() -> Self {
         Self {
@@ -67,7 +188,8 @@ impl Payload {
     }
 
     /// Create a `Payload` from an array of key-value pairs, specified as
-    /// `(PayloadKey, PayloadType)` tuples.
+    /// `(PayloadKey, PayloadType)` tuples.  If a key appears more than once, the last value is
+    /// kept.  Use `try_insert` to detect duplicate keys instead.
     pub fn from<const N: usize>(arr: [(String, PayloadType); N]) -> Self {
         Self {
             map: HashMap::from(arr),
@@ -84,13 +206,27 @@ impl Payload {
     }
 
     /// Tries to insert a key-value pair into a `Payload`.  If this key is already in the `Payload`
-    /// nothing is updated and an error is returned.
-    pub fn try_insert(&mut self, key: String, value: PayloadType) -> ProgramResult {
-        if self.map.get(&key).is_none() {
-            self.map.insert(key, value);
-            Ok(())
-        } else {
-            Err(RuleSetError::ValueOccupied.into())
+    /// nothing is updated and `RuleSetError::DuplicatePayloadKey` is returned.
+    pub fn try_insert(&mut self, key: PayloadKey, value: PayloadType) -> Result<(), RuleSetError> {
+        match self.map.entry(key.to_string()) {
+            Entry::Occupied(_) => Err(RuleSetError::DuplicatePayloadKey),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Fold the key-value pairs of `other` into the `Payload`, for example to add
+    /// operation-specific values to a base `Payload`.  When a key is in both, the value from
+    /// `other` replaces the existing one if `overwrite` is set, and is dropped otherwise.
+    pub fn merge(&mut self, other: Payload, overwrite: bool) {
+        for (key, value) in other.map {
+            if overwrite {
+                self.map.insert(key, value);
+            } else {
+                self.map.entry(key).or_insert(value);
+            }
         }
     }
 
@@ -99,6 +235,31 @@ impl Payload {
         self.map.get(key)
     }
 
+    /// Returns the number of key-value pairs in the `Payload`.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the `Payload` has no key-value pairs.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Check that the `Payload` holds at most `MAX_ENTRIES` key-value pairs and that each value is
+    /// within the size limit for its type.  See `PayloadType::check_size`.
+    pub fn check_size(&self) -> Result<(), RuleSetError> {
+        if self.map.len() > Self::MAX_ENTRIES {
+            return Err(RuleSetError::PayloadTooLarge);
+        }
+
+        self.map.values().try_for_each(PayloadType::check_size)
+    }
+
+    /// Iterate over the keys in the `Payload`, in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.map.keys()
+    }
+
     /// Get a reference to the `Pubkey` associated with a key, if and only if the `Payload` value
     /// is the `PayloadType::Pubkey` variant.  Returns `None` if the key is not present in the
     /// `Payload` or the value is a different `PayloadType` variant.
@@ -154,4 +315,18 @@ impl Payload {
             None
         }
     }
+
+    /// Get a reference to the bytes associated with a key, if and only if the `Payload` value is
+    /// the `PayloadType::Bytes` variant.  Returns `None` if the key is not present in the
+    /// `Payload` or the value is a different `PayloadType` variant.
+    pub fn get_bytes(&self, key: &String) -> Option<&[u8]> {
+        if let Some(val) = self.map.get(key) {
+            match val {
+                PayloadType::Bytes(bytes) => Some(bytes),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
 }
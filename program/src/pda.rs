@@ -1,5 +1,6 @@
 //! The helper functions for the PDA accounts.
-use solana_program::pubkey::Pubkey;
+use crate::{error::RuleSetError, utils::is_zeroed, MAX_NAME_LENGTH};
+use solana_program::pubkey::{Pubkey, PubkeyError};
 
 /// The string prefix for Rule Set PDA seeds.
 pub const PREFIX: &str = "rule_set";
@@ -19,6 +20,73 @@ pub fn find_rule_set_address(creator: Pubkey, rule_set_name: String) -> (Pubkey,
     )
 }
 
+/// Check that a Rule Set name can be used as a PDA seed: it must be at most `MAX_NAME_LENGTH`
+/// bytes and must not contain control characters such as NUL.
+pub fn validate_rule_set_name(rule_set_name: &str) -> Result<(), RuleSetError> {
+    if rule_set_name.len() > MAX_NAME_LENGTH {
+        return Err(RuleSetError::NameTooLong);
+    }
+
+    if rule_set_name.chars().any(char::is_control) {
+        return Err(RuleSetError::InvalidName);
+    }
+
+    Ok(())
+}
+
+/// Find the PDA for a Rule Set account, first checking the name with `validate_rule_set_name`.
+pub fn try_find_rule_set_address(
+    creator: Pubkey,
+    rule_set_name: String,
+) -> Result<(Pubkey, u8), RuleSetError> {
+    validate_rule_set_name(&rule_set_name)?;
+    Ok(find_rule_set_address(creator, rule_set_name))
+}
+
+/// Check whether the Rule Set account for `creator` and `rule_set_name` already holds a `RuleSet`,
+/// for example before sending `Create`.  `get_account_data` fetches an account's data using the
+/// caller's client, returning `None` if the account does not exist.  An account counts as
+/// existing in the same cases that `Create` fails with `RuleSetError::AlreadyInitialized`.
+pub fn rule_set_exists<F>(get_account_data: F, creator: Pubkey, rule_set_name: String) -> bool
+where
+    F: FnOnce(&Pubkey) -> Option<Vec<u8>>,
+{
+    let (rule_set_addr, _bump) = find_rule_set_address(creator, rule_set_name);
+
+    match get_account_data(&rule_set_addr) {
+        Some(data) => !data.is_empty() && !is_zeroed(&data),
+        None => false,
+    }
+}
+
+/// Find the PDAs for several Rule Set accounts with the same creator.  The results are in the
+/// same order as `rule_set_names`.
+pub fn find_rule_set_addresses(creator: Pubkey, rule_set_names: &[String]) -> Vec<(Pubkey, u8)> {
+    rule_set_names
+        .iter()
+        .map(|rule_set_name| find_rule_set_address(creator, rule_set_name.clone()))
+        .collect()
+}
+
+/// Create the PDA for a Rule Set account from a known bump, for example one returned earlier by
+/// `find_rule_set_address`.  This avoids the `find_program_address` search, which is expensive
+/// on-chain.
+pub fn create_rule_set_address(
+    creator: Pubkey,
+    rule_set_name: String,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            creator.as_ref(),
+            rule_set_name.as_bytes(),
+            &[bump],
+        ],
+        &crate::ID,
+    )
+}
+
 /// Find the PDA for a Rule Set State account.
 pub fn find_rule_set_state_address(
     creator: Pubkey,
@@ -36,6 +104,17 @@ pub fn find_rule_set_state_address(
     )
 }
 
+/// Find the PDA for the Frequency account used by the `Frequency` rule.  Frequency state is
+/// stored in the Rule Set State account, so this is the same address as
+/// `find_rule_set_state_address`.
+pub fn find_frequency_address(
+    creator: Pubkey,
+    rule_set_name: String,
+    mint: Pubkey,
+) -> (Pubkey, u8) {
+    find_rule_set_state_address(creator, rule_set_name, mint)
+}
+
 /// Find the PDA for the Rule Set buffer account.
 pub fn find_buffer_address(creator: Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[PREFIX.as_bytes(), creator.as_ref()], &crate::ID)
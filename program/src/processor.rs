@@ -1,22 +1,27 @@
 //! The processors for the Rule Set program instructions.   See state module for description of PDA memory layout.
-use std::collections::HashMap;
-
 use crate::{
     error::RuleSetError,
     instruction::{
-        Context, CreateOrUpdate, CreateOrUpdateArgs, PuffRuleSet, PuffRuleSetArgs,
-        RuleSetInstruction, Validate, ValidateArgs, WriteToBuffer, WriteToBufferArgs,
+        Context, CreateOrUpdate, CreateOrUpdateArgs, Delete, DeleteArgs, Freeze, FreezeArgs,
+        Migrate, MigrateArgs, PuffRuleSet, PuffRuleSetArgs, RuleSetInstruction, SetOwner,
+        SetOwnerArgs, Thaw, ThawArgs, Validate, ValidateArgs, ValidateBatchArgs, ValidateInline,
+        ValidateInlineArgs, WriteToBuffer, WriteToBufferArgs, MAX_INLINE_RULE_SET_LEN,
     },
-    pda::{PREFIX, STATE_PDA},
+    payload::Payload,
+    pda::{validate_rule_set_name, PREFIX, STATE_PDA},
     state::{
-        RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, CHUNK_SIZE, RULE_SET_LIB_VERSION,
-        RULE_SET_REV_MAP_VERSION, RULE_SET_SERIALIZED_HEADER_LEN,
+        from_msgpack_slice_strict, AccountInfosContext, FrequencyAccount, Rule, RuleSetHeader,
+        RuleSetRevisionMapV1, RuleSetV1, SolanaAccount, CHUNK_SIZE,
+        LEGACY_RULE_SET_SERIALIZED_HEADER_LEN, RULE_SET_BUMP_LOCATION,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_LIB_VERSION, RULE_SET_REV_MAP_VERSION,
+        RULE_SET_SERIALIZED_HEADER_LEN,
     },
     utils::{
-        assert_derivation, create_or_allocate_account_raw, get_existing_revision_map,
-        get_operation, is_zeroed, resize_or_reallocate_account_raw,
+        assert_current_layout, assert_derivation, assert_derivation_with_bump,
+        assert_rule_set_name, assert_rule_set_owner, create_or_allocate_account_raw, get_creator,
+        get_existing_revision_map, get_operation, get_operation_from_slice, get_stored_bump,
+        is_legacy_rule_set, is_zeroed, resize_or_reallocate_account_raw,
     },
-    MAX_NAME_LENGTH,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
@@ -37,7 +42,9 @@ impl Processor {
         accounts: &'a [AccountInfo<'a>],
         instruction_data: &[u8],
     ) -> ProgramResult {
-        let instruction = RuleSetInstruction::try_from_slice(instruction_data)?;
+        // Unknown instruction or argument version tags fail to deserialize.
+        let instruction = RuleSetInstruction::try_from_slice(instruction_data)
+            .map_err(|_| RuleSetError::InvalidInstructionData)?;
         match instruction {
             RuleSetInstruction::CreateOrUpdate(args) => {
                 msg!("Instruction: CreateOrUpdate");
@@ -55,6 +62,38 @@ impl Processor {
                 msg!("Instruction: PuffRuleSet");
                 puff_rule_set(program_id, accounts, args)
             }
+            RuleSetInstruction::Delete(args) => {
+                msg!("Instruction: Delete");
+                delete(program_id, accounts, args)
+            }
+            RuleSetInstruction::ValidateBatch(args) => {
+                msg!("Instruction: ValidateBatch");
+                validate_batch(program_id, accounts, args)
+            }
+            RuleSetInstruction::Freeze(args) => {
+                msg!("Instruction: Freeze");
+                freeze(program_id, accounts, args)
+            }
+            RuleSetInstruction::Thaw(args) => {
+                msg!("Instruction: Thaw");
+                thaw(program_id, accounts, args)
+            }
+            RuleSetInstruction::SetOwner(args) => {
+                msg!("Instruction: SetOwner");
+                set_owner(program_id, accounts, args)
+            }
+            RuleSetInstruction::Migrate(args) => {
+                msg!("Instruction: Migrate");
+                migrate(program_id, accounts, args)
+            }
+            RuleSetInstruction::ValidateInline(args) => {
+                msg!("Instruction: ValidateInline");
+                validate_inline(program_id, accounts, args)
+            }
+            RuleSetInstruction::Create(args) => {
+                msg!("Instruction: Create");
+                create(program_id, accounts, args)
+            }
         }
     }
 }
@@ -68,15 +107,31 @@ fn create_or_update<'a>(
     let context = CreateOrUpdate::to_context(accounts)?;
 
     match args {
-        CreateOrUpdateArgs::V1 { .. } => create_or_update_v1(program_id, context, args),
+        CreateOrUpdateArgs::V1 { .. } => create_or_update_v1(program_id, context, args, false),
+    }
+}
+
+// Function to match on `CreateOrUpdateArgs` version and call correct implementation, failing if
+// the `RuleSet` already exists.  `Create` takes the same accounts as `CreateOrUpdate`.
+fn create<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: CreateOrUpdateArgs,
+) -> ProgramResult {
+    let context = CreateOrUpdate::to_context(accounts)?;
+
+    match args {
+        CreateOrUpdateArgs::V1 { .. } => create_or_update_v1(program_id, context, args, true),
     }
 }
 
-/// V1 implementation of the `create` instruction.
+/// V1 implementation of the `create` instruction.  If `require_new` is set, an existing `RuleSet`
+/// is not updated and `RuleSetError::AlreadyInitialized` is returned instead.
 fn create_or_update_v1(
     program_id: &Pubkey,
     ctx: Context<CreateOrUpdate>,
     args: CreateOrUpdateArgs,
+    require_new: bool,
 ) -> ProgramResult {
     // Get the V1 arguments for the instruction.
     let CreateOrUpdateArgs::V1 {
@@ -87,56 +142,91 @@ fn create_or_update_v1(
         return Err(RuleSetError::PayerIsNotSigner.into());
     }
 
-    // Deserialize `RuleSet`.
+    // Deserialize `RuleSet`, rejecting any trailing bytes so that they are not stored with it.
     let rule_set = match ctx.accounts.buffer_pda_info {
-        Some(account_info) => rmp_serde::from_slice::<RuleSetV1>(&account_info.data.borrow())
-            .map_err(|_| RuleSetError::MessagePackDeserializationError)?,
-        None => rmp_serde::from_slice(&serialized_rule_set)
-            .map_err(|_| RuleSetError::MessagePackDeserializationError)?,
+        Some(account_info) => from_msgpack_slice_strict::<RuleSetV1>(&account_info.data.borrow())?,
+        None => from_msgpack_slice_strict(&serialized_rule_set)?,
     };
 
-    if rule_set.name().len() > MAX_NAME_LENGTH {
-        return Err(RuleSetError::NameTooLong.into());
-    }
+    validate_rule_set_name(rule_set.name())?;
 
     // Make sure we know how to work with this RuleSet.
     if rule_set.lib_version() != RULE_SET_LIB_VERSION {
         return Err(RuleSetError::UnsupportedRuleSetVersion.into());
     }
 
+    // Reject `Rule` trees that are too deep to validate.
+    rule_set
+        .operations
+        .values()
+        .try_for_each(|rule| rule.assert_depth())?;
+
     // The payer/signer must be the `RuleSet` owner.
     if ctx.accounts.payer_info.key != rule_set.owner() {
         return Err(RuleSetError::RuleSetOwnerMismatch.into());
     }
 
-    // Check `RuleSet` account info derivation.
-    let bump = assert_derivation(
-        program_id,
-        ctx.accounts.rule_set_pda_info.key,
-        &[
-            PREFIX.as_bytes(),
-            ctx.accounts.payer_info.key.as_ref(),
-            rule_set.name().as_bytes(),
-        ],
-    )?;
+    let is_new_rule_set = ctx.accounts.rule_set_pda_info.data_is_empty()
+        || is_zeroed(&ctx.accounts.rule_set_pda_info.data.borrow());
 
-    let rule_set_seeds = &[
-        PREFIX.as_ref(),
-        ctx.accounts.payer_info.key.as_ref(),
-        rule_set.name().as_ref(),
-        &[bump],
-    ];
+    if require_new && !is_new_rule_set {
+        return Err(RuleSetError::AlreadyInitialized.into());
+    }
+
+    // The creator is kept across updates, as the PDA is derived from it.
+    let (bump, creator) = if is_new_rule_set {
+        // Check `RuleSet` account info derivation.
+        let bump = assert_derivation(
+            program_id,
+            ctx.accounts.rule_set_pda_info.key,
+            &[
+                PREFIX.as_bytes(),
+                ctx.accounts.payer_info.key.as_ref(),
+                rule_set.name().as_bytes(),
+            ],
+        )?;
+        (Some(bump), *ctx.accounts.payer_info.key)
+    } else {
+        // The owner of an existing `RuleSet` may have changed since its PDA was derived, so
+        // check the payer against the owner stored in the header instead.
+        let header = assert_rule_set_owner(
+            program_id,
+            ctx.accounts.payer_info,
+            ctx.accounts.rule_set_pda_info,
+        )?;
+
+        // A frozen `RuleSet` cannot be updated.
+        if header.frozen {
+            return Err(RuleSetError::RuleSetIsFrozen.into());
+        }
+
+        assert_rule_set_name(ctx.accounts.rule_set_pda_info, rule_set.name())?;
+
+        // Move a `RuleSet` with the legacy header to the current layout before adding the new
+        // revision, as writing the current header would overwrite its first revision.
+        let (revision_map, _) = get_existing_revision_map(ctx.accounts.rule_set_pda_info)?;
+        if is_legacy_rule_set(&revision_map) {
+            migrate_legacy_layout(
+                program_id,
+                ctx.accounts.rule_set_pda_info,
+                ctx.accounts.payer_info,
+                ctx.accounts.system_program_info,
+                &header,
+                rule_set.name(),
+            )?;
+        }
+        (None, header.creator)
+    };
 
     // Get new or existing revision map.
-    let revision_map = if ctx.accounts.rule_set_pda_info.data_is_empty()
-        || is_zeroed(&ctx.accounts.rule_set_pda_info.data.borrow())
-    {
+    let revision_map = if is_new_rule_set {
         let mut revision_map = RuleSetRevisionMapV1::default();
 
-        // Initially set the latest revision location to a the value right after the header.
+        // Initially set the latest revision location to the value right after the header and
+        // the stored bump.
         revision_map
             .rule_set_revisions
-            .push(RULE_SET_SERIALIZED_HEADER_LEN);
+            .push(RULE_SET_FIRST_REVISION_LOCATION);
         revision_map
     } else {
         // Get existing revision map and its serialized length.
@@ -162,7 +252,7 @@ fn create_or_update_v1(
     };
 
     // Determine size needed for PDA: next revision location (which is:
-    // (RULE_SET_SERIALIZED_HEADER_LEN || existing latest revision map location)) +
+    // (RULE_SET_FIRST_REVISION_LOCATION || existing latest revision map location)) +
     // 2 bytes for version numbers + length of the serialized revision map +
     // length of user-pre-serialized `RuleSet`.
     let new_pda_data_len = revision_map
@@ -176,6 +266,13 @@ fn create_or_update_v1(
 
     // Create or allocate, resize or reallocate the `RuleSet` PDA.
     if ctx.accounts.rule_set_pda_info.data_is_empty() {
+        let rule_set_seeds = &[
+            PREFIX.as_ref(),
+            ctx.accounts.payer_info.key.as_ref(),
+            rule_set.name().as_ref(),
+            &[bump.ok_or(RuleSetError::DerivedKeyInvalid)?],
+        ];
+
         create_or_allocate_account_raw(
             *program_id,
             ctx.accounts.rule_set_pda_info,
@@ -199,6 +296,8 @@ fn create_or_update_v1(
         Some(account_info) => {
             write_data_to_pda(
                 ctx.accounts.rule_set_pda_info,
+                &creator,
+                ctx.accounts.payer_info.key,
                 *revision_map
                     .rule_set_revisions
                     .last()
@@ -210,6 +309,8 @@ fn create_or_update_v1(
         None => {
             write_data_to_pda(
                 ctx.accounts.rule_set_pda_info,
+                &creator,
+                ctx.accounts.payer_info.key,
                 *revision_map
                     .rule_set_revisions
                     .last()
@@ -220,6 +321,11 @@ fn create_or_update_v1(
         }
     };
 
+    // Store the bump of a new `RuleSet` so later instructions can skip searching for it.
+    if let Some(bump) = bump {
+        write_bump(ctx.accounts.rule_set_pda_info, bump)?;
+    }
+
     Ok(())
 }
 
@@ -233,42 +339,160 @@ fn validate<'a>(
 
     match args {
         ValidateArgs::V1 { .. } => validate_v1(program_id, context, args),
+        ValidateArgs::V2 { .. } => validate_v2(program_id, context, args),
     }
 }
 
 /// V1 implementation of the `validate` instruction.
 fn validate_v1(program_id: &Pubkey, ctx: Context<Validate>, args: ValidateArgs) -> ProgramResult {
     // Get the V1 arguments for the instruction.
-    let ValidateArgs::V1 {
-        operation,
-        payload,
+    let (operation, payload, update_rule_state, rule_set_revision) = match args {
+        ValidateArgs::V1 {
+            operation,
+            payload,
+            update_rule_state,
+            rule_set_revision,
+        } => (operation, payload, update_rule_state, rule_set_revision),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    validate_operations(
+        program_id,
+        &ctx,
+        &[(operation, payload)],
         update_rule_state,
         rule_set_revision,
-    } = args;
+        false,
+    )
+}
 
-    // If state is being updated for any `Rule`s, the payer must be present and must be a signer so
-    // that the `RuleSet` state PDA can be created or reallocated.
-    if update_rule_state {
-        if let Some(payer_info) = ctx.accounts.payer_info {
-            if !payer_info.is_signer {
-                return Err(RuleSetError::PayerIsNotSigner.into());
-            }
-        } else {
-            return Err(ProgramError::NotEnoughAccountKeys);
-        }
+/// V2 implementation of the `validate` instruction.
+fn validate_v2(program_id: &Pubkey, ctx: Context<Validate>, args: ValidateArgs) -> ProgramResult {
+    // Get the V2 arguments for the instruction.
+    let (operation, payload, update_rule_state, rule_set_revision, simulate) = match args {
+        ValidateArgs::V2 {
+            operation,
+            payload,
+            update_rule_state,
+            rule_set_revision,
+            simulate,
+        } => (
+            operation,
+            payload,
+            update_rule_state,
+            rule_set_revision,
+            simulate,
+        ),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    validate_operations(
+        program_id,
+        &ctx,
+        &[(operation, payload)],
+        update_rule_state && !simulate,
+        rule_set_revision,
+        simulate,
+    )
+}
+
+// Function to match on `ValidateBatchArgs` version and call correct implementation.
+fn validate_batch<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: ValidateBatchArgs,
+) -> ProgramResult {
+    // `ValidateBatch` uses the same accounts as `Validate`.
+    let context = Validate::to_context(accounts)?;
+
+    match args {
+        ValidateBatchArgs::V1 { .. } => validate_batch_v1(program_id, context, args),
+        ValidateBatchArgs::V2 { .. } => validate_batch_v2(program_id, context, args),
     }
+}
+
+/// V1 implementation of the `validate_batch` instruction.
+fn validate_batch_v1(
+    program_id: &Pubkey,
+    ctx: Context<Validate>,
+    args: ValidateBatchArgs,
+) -> ProgramResult {
+    // Get the V1 arguments for the instruction.
+    let (operations, update_rule_state, rule_set_revision) = match args {
+        ValidateBatchArgs::V1 {
+            operations,
+            update_rule_state,
+            rule_set_revision,
+        } => (operations, update_rule_state, rule_set_revision),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    validate_operations(
+        program_id,
+        &ctx,
+        &operations,
+        update_rule_state,
+        rule_set_revision,
+        false,
+    )
+}
+
+/// V2 implementation of the `validate_batch` instruction.
+fn validate_batch_v2(
+    program_id: &Pubkey,
+    ctx: Context<Validate>,
+    args: ValidateBatchArgs,
+) -> ProgramResult {
+    // Get the V2 arguments for the instruction.
+    let (operations, update_rule_state, rule_set_revision, simulate) = match args {
+        ValidateBatchArgs::V2 {
+            operations,
+            update_rule_state,
+            rule_set_revision,
+            simulate,
+        } => (operations, update_rule_state, rule_set_revision, simulate),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
 
-    // `RuleSet` must be owned by this program.
-    if *ctx.accounts.rule_set_pda_info.owner != crate::ID {
-        return Err(RuleSetError::IncorrectOwner.into());
+    validate_operations(
+        program_id,
+        &ctx,
+        &operations,
+        update_rule_state && !simulate,
+        rule_set_revision,
+        simulate,
+    )
+}
+
+/// Validate each operation and payload pair against the same `RuleSet` revision, which is only
+/// looked up and checked once.  Fails on the first operation that does not validate, unless
+/// `simulate` is true, in which case every rule's result is logged and validation succeeds.  On
+/// failure or when simulating, the result of each operation validated is logged as a list, for
+/// example `[true, false, true]`.
+fn validate_operations(
+    program_id: &Pubkey,
+    ctx: &Context<Validate>,
+    operations: &[(String, Payload)],
+    update_rule_state: bool,
+    rule_set_revision: Option<usize>,
+    simulate: bool,
+) -> ProgramResult {
+    // Bound the work done on each `Payload` before any `Rule` sees it.
+    for (_, payload) in operations {
+        payload.check_size()?;
     }
 
-    // `RuleSet` must not be empty.
-    if ctx.accounts.rule_set_pda_info.data_is_empty() {
-        return Err(RuleSetError::DataIsEmpty.into());
+    // An account that was never created, for example a `RuleSet` PDA for a name that has not
+    // been used yet, is owned by the system program and has no data.  Any account not owned by
+    // this program or without data cannot hold a `RuleSet` either.
+    if *ctx.accounts.rule_set_pda_info.owner != crate::ID
+        || ctx.accounts.rule_set_pda_info.data_is_empty()
+    {
+        return Err(RuleSetError::Uninitialized.into());
     }
 
-    // Get existing revision map and its serialized length.
+    // Get existing revision map and its serialized length.  This also checks the `Key` at the
+    // start of the account data, so any other account fails as `Uninitialized`.
     let (revision_map, rev_map_location) =
         get_existing_revision_map(ctx.accounts.rule_set_pda_info)?;
 
@@ -308,76 +532,185 @@ fn validate_v1(program_id: &Pubkey, ctx: Context<Validate>, args: ValidateArgs)
         .map_err(|_| ProgramError::AccountBorrowFailed)?;
 
     // Check `RuleSet` lib version.
-    let rule_set = match data.get(start) {
+    let (rule_set, serialized_rule_set) = match data.get(start) {
         Some(&RULE_SET_LIB_VERSION) => {
             // Increment starting location by size of lib version.
             let start = start
                 .checked_add(1)
                 .ok_or(RuleSetError::NumericalOverflow)?;
 
-            // Deserialize `RuleSet`.
+            // Deserialize `RuleSet`, skipping its operations.  Only the `Rule` for the
+            // requested operation is deserialized below.
             if end < ctx.accounts.rule_set_pda_info.data_len() {
-                rmp_serde::from_slice::<RuleSetV1>(&data[start..end])
-                    .map_err(|_| RuleSetError::MessagePackDeserializationError)?
+                (
+                    RuleSetV1::from_slice_without_operations(&data[start..end])?,
+                    &data[start..end],
+                )
             } else {
                 return Err(RuleSetError::DataTypeMismatch.into());
             }
         }
-        Some(_) => return Err(RuleSetError::UnsupportedRuleSetVersion.into()),
+        Some(_) => return Err(RuleSetError::InvalidVersion.into()),
         None => return Err(RuleSetError::DataTypeMismatch.into()),
     };
 
-    // Check `RuleSet` account info derivation.
-    let _bump = assert_derivation(
+    // The PDAs are derived from the creator, as the owner can change.
+    let creator = get_creator(&data, &revision_map, &rule_set)?;
+
+    // Check `RuleSet` account info derivation, using the stored bump when there is one.
+    let _bump = assert_derivation_with_bump(
         program_id,
         ctx.accounts.rule_set_pda_info.key,
         &[
             PREFIX.as_bytes(),
-            rule_set.owner().as_ref(),
+            creator.as_ref(),
             rule_set.name().as_bytes(),
         ],
+        get_stored_bump(&data, &revision_map),
     )?;
 
+    // Get the `Rule` for each user-specified operation from the `RuleSet`.  A `Namespace` is
+    // already resolved to its fallback `Rule` here.
+    let rules = operations
+        .iter()
+        .map(|(operation, _)| get_operation_from_slice(operation.clone(), serialized_rule_set))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A `Pass` always succeeds, so when every operation has one, skip validation and never touch
+    // the `Rule` accounts or the `RuleSet` state PDA.
+    if !simulate && rules.iter().all(|rule| *rule == Rule::Pass) {
+        return Ok(());
+    }
+
+    // If state is being updated for any `Rule`s, the payer must be present and must be a signer so
+    // that the `RuleSet` state PDA can be created or reallocated.
+    if update_rule_state {
+        if let Some(payer_info) = ctx.accounts.payer_info {
+            if !payer_info.is_signer {
+                return Err(RuleSetError::PayerIsNotSigner.into());
+            }
+        } else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+    }
+
     // If `RuleSet` state is to be updated, check account info derivation.
     if update_rule_state {
         if let Some(rule_set_state_pda_info) = ctx.accounts.rule_set_state_pda_info {
-            let _bump = assert_derivation(
+            let bump = assert_derivation(
                 program_id,
                 rule_set_state_pda_info.key,
                 &[
                     STATE_PDA.as_bytes(),
-                    rule_set.owner().as_ref(),
+                    creator.as_ref(),
                     rule_set.name().as_bytes(),
                     ctx.accounts.mint_info.key.as_ref(),
                 ],
             )?;
+
+            // Create the `RuleSet` state PDA on first use.
+            if rule_set_state_pda_info.data_is_empty() {
+                // Payer presence was checked above.
+                let payer_info = ctx
+                    .accounts
+                    .payer_info
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+                let frequency_account = FrequencyAccount::new(0, 0);
+                let serialized_frequency_account = frequency_account.try_to_vec()?;
+
+                create_or_allocate_account_raw(
+                    *program_id,
+                    rule_set_state_pda_info,
+                    ctx.accounts.system_program_info,
+                    payer_info,
+                    serialized_frequency_account.len(),
+                    &[
+                        STATE_PDA.as_bytes(),
+                        creator.as_ref(),
+                        rule_set.name().as_bytes(),
+                        ctx.accounts.mint_info.key.as_ref(),
+                        &[bump],
+                    ],
+                )?;
+
+                frequency_account.to_account_data(rule_set_state_pda_info)?;
+            }
         } else {
             return Err(ProgramError::NotEnoughAccountKeys);
         }
     }
 
-    // Convert remaining `Rule` accounts into a map of `Pubkey`s to the corresponding
-    // `AccountInfo`s.  This makes it easy to pass the account infos into validation functions
-    // since they store the `Pubkey`s.
-    let accounts_map = ctx
-        .remaining_accounts
-        .iter()
-        .map(|account| (*account.key, *account))
-        .collect::<HashMap<Pubkey, &AccountInfo>>();
+    // Wrap the remaining `Rule` accounts in the on-chain `ValidationContext` passed into
+    // validation functions.  It looks accounts up by their `Pubkey` or by their index.
+    let accounts_map = AccountInfosContext::new(&ctx.remaining_accounts);
 
-    // Get the `Rule` from the `RuleSet` based on the user-specified operation.
-    let rule = get_operation(operation, &rule_set)?;
+    // `Frequency` rules grow the `RuleSet` state PDA when they add a slot to it.
+    let state_len = ctx
+        .accounts
+        .rule_set_state_pda_info
+        .map(|rule_set_state_pda_info| rule_set_state_pda_info.data_len());
 
-    // Validate the `Rule`.
-    if let Err(err) = rule.validate(
-        &accounts_map,
-        &payload,
-        update_rule_state,
-        &ctx.accounts.rule_set_state_pda_info,
-        &ctx.accounts.rule_authority_info,
-    ) {
-        msg!("Failed to validate: {}", err);
-        return Err(err);
+    // The result of each operation validated so far.
+    let mut results = Vec::with_capacity(operations.len());
+
+    for (index, (rule, (_, payload))) in rules.iter().zip(operations).enumerate() {
+        // A `Pass` always succeeds, so skip validation and never touch the `Rule` accounts.
+        if !simulate && *rule == Rule::Pass {
+            results.push(true);
+            continue;
+        }
+
+        // Log every `Rule` result without failing.
+        if simulate {
+            results.push(rule.simulate(
+                &accounts_map,
+                payload,
+                &ctx.accounts.rule_set_state_pda_info,
+                &ctx.accounts.rule_authority_info,
+            ));
+            continue;
+        }
+
+        // Validate the `Rule`.
+        if let Err(err) = rule.validate(
+            &accounts_map,
+            payload,
+            update_rule_state,
+            &ctx.accounts.rule_set_state_pda_info,
+            &ctx.accounts.rule_authority_info,
+        ) {
+            results.push(false);
+            msg!("Failed to validate operation {}: {}", index, err);
+            msg!("Operation results: {:?}", results);
+            return Err(err.into());
+        }
+        results.push(true);
+    }
+
+    // Only log when simulating, so that passing validation costs no extra compute.
+    if simulate {
+        msg!("Operation results: {:?}", results);
+    }
+
+    // Keep the `RuleSet` state PDA rent exempt after it grew.
+    if let (Some(rule_set_state_pda_info), Some(state_len)) =
+        (ctx.accounts.rule_set_state_pda_info, state_len)
+    {
+        if rule_set_state_pda_info.data_len() > state_len {
+            // Payer presence was checked above, as only updating state grows the account.
+            let payer_info = ctx
+                .accounts
+                .payer_info
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            resize_or_reallocate_account_raw(
+                rule_set_state_pda_info,
+                payer_info,
+                ctx.accounts.system_program_info,
+                rule_set_state_pda_info.data_len(),
+            )?;
+        }
     }
 
     Ok(())
@@ -543,6 +876,298 @@ fn puff_rule_set_v1(
     }
 }
 
+// Function to match on `DeleteArgs` version and call correct implementation.
+fn delete<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: DeleteArgs,
+) -> ProgramResult {
+    let context = Delete::to_context(accounts)?;
+
+    match args {
+        DeleteArgs::V1 { .. } => delete_v1(program_id, context, args),
+    }
+}
+
+/// V1 implementation of the `delete` instruction.
+fn delete_v1(program_id: &Pubkey, ctx: Context<Delete>, args: DeleteArgs) -> ProgramResult {
+    let DeleteArgs::V1 { rule_set_name } = args;
+
+    // Only the current owner can delete a `RuleSet`.
+    let header = assert_rule_set_owner(
+        program_id,
+        ctx.accounts.owner_info,
+        ctx.accounts.rule_set_pda_info,
+    )?;
+    assert_rule_set_name(ctx.accounts.rule_set_pda_info, &rule_set_name)?;
+
+    // A frozen `RuleSet` cannot be deleted.
+    if header.frozen {
+        return Err(RuleSetError::RuleSetIsFrozen.into());
+    }
+
+    // Zero the `RuleSet` data.
+    ctx.accounts
+        .rule_set_pda_info
+        .try_borrow_mut_data()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?
+        .fill(0);
+
+    // Move all the lamports to the refund destination, which closes the account.
+    let rule_set_lamports = ctx.accounts.rule_set_pda_info.lamports();
+    let refund_destination_lamports = ctx
+        .accounts
+        .refund_destination_info
+        .lamports()
+        .checked_add(rule_set_lamports)
+        .ok_or(RuleSetError::NumericalOverflow)?;
+
+    **ctx
+        .accounts
+        .refund_destination_info
+        .try_borrow_mut_lamports()? = refund_destination_lamports;
+    **ctx.accounts.rule_set_pda_info.try_borrow_mut_lamports()? = 0;
+
+    Ok(())
+}
+
+// Function to match on `FreezeArgs` version and call correct implementation.
+fn freeze<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: FreezeArgs,
+) -> ProgramResult {
+    let context = Freeze::to_context(accounts)?;
+
+    match args {
+        FreezeArgs::V1 { rule_set_name } => set_frozen(
+            program_id,
+            context.accounts.owner_info,
+            context.accounts.rule_set_pda_info,
+            &rule_set_name,
+            true,
+        ),
+    }
+}
+
+// Function to match on `ThawArgs` version and call correct implementation.
+fn thaw<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], args: ThawArgs) -> ProgramResult {
+    let context = Thaw::to_context(accounts)?;
+
+    match args {
+        ThawArgs::V1 { rule_set_name } => set_frozen(
+            program_id,
+            context.accounts.owner_info,
+            context.accounts.rule_set_pda_info,
+            &rule_set_name,
+            false,
+        ),
+    }
+}
+
+// Set the `frozen` flag in the `RuleSet` PDA header.  Shared by `freeze` and `thaw`.
+fn set_frozen(
+    program_id: &Pubkey,
+    owner_info: &AccountInfo,
+    rule_set_pda_info: &AccountInfo,
+    rule_set_name: &str,
+    frozen: bool,
+) -> ProgramResult {
+    // Only the current owner can freeze or thaw a `RuleSet`.
+    let mut header = assert_rule_set_owner(program_id, owner_info, rule_set_pda_info)?;
+    assert_rule_set_name(rule_set_pda_info, rule_set_name)?;
+    assert_current_layout(rule_set_pda_info)?;
+
+    header.frozen = frozen;
+    write_header(rule_set_pda_info, &header)
+}
+
+// Function to match on `SetOwnerArgs` version and call correct implementation.
+fn set_owner<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: SetOwnerArgs,
+) -> ProgramResult {
+    let context = SetOwner::to_context(accounts)?;
+
+    match args {
+        SetOwnerArgs::V1 { .. } => set_owner_v1(program_id, context, args),
+    }
+}
+
+/// V1 implementation of the `set_owner` instruction.
+fn set_owner_v1(program_id: &Pubkey, ctx: Context<SetOwner>, args: SetOwnerArgs) -> ProgramResult {
+    let SetOwnerArgs::V1 { new_owner } = args;
+
+    if new_owner == Pubkey::default() {
+        return Err(RuleSetError::InvalidNewOwner.into());
+    }
+
+    // Only the current owner can set a new owner.
+    let mut header = assert_rule_set_owner(
+        program_id,
+        ctx.accounts.owner_info,
+        ctx.accounts.rule_set_pda_info,
+    )?;
+    assert_current_layout(ctx.accounts.rule_set_pda_info)?;
+
+    header.owner = new_owner;
+    write_header(ctx.accounts.rule_set_pda_info, &header)
+}
+
+// Function to match on `MigrateArgs` version and call correct implementation.
+fn migrate<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: MigrateArgs,
+) -> ProgramResult {
+    let context = Migrate::to_context(accounts)?;
+
+    match args {
+        MigrateArgs::V1 { .. } => migrate_v1(program_id, context, args),
+    }
+}
+
+/// V1 implementation of the `migrate` instruction.
+fn migrate_v1(program_id: &Pubkey, ctx: Context<Migrate>, args: MigrateArgs) -> ProgramResult {
+    let MigrateArgs::V1 { rule_set_name } = args;
+
+    // Only the owner can migrate a `RuleSet`.  This also checks that the account holds one.
+    let header = assert_rule_set_owner(
+        program_id,
+        ctx.accounts.owner_info,
+        ctx.accounts.rule_set_pda_info,
+    )?;
+    assert_rule_set_name(ctx.accounts.rule_set_pda_info, &rule_set_name)?;
+
+    // An account that already has the current header needs no changes, so that migrating it
+    // again succeeds.
+    let (revision_map, _) = get_existing_revision_map(ctx.accounts.rule_set_pda_info)?;
+    if !is_legacy_rule_set(&revision_map) {
+        return Ok(());
+    }
+
+    migrate_legacy_layout(
+        program_id,
+        ctx.accounts.rule_set_pda_info,
+        ctx.accounts.owner_info,
+        ctx.accounts.system_program_info,
+        &header,
+        &rule_set_name,
+    )
+}
+
+// Move the revisions and the revision map of a `RuleSet` PDA with the legacy header to make room
+// for the current header and the stored bump, then write both.  Legacy `RuleSet`s could not
+// change owner, so the owner in the given header is also the creator the PDA is derived from.
+fn migrate_legacy_layout<'a>(
+    program_id: &Pubkey,
+    rule_set_pda_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    header: &RuleSetHeader,
+    rule_set_name: &str,
+) -> ProgramResult {
+    // Check `RuleSet` account info derivation.
+    let bump = assert_derivation(
+        program_id,
+        rule_set_pda_info.key,
+        &[
+            PREFIX.as_bytes(),
+            header.owner.as_ref(),
+            rule_set_name.as_bytes(),
+        ],
+    )?;
+
+    let (revision_map, rev_map_version_location) = get_existing_revision_map(rule_set_pda_info)?;
+
+    // Every revision and the revision map move by the same number of bytes.
+    let shift = RULE_SET_FIRST_REVISION_LOCATION - LEGACY_RULE_SET_SERIALIZED_HEADER_LEN;
+    let rule_set_revisions = revision_map
+        .rule_set_revisions
+        .iter()
+        .map(|location| location.checked_add(shift))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(RuleSetError::NumericalOverflow)?;
+    let new_rev_map_version_location = rev_map_version_location
+        .checked_add(shift)
+        .ok_or(RuleSetError::NumericalOverflow)?;
+
+    // Borsh serialize the moved revision map.
+    let mut serialized_rev_map = Vec::new();
+    RuleSetRevisionMapV1 { rule_set_revisions }
+        .serialize(&mut serialized_rev_map)
+        .map_err(|_| RuleSetError::BorshSerializationError)?;
+
+    // Determine size needed for PDA: moved revision map version location + 1 byte for the
+    // revision map version + length of the serialized revision map.
+    let rev_map_start = new_rev_map_version_location
+        .checked_add(1)
+        .ok_or(RuleSetError::NumericalOverflow)?;
+    let new_pda_data_len = rev_map_start
+        .checked_add(serialized_rev_map.len())
+        .ok_or(RuleSetError::NumericalOverflow)?;
+
+    resize_or_reallocate_account_raw(
+        rule_set_pda_info,
+        payer_info,
+        system_program_info,
+        new_pda_data_len,
+    )?;
+
+    {
+        let mut data = rule_set_pda_info
+            .try_borrow_mut_data()
+            .map_err(|_| ProgramError::AccountBorrowFailed)?;
+
+        // Move the revisions, each with its lib version, after the stored bump, then write the
+        // revision map after them.
+        data.copy_within(
+            LEGACY_RULE_SET_SERIALIZED_HEADER_LEN..rev_map_version_location,
+            RULE_SET_FIRST_REVISION_LOCATION,
+        );
+        data[new_rev_map_version_location] = RULE_SET_REV_MAP_VERSION;
+        data[rev_map_start..new_pda_data_len].copy_from_slice(&serialized_rev_map);
+    }
+
+    let header = RuleSetHeader::new(new_rev_map_version_location, header.owner);
+    write_header(rule_set_pda_info, &header)?;
+    write_bump(rule_set_pda_info, bump)
+}
+
+// Store the `RuleSet` PDA bump in the byte after the header.
+fn write_bump(rule_set_pda_info: &AccountInfo, bump: u8) -> ProgramResult {
+    let mut data = rule_set_pda_info
+        .try_borrow_mut_data()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?;
+
+    match data.get_mut(RULE_SET_BUMP_LOCATION) {
+        Some(byte) => *byte = bump,
+        None => return Err(RuleSetError::DataSliceUnexpectedIndexError.into()),
+    }
+
+    Ok(())
+}
+
+// Borsh serialize a header and copy it to the start of the `RuleSet` PDA.
+fn write_header(rule_set_pda_info: &AccountInfo, header: &RuleSetHeader) -> ProgramResult {
+    let mut serialized_header = Vec::new();
+    header
+        .serialize(&mut serialized_header)
+        .map_err(|_| RuleSetError::BorshSerializationError)?;
+
+    let mut data = rule_set_pda_info
+        .try_borrow_mut_data()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?;
+    sol_memcpy(
+        &mut data[..RULE_SET_SERIALIZED_HEADER_LEN],
+        &serialized_header,
+        serialized_header.len(),
+    );
+
+    Ok(())
+}
+
 /// Convenience function for accessing the next item in an [`AccountInfo`]
 /// iterator and validating whether the account is present or not.
 ///
@@ -566,10 +1191,62 @@ pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
     sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
 }
 
+// Function to match on `ValidateInlineArgs` version and call correct implementation.
+fn validate_inline<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: ValidateInlineArgs,
+) -> ProgramResult {
+    let context = ValidateInline::to_context(accounts)?;
+
+    match args {
+        ValidateInlineArgs::V1 { .. } => validate_inline_v1(program_id, context, args),
+    }
+}
+
+/// V1 implementation of the `validate_inline` instruction.
+fn validate_inline_v1(
+    _program_id: &Pubkey,
+    ctx: Context<ValidateInline>,
+    args: ValidateInlineArgs,
+) -> ProgramResult {
+    let ValidateInlineArgs::V1 {
+        serialized_rule_set,
+        operation,
+        payload,
+    } = args;
+
+    // Bound the work done deserializing a `RuleSet` that was not checked when it was created.
+    if serialized_rule_set.len() > MAX_INLINE_RULE_SET_LEN {
+        return Err(RuleSetError::RuleSetTooLarge.into());
+    }
+
+    // Likewise bound the work done on the `Payload`.
+    payload.check_size()?;
+
+    // Deserialize `RuleSet`, rejecting any trailing bytes as it was never checked by `create`.
+    let rule_set = from_msgpack_slice_strict::<RuleSetV1>(&serialized_rule_set)?;
+
+    // Make sure we know how to work with this RuleSet.
+    if rule_set.lib_version() != RULE_SET_LIB_VERSION {
+        return Err(RuleSetError::UnsupportedRuleSetVersion.into());
+    }
+
+    // Wrap the remaining `Rule` accounts in the on-chain `ValidationContext`.
+    let accounts_map = AccountInfosContext::new(&ctx.remaining_accounts);
+
+    // Get the `Rule` from the `RuleSet` based on the user-specified operation and validate it.
+    let rule = get_operation(operation, &rule_set)?;
+    rule.validate(&accounts_map, &payload, false, &None, &None)
+        .map_err(Into::into)
+}
+
 // Write the `RuleSet` lib version, a serialized `RuleSet`, the revision map version,
 // a revision map, and a header to the `RuleSet` PDA.
 fn write_data_to_pda(
     rule_set_pda_info: &AccountInfo,
+    creator: &Pubkey,
+    owner: &Pubkey,
     starting_location: usize,
     serialized_rev_map: &[u8],
     serialized_rule_set: &[u8],
@@ -617,8 +1294,12 @@ fn write_data_to_pda(
         return Err(RuleSetError::DataSliceUnexpectedIndexError.into());
     }
 
-    // Create a new header holding the location of the revision map version.
-    let header = RuleSetHeader::new(start);
+    // Create a new header holding the location of the revision map version, the owner and the
+    // creator.
+    let header = RuleSetHeader {
+        creator: *creator,
+        ..RuleSetHeader::new(start, *owner)
+    };
 
     // Borsh serialize the header.
     let mut serialized_header = Vec::new();
@@ -3,15 +3,28 @@ use shank::ShankAccount;
 
 use super::{Key, SolanaAccount};
 
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, ShankAccount)]
+#[derive(BorshSerialize, PartialEq, Eq, Debug, Clone, ShankAccount)]
 /// An account containing frequency state.
 pub struct FrequencyAccount {
     /// The `Key` for this account which identifies it as a Frequency account.
     pub key: Key,
-    /// The last time the frequency counter was updated.
+    /// The last time the frequency counter was updated, for `Frequency` rules without a name.
     pub last_update: i64,
-    /// The period which must transpire before the rule will succeed again.
+    /// The period which must transpire before the rule will succeed again, for `Frequency` rules
+    /// without a name.
     pub period: i64,
+    /// The last update of each named `Frequency` rule, so that rules guarding different
+    /// operations are limited independently.
+    pub slots: Vec<FrequencySlot>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// The state of one named `Frequency` rule in a `FrequencyAccount`.
+pub struct FrequencySlot {
+    /// The name of the `Frequency` rule, such as the operation it guards.
+    pub name: String,
+    /// The last time the rule passed with `update_rule_state` set.
+    pub last_update: i64,
 }
 
 impl FrequencyAccount {
@@ -21,10 +34,64 @@ impl FrequencyAccount {
             key: Key::Frequency,
             last_update,
             period,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Get the last update of the `Frequency` rule named `name`.  A rule that never updated its
+    /// slot reads as last updated at time zero.
+    pub fn last_update_of(&self, name: &str) -> i64 {
+        if name.is_empty() {
+            return self.last_update;
+        }
+
+        self.slots
+            .iter()
+            .find(|slot| slot.name == name)
+            .map_or(0, |slot| slot.last_update)
+    }
+
+    /// Store the last update and period of the `Frequency` rule named `name`, adding a slot for
+    /// it if it has none.  Only rules without a name store their period.
+    pub fn update(&mut self, name: &str, last_update: i64, period: i64) {
+        if name.is_empty() {
+            self.last_update = last_update;
+            self.period = period;
+            return;
+        }
+
+        match self.slots.iter_mut().find(|slot| slot.name == name) {
+            Some(slot) => slot.last_update = last_update,
+            None => self.slots.push(FrequencySlot {
+                name: name.to_string(),
+                last_update,
+            }),
         }
     }
 }
 
+// Accounts created before named slots were added end after `period`, so missing slots are read
+// as empty.
+impl BorshDeserialize for FrequencyAccount {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let key = Key::deserialize(buf)?;
+        let last_update = i64::deserialize(buf)?;
+        let period = i64::deserialize(buf)?;
+        let slots = if buf.is_empty() {
+            Vec::new()
+        } else {
+            Vec::<FrequencySlot>::deserialize(buf)?
+        };
+
+        Ok(Self {
+            key,
+            last_update,
+            period,
+            slots,
+        })
+    }
+}
+
 impl SolanaAccount for FrequencyAccount {
     fn key() -> Key {
         Key::Frequency
@@ -17,36 +17,196 @@
 //! This is not included in the data struct itself to give flexibility to update `RuleSet`s and
 //! the revision map data structs and even change serialization format.
 //!
+//! The PDA bump is stored right after the header so that the PDA derivation can be checked
+//! without searching for the bump.  `RuleSet`s created before the bump was stored have no bump
+//! byte, and their first revision starts right after the header.
+//!
+//! `RuleSet`s written before the header held the frozen flag and the owner have the 9 byte
+//! `LegacyRuleSetHeader` instead, with their first revision right after it.  They can still be
+//! validated and deleted, and their next update or the `Migrate` instruction moves them to the
+//! current layout.
+//!
 //! RuleSet PDA data layout
 //! ```text
-//! | Header  | RuleSet version | RuleSet Revision 0 | RuleSet version | RuleSet Revision 1 | RuleSet version | RuleSet Revision 2 | ... | RuleSetRevisionMap version | RuleSetRevisionMap |
-//! |---------|-----------------|--------------------|-----------------|--------------------|-----------------|--------------------|-----|----------------------------|--------------------|
-//! | 9 bytes | 1 byte          | variable bytes     | 1 byte          | variable bytes     | 1 byte          | variable bytes     | ... | 1 byte                     | variable bytes     |
+//! | Header   | Bump   | RuleSet version | RuleSet Revision 0 | RuleSet version | RuleSet Revision 1 | RuleSet version | RuleSet Revision 2 | ... | RuleSetRevisionMap version | RuleSetRevisionMap |
+//! |----------|--------|-----------------|--------------------|-----------------|--------------------|-----------------|--------------------|-----|----------------------------|--------------------|
+//! | 74 bytes | 1 byte | 1 byte          | variable bytes     | 1 byte          | variable bytes     | 1 byte          | variable bytes     | ... | 1 byte                     | variable bytes     |
 //! ```
 use borsh::{BorshDeserialize, BorshSerialize};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
+use serde::{
+    de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serializer,
+};
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    io::Cursor,
+    marker::PhantomData,
 };
 
 mod frequency;
+mod operation;
 mod rule_set;
 mod rules;
+mod validation_context;
 
 pub use frequency::*;
+pub use operation::*;
 pub use rule_set::*;
 pub use rules::*;
+pub use validation_context::*;
 
 use crate::{error::RuleSetError, utils::assert_owned_by};
 
 /// The maximum size that can be allocated at one time for a PDA.
 pub const CHUNK_SIZE: usize = 10_000;
 
+/// The maximum number of `Rule`s a `Rule::All` or `Rule::Any` can hold when deserialized.
+pub const MAX_DESERIALIZED_RULES: usize = 1024;
+
+/// The maximum number of `Pubkey`s a list in a `Rule` can hold when deserialized.
+pub const MAX_DESERIALIZED_PUBKEYS: usize = 1024;
+
+// The message of the serde error raised when a collection is longer than its limit, used to tell
+// it apart from other malformed data.
+const LENGTH_LIMIT_EXCEEDED: &str = "collection exceeds its maximum length";
+
+/// Deserialize a MessagePack-serialized value, such as a `RuleSetV1`, from untrusted bytes.  The
+/// operations map and the `Rule` and `Pubkey` lists are checked against their maximum lengths
+/// before they are allocated, so a length prefix claiming a huge collection fails with
+/// `RuleSetError::DataTypeMismatch` instead of exhausting the heap.  Other malformed data fails
+/// with `RuleSetError::MessagePackDeserializationError`.
+pub fn from_msgpack_slice<'a, T: Deserialize<'a>>(data: &'a [u8]) -> Result<T, RuleSetError> {
+    rmp_serde::from_slice(data).map_err(msgpack_error)
+}
+
+/// Deserialize a MessagePack-serialized value as with `from_msgpack_slice`, but fail with
+/// `RuleSetError::DataTypeMismatch` if any bytes are left over after the value.  A padded or
+/// corrupted account can otherwise decode successfully while leaving garbage behind it.
+pub fn from_msgpack_slice_strict<T: DeserializeOwned>(data: &[u8]) -> Result<T, RuleSetError> {
+    let mut deserializer = rmp_serde::Deserializer::new(Cursor::new(data));
+    let value = T::deserialize(&mut deserializer).map_err(msgpack_error)?;
+
+    if deserializer.position() != data.len() as u64 {
+        return Err(RuleSetError::DataTypeMismatch);
+    }
+
+    Ok(value)
+}
+
+// Convert an error from deserializing MessagePack into a `RuleSetError`.
+pub(crate) fn msgpack_error(err: rmp_serde::decode::Error) -> RuleSetError {
+    match err {
+        rmp_serde::decode::Error::Syntax(msg) if msg == LENGTH_LIMIT_EXCEEDED => {
+            RuleSetError::DataTypeMismatch
+        }
+        _ => RuleSetError::MessagePackDeserializationError,
+    }
+}
+
+// Deserialize the operations map of a `RuleSetV1`, holding at most `RuleSetV1::MAX_OPERATIONS`.
+pub(crate) fn deserialize_operations<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Rule>, D::Error> {
+    deserializer.deserialize_map(BoundedMapVisitor::<{ RuleSetV1::MAX_OPERATIONS }>)
+}
+
+// Deserialize the `Rule`s contained in a `Rule::All` or `Rule::Any`.
+pub(crate) fn deserialize_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Rule>, D::Error> {
+    deserializer.deserialize_seq(BoundedVecVisitor::<Rule, MAX_DESERIALIZED_RULES>(
+        PhantomData,
+    ))
+}
+
+// Deserialize a list of `Pubkey`s contained in a `Rule`.
+pub(crate) fn deserialize_pubkeys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Pubkey>, D::Error> {
+    deserializer.deserialize_seq(BoundedVecVisitor::<Pubkey, MAX_DESERIALIZED_PUBKEYS>(
+        PhantomData,
+    ))
+}
+
+// Serialize a set of `Pubkey`s in sorted order, so that the serialized bytes do not depend on the
+// set's hash order.
+pub(crate) fn serialize_pubkey_set<S: Serializer>(
+    pubkeys: &HashSet<Pubkey>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut sorted: Vec<_> = pubkeys.iter().collect();
+    sorted.sort();
+    serializer.collect_seq(sorted)
+}
+
+// Deserializes a sequence of at most `MAX` elements.  The length prefix is checked up front, but
+// nothing is reserved for it, so the `Vec` only grows as elements are actually read.
+struct BoundedVecVisitor<T, const MAX: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const MAX: usize> Visitor<'de> for BoundedVecVisitor<T, MAX> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of at most {} elements", MAX)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        if seq.size_hint().map_or(false, |len| len > MAX) {
+            return Err(de::Error::custom(LENGTH_LIMIT_EXCEEDED));
+        }
+
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            if values.len() == MAX {
+                return Err(de::Error::custom(LENGTH_LIMIT_EXCEEDED));
+            }
+            values.push(value);
+        }
+
+        Ok(values)
+    }
+}
+
+// Deserializes a map of operations to `Rule`s holding at most `MAX` entries, checked the same way
+// as `BoundedVecVisitor`.
+struct BoundedMapVisitor<const MAX: usize>;
+
+impl<'de, const MAX: usize> Visitor<'de> for BoundedMapVisitor<MAX> {
+    type Value = BTreeMap<String, Rule>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a map of at most {} operations to rules", MAX)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        if map.size_hint().map_or(false, |len| len > MAX) {
+            return Err(de::Error::custom(LENGTH_LIMIT_EXCEEDED));
+        }
+
+        let mut operations = BTreeMap::new();
+        while let Some((operation, rule)) = map.next_entry()? {
+            operations.insert(operation, rule);
+            if operations.len() > MAX {
+                return Err(de::Error::custom(LENGTH_LIMIT_EXCEEDED));
+            }
+        }
+
+        Ok(operations)
+    }
+}
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, Copy, FromPrimitive)]
 /// The key at the beginning of the serialized account that identifies the account type.
-/// NOTE: This is not used for the `RuleSet` account, which uses msgpack instead of Borsh for SerDes.
+/// For the `RuleSet` account it is the first byte of the Borsh serialized `RuleSetHeader`, ahead
+/// of the msgpack serialized revisions.
 pub enum Key {
     /// An uninitialized account, which has all bytes set to zero by default.
     Uninitialized,
@@ -56,6 +216,15 @@ pub enum Key {
     Frequency,
 }
 
+impl Key {
+    /// Get the `Key` stored in the first byte of the account data, so that the account type can be
+    /// determined without deserializing the rest of the account.  Returns `None` for empty data or
+    /// an unknown key.
+    pub fn from_account_data(data: &[u8]) -> Option<Key> {
+        data.first().and_then(|key| Key::from_u8(*key))
+    }
+}
+
 /// A trait implementing generic functions required by all accounts on Solana.
 pub trait SolanaAccount: BorshSerialize + BorshDeserialize {
     /// Get the `Key` for this `Account`.  This key is to be stored in the first byte of the
@@ -84,7 +253,9 @@ pub trait SolanaAccount: BorshSerialize + BorshDeserialize {
     /// BorshSerialize the Rust data structure into the `Account` data.
     fn to_account_data(&self, account: &AccountInfo) -> ProgramResult {
         let mut data = account.try_borrow_mut_data()?;
-        self.serialize(&mut *data).map_err(Into::into)
+        // Serialize through a reborrowed slice so the writer does not advance the `AccountInfo`'s
+        // own data slice.
+        self.serialize(&mut &mut data[..]).map_err(Into::into)
     }
 }
 
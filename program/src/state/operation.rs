@@ -0,0 +1,45 @@
+use std::{convert::Infallible, fmt, str::FromStr};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// The canonical operation names that a `RuleSet` can store `Rule`s for.  Operations are stored
+/// as `String`s in the `RuleSet`, so `Other` allows any name not covered by the other variants.
+pub enum Operation {
+    /// Transfer of an asset.
+    Transfer,
+    /// Delegation of an asset.
+    Delegate,
+    /// Transfer of an asset as part of a sale.
+    SaleTransfer,
+    /// Any other operation name.
+    Other(String),
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Transfer => write!(f, "Transfer"),
+            Operation::Delegate => write!(f, "Delegate"),
+            Operation::SaleTransfer => write!(f, "SaleTransfer"),
+            Operation::Other(operation) => write!(f, "{}", operation),
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Transfer" => Operation::Transfer,
+            "Delegate" => Operation::Delegate,
+            "SaleTransfer" => Operation::SaleTransfer,
+            _ => Operation::Other(s.to_string()),
+        })
+    }
+}
+
+impl From<Operation> for String {
+    fn from(operation: Operation) -> Self {
+        operation.to_string()
+    }
+}
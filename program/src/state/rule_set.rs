@@ -1,14 +1,28 @@
 /// See state module for description of PDA memory layout.
 use crate::{
-    error::RuleSetError,
-    state::{Key, Rule},
+    error::{RuleError, RuleSetError},
+    payload::Payload,
+    pda::validate_rule_set_name,
+    state::{
+        deserialize_operations, from_msgpack_slice, from_msgpack_slice_strict, msgpack_error, Key,
+        Rule, RuleVisitor, ValidationContext, MAX_PUBKEY_LIST_LEN,
+    },
+    utils::get_operation,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use serde::{Deserialize, Serialize};
+use num_traits::FromPrimitive;
+use rmp_serde::Serializer;
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 #[cfg(feature = "serde-with-feature")]
 use serde_with::{As, DisplayFromStr};
-use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
-use std::collections::HashMap;
+use solana_program::{
+    entrypoint::ProgramResult, keccak, program_error::ProgramError, pubkey::Pubkey,
+    system_instruction::MAX_PERMITTED_DATA_LENGTH,
+};
+use std::{collections::BTreeMap, fmt, io::Write};
 
 /// Version of the `RuleSetRevisionMapV1` struct.
 pub const RULE_SET_REV_MAP_VERSION: u8 = 1;
@@ -26,20 +40,55 @@ pub struct RuleSetHeader {
     /// The location of revision map version stored in the PDA.  This is one byte before the
     /// revision map itself.
     pub rev_map_version_location: usize,
+    /// Whether the `RuleSet` is frozen.  A frozen `RuleSet` cannot be updated or deleted until
+    /// its owner thaws it.
+    pub frozen: bool,
+    /// The current owner of the `RuleSet`.  This starts as the creator, but can be changed with
+    /// the `SetOwner` instruction.
+    pub owner: Pubkey,
+    /// The creator of the `RuleSet`, used with its name to derive the PDA.  This never changes,
+    /// even when the `RuleSet` gets a new owner.
+    pub creator: Pubkey,
 }
 
 impl RuleSetHeader {
-    /// Create a new `RuleSetHeader`.
-    pub fn new(rev_map_version_location: usize) -> Self {
+    /// Create a new `RuleSetHeader` for a `RuleSet` still owned by its creator.
+    pub fn new(rev_map_version_location: usize, owner: Pubkey) -> Self {
         Self {
             key: Key::RuleSet,
             rev_map_version_location,
+            frozen: false,
+            owner,
+            creator: owner,
         }
     }
 }
 
 /// Size of `RuleSetHeader` when Borsh serialized.
-pub const RULE_SET_SERIALIZED_HEADER_LEN: usize = 9;
+pub const RULE_SET_SERIALIZED_HEADER_LEN: usize = 74;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+/// Header of `RuleSet` PDAs written before the header held the frozen flag and the owner.  Its
+/// fields are stored at the same place as the first fields of `RuleSetHeader`, so it can be read
+/// from either layout to find the revision map.
+pub struct LegacyRuleSetHeader {
+    /// The `Key` for this account which identifies it as a `RuleSet` account.
+    pub key: Key,
+    /// The location of revision map version stored in the PDA.
+    pub rev_map_version_location: usize,
+}
+
+/// Size of `LegacyRuleSetHeader` when Borsh serialized.  The first revision of a `RuleSet` with
+/// the legacy header starts right after it.
+pub const LEGACY_RULE_SET_SERIALIZED_HEADER_LEN: usize = 9;
+
+/// Location of the `RuleSet` PDA bump, stored in the byte after the header so that the PDA can
+/// be checked without searching for the bump.  `RuleSet`s created before the bump was stored
+/// have their first revision at this location instead.
+pub const RULE_SET_BUMP_LOCATION: usize = RULE_SET_SERIALIZED_HEADER_LEN;
+
+/// Location of the first `RuleSet` revision in PDAs that store their bump.
+pub const RULE_SET_FIRST_REVISION_LOCATION: usize = RULE_SET_BUMP_LOCATION + 1;
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, Default)]
 /// Revision map used to keep track of where individual `RuleSet` revisions are stored in the PDA.
@@ -49,6 +98,83 @@ pub struct RuleSetRevisionMapV1 {
     pub rule_set_revisions: Vec<usize>,
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// How `RuleSetV1::merge` resolves an operation that is in both `RuleSet`s.
+pub enum MergeStrategy {
+    /// Replace the existing `Rule` with the one from the other `RuleSet`.
+    Overwrite,
+    /// Keep the existing `Rule` and drop the one from the other `RuleSet`.
+    KeepExisting,
+    /// Fail the merge with `RuleSetError::ValueOccupied`.
+    Error,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+/// An operation whose `Rule` differs between two `RuleSet`s.
+pub struct ChangedOperation {
+    /// The operation.
+    pub operation: String,
+    /// The `Rule` in the `RuleSet` being compared against.
+    pub old: Rule,
+    /// The `Rule` in the other `RuleSet`.
+    pub new: Rule,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+/// The operation changes between two `RuleSet`s, as returned by `RuleSetV1::diff`.  Each list is
+/// sorted by operation.
+pub struct RuleSetDiff {
+    /// Operations only in the other `RuleSet`.
+    pub added: Vec<String>,
+    /// Operations only in the `RuleSet` being compared against.
+    pub removed: Vec<String>,
+    /// Operations in both `RuleSet`s with different `Rule`s.
+    pub changed: Vec<ChangedOperation>,
+}
+
+impl RuleSetDiff {
+    /// Whether the two `RuleSet`s have the same operations and `Rule`s.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+/// A kind of problem found by `RuleSetV1::check`.
+pub enum RuleSetWarningKind {
+    /// The `Rule` tree is nested deeper than `MAX_RULE_DEPTH`, so its `Rule`s were not checked.
+    TreeTooDeep,
+    /// An `All` or `Any` holds no `Rule`s.  An empty `All` always passes and an empty `Any` never
+    /// does.
+    EmptyRuleList,
+    /// A `Rule` that checks a list or set of `Pubkey`s has an empty one, so it can never pass, or
+    /// for `PubkeyListDeny`, never fail.
+    EmptyPubkeyList,
+    /// A `PubkeyListMatch` or `PubkeyListDeny` holds more than `MAX_PUBKEY_LIST_LEN` `Pubkey`s.
+    PubkeyListTooLong,
+    /// An `AdditionalSignerList` threshold is zero or more than the number of signers.
+    InvalidSignerThreshold,
+    /// A `Payload` field name is empty, or an `AmountSum` has no fields to sum.
+    EmptyField,
+    /// A `Timestamp` or `Slot` range ends before it starts, so it can never pass.
+    EmptyRange,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A problem found by `RuleSetV1::check` in one `Rule` of a `RuleSet`.
+pub struct RuleSetWarning {
+    /// The operation whose `Rule` tree holds the `Rule`.
+    pub operation: String,
+    /// The path to the `Rule` in the tree, in the same format as the failure path logged by
+    /// `Validate`, for example `All[1].Any[0].Amount`.
+    pub path: String,
+    /// The problem found.
+    pub kind: RuleSetWarningKind,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 /// The struct containing all Rule Set data, most importantly the map of operations to `Rules`.
@@ -63,21 +189,40 @@ pub struct RuleSetV1 {
     owner: Pubkey,
     /// Name of the RuleSet, used in PDA derivation.
     rule_set_name: String,
-    /// A map to determine the `Rule` that belongs to a given `Operation`.
-    pub operations: HashMap<String, Rule>,
+    /// A map to determine the `Rule` that belongs to a given `Operation`.  The map is ordered so
+    /// that the serialized `RuleSet` does not depend on the order operations were added in.
+    #[serde(deserialize_with = "deserialize_operations")]
+    pub operations: BTreeMap<String, Rule>,
 }
 
 impl RuleSetV1 {
+    /// The maximum number of operations a `RuleSet` can hold.
+    pub const MAX_OPERATIONS: usize = 256;
+
+    /// The maximum serialized size of a `RuleSet`, so that it fits in an account along with the
+    /// header and its version byte.
+    pub const MAX_SERIALIZED_SIZE: usize =
+        MAX_PERMITTED_DATA_LENGTH as usize - RULE_SET_SERIALIZED_HEADER_LEN - 1;
+
+    /// The reserved operation holding the default `Rule`, used by `Validate` for any operation
+    /// that has no entry of its own and no namespace entry to fall back to.
+    pub const DEFAULT_OPERATION: &'static str = "*";
+
     /// Create a new empty `RuleSet`.
     pub fn new(rule_set_name: String, owner: Pubkey) -> Self {
         Self {
             lib_version: RULE_SET_LIB_VERSION,
             rule_set_name,
             owner,
-            operations: HashMap::new(),
+            operations: BTreeMap::new(),
         }
     }
 
+    /// Create a `RuleSetBuilder`, for building a `RuleSet` with chained calls.
+    pub fn builder() -> RuleSetBuilder {
+        RuleSetBuilder::new()
+    }
+
     /// Get the name of the `RuleSet`.
     pub fn name(&self) -> &str {
         &self.rule_set_name
@@ -94,18 +239,710 @@ impl RuleSetV1 {
     }
 
     /// Add a key-value pair into a `RuleSet`.  If this key is already in the `RuleSet`
-    /// nothing is updated and an error is returned.
-    pub fn add(&mut self, operation: String, rules: Rule) -> ProgramResult {
-        if self.operations.get(&operation).is_none() {
-            self.operations.insert(operation, rules);
-            Ok(())
-        } else {
-            Err(RuleSetError::ValueOccupied.into())
+    /// nothing is updated and an error is returned.  An error is also returned if the `Rule`
+    /// is malformed, see `Rule::assert_valid`, or if the `RuleSet` would hold more than
+    /// `MAX_OPERATIONS` operations or have a serialized size over `MAX_SERIALIZED_SIZE`.  The
+    /// operation can be given as a `String` or as an `Operation`.
+    pub fn add(&mut self, operation: impl Into<String>, rules: Rule) -> ProgramResult {
+        rules.assert_valid()?;
+
+        let operation = operation.into();
+        if self.operations.get(&operation).is_some() {
+            return Err(RuleSetError::ValueOccupied.into());
+        }
+
+        if self.remaining_capacity() == 0 {
+            return Err(RuleSetError::RuleSetTooLarge.into());
+        }
+
+        // Estimate the new size from the current size plus the new map entry.
+        let size = self.serialized_size()? + msgpack_size(&operation)? + msgpack_size(&rules)?;
+        if size > Self::MAX_SERIALIZED_SIZE {
+            return Err(RuleSetError::RuleSetTooLarge.into());
+        }
+
+        self.operations.insert(operation, rules);
+        Ok(())
+    }
+
+    /// Get the number of operations that can still be added before reaching `MAX_OPERATIONS`.
+    pub fn remaining_capacity(&self) -> usize {
+        Self::MAX_OPERATIONS.saturating_sub(self.operations.len())
+    }
+
+    /// Add all operations from `other` into this `RuleSet`, keeping this `RuleSet`'s name and
+    /// owner.  Operations only in `other` are added, and operations in both are resolved using
+    /// `on_conflict`.  Conflicts, and the `MAX_OPERATIONS` limit, are checked before anything is
+    /// added, so a failed merge leaves this `RuleSet` unchanged.
+    pub fn merge(
+        &mut self,
+        other: RuleSetV1,
+        on_conflict: MergeStrategy,
+    ) -> Result<(), RuleSetError> {
+        if on_conflict == MergeStrategy::Error
+            && other
+                .operations
+                .keys()
+                .any(|operation| self.operations.contains_key(operation))
+        {
+            return Err(RuleSetError::ValueOccupied);
+        }
+
+        let new_operations = other
+            .operations
+            .keys()
+            .filter(|operation| !self.operations.contains_key(*operation))
+            .count();
+        if new_operations > self.remaining_capacity() {
+            return Err(RuleSetError::RuleSetTooLarge);
+        }
+
+        for (operation, rule) in other.operations {
+            if on_conflict == MergeStrategy::KeepExisting {
+                self.operations.entry(operation).or_insert(rule);
+            } else {
+                self.operations.insert(operation, rule);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check every `Rule` in the `RuleSet` for problems that are not errors, but that make a
+    /// `Rule` behave in a way its author is unlikely to have meant, such as an empty `Any` or a
+    /// Merkle proof field with no name.  This is a lint for `RuleSet` authors to run before
+    /// putting a `RuleSet` on chain.  Warnings are returned in operation order.
+    pub fn check(&self) -> Vec<RuleSetWarning> {
+        let mut warnings = Vec::new();
+        for (operation, rule) in &self.operations {
+            // Bound the recursion below.
+            if rule.assert_depth().is_err() {
+                warnings.push(RuleSetWarning {
+                    operation: operation.clone(),
+                    path: rule.name().to_string(),
+                    kind: RuleSetWarningKind::TreeTooDeep,
+                });
+                continue;
+            }
+
+            check_rule(operation, rule.name().to_string(), rule, &mut warnings);
         }
+
+        warnings
+    }
+
+    /// Compare the operations in this `RuleSet` to those in `other`, for example an updated
+    /// revision that is about to be put on chain.  Only operations and their `Rule`s are compared,
+    /// not the name or owner.
+    pub fn diff(&self, other: &RuleSetV1) -> RuleSetDiff {
+        let mut diff = RuleSetDiff::default();
+
+        for (operation, old) in &self.operations {
+            match other.operations.get(operation) {
+                None => diff.removed.push(operation.clone()),
+                Some(new) if new != old => diff.changed.push(ChangedOperation {
+                    operation: operation.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => (),
+            }
+        }
+        diff.added = other
+            .operations
+            .keys()
+            .filter(|operation| !self.operations.contains_key(*operation))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// Set the default `Rule`, used by `Validate` for operations that are not in the `RuleSet`.
+    /// An operation's own entry, or its namespace entry, always takes precedence over the
+    /// default.  This is the same as calling `add` with `DEFAULT_OPERATION`, so an error is
+    /// returned if a default is already set.
+    pub fn set_default(&mut self, rule: Rule) -> ProgramResult {
+        self.add(Self::DEFAULT_OPERATION, rule)
+    }
+
+    /// Retrieve the default `Rule`, if one is set.
+    pub fn default_rule(&self) -> Option<&Rule> {
+        self.get(Self::DEFAULT_OPERATION)
     }
 
     /// Retrieve the `Rule` tree for a given `Operation`.
-    pub fn get(&self, operation: String) -> Option<&Rule> {
-        self.operations.get(&operation)
+    pub fn get(&self, operation: &str) -> Option<&Rule> {
+        self.operations.get(operation)
+    }
+
+    /// Remove an operation from the `RuleSet`, returning its `Rule` if the operation was present.
+    pub fn remove(&mut self, operation: &str) -> Option<Rule> {
+        self.operations.remove(operation)
+    }
+
+    /// Validate a payload against the `Rule` for an operation, falling back to the operation
+    /// namespace the same way as the `Validate` instruction.  This does not need a transaction, so
+    /// clients can use it as a pre-flight check.  `Rule`s that use `RuleSet` state, such as
+    /// `Frequency`, need the on-chain state PDA and do not pass here.  Failures return the full
+    /// `RuleError` detail rather than the flattened on-chain error.
+    pub fn validate(
+        &self,
+        operation: &str,
+        payload: &Payload,
+        context: &dyn ValidationContext,
+    ) -> Result<(), RuleError> {
+        let rule = get_operation(operation.to_string(), self)?;
+        rule.validate(context, payload, false, &None, &None)
+    }
+
+    /// Get the accounts that must be passed to `Validate` in `additional_rule_accounts` to
+    /// validate `operation` with `payload`.  The `Rule` for the operation is looked up as in
+    /// `validate`, and each `Pubkey` is listed once, in the order the `Rule`s are first visited.
+    /// Accounts whose `Pubkey` is missing from the `Payload` are left out, as are those of
+    /// `ProgramOwnedAtIndex` rules, which are found by index rather than `Pubkey`.  An operation
+    /// with no `Rule` needs no accounts.
+    pub fn required_accounts(&self, operation: &str, payload: &Payload) -> Vec<Pubkey> {
+        let mut visitor = RequiredAccounts {
+            payload,
+            accounts: Vec::new(),
+        };
+        if let Ok(rule) = get_operation(operation.to_string(), self) {
+            rule.visit(&mut visitor);
+        }
+
+        visitor.accounts
+    }
+
+    /// Iterate over the `Operation`s that have a `Rule` in this `RuleSet`, in sorted order.
+    pub fn operations(&self) -> impl Iterator<Item = &String> {
+        self.operations.keys()
+    }
+
+    /// Get the length in bytes of this `RuleSet` when serialized into the MessagePack format,
+    /// without allocating a buffer for the serialized data.
+    pub fn serialized_size(&self) -> Result<usize, RuleSetError> {
+        msgpack_size(self)
+    }
+
+    /// Get the keccak256 hash of this `RuleSet` serialized into the MessagePack format, as a
+    /// stable identifier that other programs can store and later compare.  Operations are
+    /// serialized in sorted order, so equal `RuleSet`s always have the same hash.  A revision
+    /// written on chain from this serialization hashes to the same value with
+    /// `solana_program::keccak::hash`.
+    pub fn hash(&self) -> Result<[u8; 32], RuleSetError> {
+        let mut hasher = HashWriter::default();
+        self.serialize(&mut Serializer::new(&mut hasher))
+            .map_err(|_| RuleSetError::MessagePackSerializationError)?;
+
+        Ok(hasher.0.result().to_bytes())
+    }
+
+    /// Deserialize only the `Rule` for `operation` from a MessagePack-serialized `RuleSetV1`.  The
+    /// other operations are skipped over without being deserialized into `Rule`s, which saves
+    /// compute when validating a large `RuleSet`.
+    pub fn get_rule_for_operation(data: &[u8], operation: &str) -> Result<Rule, RuleSetError> {
+        let mut deserializer = rmp_serde::Deserializer::from_read_ref(data);
+        deserializer
+            .deserialize_struct(
+                "RuleSetV1",
+                RULE_SET_V1_FIELDS,
+                RuleSetOperationVisitor { operation },
+            )
+            .map_err(msgpack_error)?
+            .ok_or(RuleSetError::OperationNotFound)
+    }
+
+    /// Deserialize a MessagePack-serialized `RuleSetV1` while skipping over all of its operations.
+    /// The returned `RuleSet` has no operations; use `get_rule_for_operation` to look them up.
+    pub fn from_slice_without_operations(data: &[u8]) -> Result<Self, RuleSetError> {
+        let rule_set: RuleSetV1WithoutOperations = from_msgpack_slice(data)?;
+
+        Ok(Self {
+            lib_version: rule_set.lib_version,
+            owner: rule_set.owner,
+            rule_set_name: rule_set.rule_set_name,
+            operations: BTreeMap::new(),
+        })
+    }
+
+    /// Deserialize a `RuleSet` from JSON.  Pubkeys are expected as base58 strings.
+    #[cfg(feature = "serde-with-feature")]
+    pub fn from_json(json: &str) -> Result<Self, RuleSetError> {
+        serde_json::from_str(json).map_err(|_| RuleSetError::JsonDeserializationError)
+    }
+
+    /// Serialize the `RuleSet` to pretty-printed JSON.  Pubkeys are written as base58 strings.
+    #[cfg(feature = "serde-with-feature")]
+    pub fn to_json_pretty(&self) -> Result<String, RuleSetError> {
+        serde_json::to_string_pretty(self).map_err(|_| RuleSetError::JsonSerializationError)
+    }
+}
+
+impl TryFrom<&[u8]> for RuleSetV1 {
+    type Error = RuleSetError;
+
+    /// Deserialize the latest `RuleSet` revision from the data of a `RuleSet` PDA.  The header,
+    /// revision map and version bytes are checked, so the raw account data can be passed in as
+    /// is.  See the state module for a description of the PDA memory layout.
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        // Check the `Key` before deserializing so that accounts not holding a `RuleSet` give a
+        // clear error.
+        match data.first() {
+            Some(key) if *key == Key::RuleSet as u8 => (),
+            Some(key) if *key == Key::Uninitialized as u8 => {
+                return Err(RuleSetError::Uninitialized)
+            }
+            _ => return Err(RuleSetError::DataTypeMismatch),
+        }
+
+        // Only the revision map location is needed, which is stored at the same place in the
+        // legacy and the current header.
+        let header = data
+            .get(..LEGACY_RULE_SET_SERIALIZED_HEADER_LEN)
+            .and_then(|header| LegacyRuleSetHeader::try_from_slice(header).ok())
+            .ok_or(RuleSetError::DataTypeMismatch)?;
+
+        // Find the latest revision from the revision map.
+        let rev_map_location = header.rev_map_version_location;
+        match data.get(rev_map_location) {
+            Some(&RULE_SET_REV_MAP_VERSION) => (),
+            Some(_) => return Err(RuleSetError::UnsupportedRuleSetRevMapVersion),
+            None => return Err(RuleSetError::DataTypeMismatch),
+        }
+
+        let rev_map_start = rev_map_location
+            .checked_add(1)
+            .ok_or(RuleSetError::NumericalOverflow)?;
+        let revision_map = data
+            .get(rev_map_start..)
+            .and_then(|revision_map| RuleSetRevisionMapV1::try_from_slice(revision_map).ok())
+            .ok_or(RuleSetError::DataTypeMismatch)?;
+
+        let start = *revision_map
+            .rule_set_revisions
+            .last()
+            .ok_or(RuleSetError::RuleSetRevisionNotAvailable)?;
+
+        // Check the `RuleSet` lib version and deserialize the revision following it.
+        match data.get(start) {
+            Some(&RULE_SET_LIB_VERSION) => {
+                let start = start
+                    .checked_add(1)
+                    .ok_or(RuleSetError::NumericalOverflow)?;
+                data.get(start..rev_map_location)
+                    .ok_or(RuleSetError::DataTypeMismatch)
+                    .and_then(from_msgpack_slice_strict)
+            }
+            Some(_) => Err(RuleSetError::UnsupportedRuleSetVersion),
+            None => Err(RuleSetError::DataTypeMismatch),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+/// A builder for a `RuleSetV1`, collecting the name, owner and operations so that any error is
+/// returned once by `build` instead of by each `add`.
+pub struct RuleSetBuilder {
+    name: Option<String>,
+    owner: Option<Pubkey>,
+    operations: Vec<(String, Rule)>,
+}
+
+impl RuleSetBuilder {
+    /// Create a new empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `RuleSet` name.
+    pub fn name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the `RuleSet` owner.
+    pub fn owner(&mut self, owner: Pubkey) -> &mut Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Add an operation and its `Rule`.  Operations are added in order by `build`.
+    pub fn operation(&mut self, operation: impl Into<String>, rule: Rule) -> &mut Self {
+        self.operations.push((operation.into(), rule));
+        self
+    }
+
+    /// Build the `RuleSet`.  The name must be non-empty and pass `validate_rule_set_name`, and
+    /// the owner must be set to a `Pubkey` other than the default.  Each operation is then added
+    /// with `RuleSetV1::add`, and the first error it returns is returned.
+    pub fn build(&self) -> Result<RuleSetV1, RuleSetError> {
+        let name = match &self.name {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => return Err(RuleSetError::EmptyName),
+        };
+        validate_rule_set_name(&name)?;
+
+        let owner = match self.owner {
+            Some(owner) if owner != Pubkey::default() => owner,
+            _ => return Err(RuleSetError::MissingOwner),
+        };
+
+        let mut rule_set = RuleSetV1::new(name, owner);
+        for (operation, rule) in &self.operations {
+            rule_set
+                .add(operation.clone(), rule.clone())
+                .map_err(rule_set_error)?;
+        }
+
+        Ok(rule_set)
+    }
+}
+
+// Recover the `RuleSetError` from an error returned by `RuleSetV1::add`, which only returns
+// `RuleSetError`s converted into `ProgramError::Custom`.
+fn rule_set_error(err: ProgramError) -> RuleSetError {
+    match err {
+        ProgramError::Custom(code) => {
+            RuleSetError::from_u32(code).unwrap_or(RuleSetError::DataTypeMismatch)
+        }
+        _ => RuleSetError::DataTypeMismatch,
+    }
+}
+
+// Add a warning to `warnings` for each problem with `rule`, which is at `path` in the `Rule` tree
+// for `operation`, and recurse into any contained rules.
+fn check_rule(operation: &str, path: String, rule: &Rule, warnings: &mut Vec<RuleSetWarning>) {
+    let mut kinds = Vec::new();
+    let mut fields: Vec<&String> = Vec::new();
+    let mut children: Vec<(String, &Rule)> = Vec::new();
+
+    match rule {
+        Rule::All { rules } | Rule::Any { rules } => {
+            if rules.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyRuleList);
+            }
+            children.extend(
+                rules
+                    .iter()
+                    .enumerate()
+                    .map(|(index, child)| (format!("{}[{}].{}", path, index, child.name()), child)),
+            );
+        }
+        Rule::Not { rule: child } => children.push((format!("{}.{}", path, child.name()), child)),
+        Rule::IfThen {
+            condition,
+            then_rule,
+            else_rule,
+        } => children.extend(
+            [("condition", condition), ("then_rule", then_rule)]
+                .into_iter()
+                .chain(else_rule.iter().map(|rule| ("else_rule", rule)))
+                .map(|(segment, child)| {
+                    (
+                        format!("{}.{}.{}", path, segment, child.name()),
+                        child.as_ref(),
+                    )
+                }),
+        ),
+        Rule::PubkeyListMatch { pubkeys, field } | Rule::PubkeyListDeny { pubkeys, field } => {
+            if pubkeys.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyPubkeyList);
+            }
+            if pubkeys.len() > MAX_PUBKEY_LIST_LEN {
+                kinds.push(RuleSetWarningKind::PubkeyListTooLong);
+            }
+            fields.push(field);
+        }
+        Rule::ProgramOwnedList { programs, field } => {
+            if programs.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyPubkeyList);
+            }
+            fields.push(field);
+        }
+        Rule::ProgramOwnedSet { programs, field } => {
+            if programs.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyPubkeyList);
+            }
+            fields.push(field);
+        }
+        Rule::AdditionalSignerList { signers, threshold } => {
+            if signers.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyPubkeyList);
+            }
+            if *threshold == 0 || *threshold as usize > signers.len() {
+                kinds.push(RuleSetWarningKind::InvalidSignerThreshold);
+            }
+        }
+        Rule::PubkeyTreeMatch {
+            pubkey_field,
+            proof_field,
+            ..
+        }
+        | Rule::ProgramOwnedTree {
+            pubkey_field,
+            proof_field,
+            ..
+        }
+        | Rule::PubkeyTreeMatchFromAccount {
+            pubkey_field,
+            proof_field,
+            ..
+        } => fields.extend([pubkey_field, proof_field]),
+        Rule::PubkeyTreeMatchAny {
+            pubkey_field,
+            proof_field,
+            root_index_field,
+            ..
+        } => fields.extend([pubkey_field, proof_field, root_index_field]),
+        Rule::PubkeyMatchFromData {
+            field,
+            source_field,
+            ..
+        } => fields.extend([field, source_field]),
+        Rule::PDAMatch {
+            pda_field,
+            seeds_field,
+            ..
+        } => fields.extend([pda_field, seeds_field]),
+        Rule::DataMatch {
+            account_field,
+            bytes_field,
+            ..
+        } => fields.extend([account_field, bytes_field]),
+        Rule::PubkeyMatch { field, .. }
+        | Rule::ProgramOwned { field, .. }
+        | Rule::Amount { field, .. }
+        | Rule::AmountFromTokenAccount { field, .. }
+        | Rule::IsWallet { field }
+        | Rule::ProgramOwnedWithData { field, .. }
+        | Rule::LastUpdateWithin { field, .. } => fields.push(field),
+        Rule::AmountSum {
+            fields: amount_fields,
+            ..
+        } => {
+            if amount_fields.is_empty() {
+                kinds.push(RuleSetWarningKind::EmptyField);
+            }
+            fields.extend(amount_fields);
+        }
+        Rule::Timestamp {
+            start: Some(start),
+            end: Some(end),
+        } if start > end => kinds.push(RuleSetWarningKind::EmptyRange),
+        Rule::Slot {
+            min: Some(min),
+            max: Some(max),
+        } if min > max => kinds.push(RuleSetWarningKind::EmptyRange),
+        _ => (),
+    }
+
+    if fields.iter().any(|field| field.is_empty()) {
+        kinds.push(RuleSetWarningKind::EmptyField);
+    }
+
+    warnings.extend(kinds.into_iter().map(|kind| RuleSetWarning {
+        operation: operation.to_string(),
+        path: path.clone(),
+        kind,
+    }));
+
+    for (child_path, child) in children {
+        check_rule(operation, child_path, child, warnings);
+    }
+}
+
+// Collects the accounts read by the `Rule`s of a rule tree, for `RuleSetV1::required_accounts`.
+struct RequiredAccounts<'a> {
+    payload: &'a Payload,
+    accounts: Vec<Pubkey>,
+}
+
+impl RequiredAccounts<'_> {
+    fn push(&mut self, account: &Pubkey) {
+        if !self.accounts.contains(account) {
+            self.accounts.push(*account);
+        }
+    }
+
+    fn push_field(&mut self, field: &str) {
+        if let Some(account) = self.payload.get_pubkey(&field.to_string()) {
+            self.push(account);
+        }
+    }
+}
+
+impl RuleVisitor for RequiredAccounts<'_> {
+    fn visit_leaf(&mut self, rule: &Rule) {
+        match rule {
+            Rule::AdditionalSigner { account } => self.push(account),
+            Rule::AdditionalSignerList { signers, .. } => {
+                signers.iter().for_each(|signer| self.push(signer))
+            }
+            // The PDA's owner is only read when the rule has no program.
+            Rule::PDAMatch {
+                program: None,
+                pda_field,
+                ..
+            } => self.push_field(pda_field),
+            // These rules check each account in a `|`-separated list of fields.
+            Rule::ProgramOwnedList { field, .. } | Rule::ProgramOwnedSet { field, .. } => {
+                field.split('|').for_each(|field| self.push_field(field))
+            }
+            Rule::ProgramOwned { field, .. }
+            | Rule::ProgramOwnedWithData { field, .. }
+            | Rule::IsWallet { field }
+            | Rule::LastUpdateWithin { field, .. }
+            | Rule::AmountFromTokenAccount { field, .. }
+            | Rule::DataMatch {
+                account_field: field,
+                ..
+            }
+            | Rule::ProgramOwnedTree {
+                pubkey_field: field,
+                ..
+            }
+            | Rule::PubkeyTreeMatchFromAccount {
+                pubkey_field: field,
+                ..
+            }
+            | Rule::PubkeyMatchFromData {
+                source_field: field,
+                ..
+            } => self.push_field(field),
+            _ => (),
+        }
+    }
+}
+
+/// The serialized field names of `RuleSetV1`.
+const RULE_SET_V1_FIELDS: &[&str] = &["libVersion", "owner", "ruleSetName", "operations"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Mirror of `RuleSetV1` used to deserialize everything but the operations.
+struct RuleSetV1WithoutOperations {
+    lib_version: u8,
+    #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
+    owner: Pubkey,
+    rule_set_name: String,
+    #[allow(dead_code)]
+    operations: IgnoredAny,
+}
+
+/// Visits a serialized `RuleSetV1`, skipping everything except the `Rule` for one operation.
+struct RuleSetOperationVisitor<'a> {
+    operation: &'a str,
+}
+
+impl<'de, 'a> Visitor<'de> for RuleSetOperationVisitor<'a> {
+    type Value = Option<Rule>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct RuleSetV1")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Skip the lib version, owner, and name.
+        for index in 0..3 {
+            seq.next_element::<IgnoredAny>()?
+                .ok_or_else(|| de::Error::invalid_length(index, &self))?;
+        }
+
+        seq.next_element_seed(OperationsSeed {
+            operation: self.operation,
+        })?
+        .ok_or_else(|| de::Error::invalid_length(3, &self))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut rule = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "operations" {
+                rule = map.next_value_seed(OperationsSeed {
+                    operation: self.operation,
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Deserializes the operations map of a `RuleSetV1`, keeping only the `Rule` for one operation.
+struct OperationsSeed<'a> {
+    operation: &'a str,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for OperationsSeed<'a> {
+    type Value = Option<Rule>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for OperationsSeed<'a> {
+    type Value = Option<Rule>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of operations to rules")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut rule = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if rule.is_none() && key == self.operation {
+                rule = Some(map.next_value::<Rule>()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(rule)
+    }
+}
+
+// Get the length in bytes of `value` when serialized into the MessagePack format.
+fn msgpack_size<T: Serialize + ?Sized>(value: &T) -> Result<usize, RuleSetError> {
+    let mut counter = ByteCounter::default();
+    value
+        .serialize(&mut Serializer::new(&mut counter))
+        .map_err(|_| RuleSetError::MessagePackSerializationError)?;
+
+    Ok(counter.count)
+}
+
+#[derive(Default)]
+/// A `Write` sink that only counts the number of bytes written to it.
+struct ByteCounter {
+    count: usize,
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+/// A `Write` sink that feeds the bytes written to it into a keccak256 hash.
+struct HashWriter(keccak::Hasher);
+
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.hash(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
@@ -1,18 +1,50 @@
 use crate::{
-    error::RuleSetError,
-    payload::Payload,
+    error::{RuleError, RuleSetError},
+    merkle::{hash_leaf, verify, verify_proof},
+    payload::{Payload, MAX_PROOF_DEPTH},
+    state::{
+        deserialize_pubkeys, deserialize_rules, from_msgpack_slice_strict, serialize_pubkey_set,
+        FrequencyAccount, OwnerCachingContext, SolanaAccount, ValidationContext,
+    },
     // TODO: Uncomment this after on-curve sycall available.
     // utils::is_on_curve,
-    utils::{assert_derivation, compute_merkle_root, is_zeroed},
+    utils::assert_derivation,
+    MAX_NAME_LENGTH,
 };
+use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-with-feature")]
 use serde_with::{As, DisplayFromStr};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
-    pubkey::Pubkey, system_program,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+    system_program,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+
+/// The maximum number of `Pubkey`s allowed in a `PubkeyListMatch` or `PubkeyListDeny` rule.
+/// Larger allowlists should use `PubkeyTreeMatch` instead.
+pub const MAX_PUBKEY_LIST_LEN: usize = 64;
+
+/// The maximum number of roots allowed in a `PubkeyTreeMatchAny` rule.
+pub const MAX_TREE_ROOTS: usize = 16;
+
+/// The maximum number of composite rules (`All`, `Any`, `Not` and `IfThen`) that can be nested
+/// inside each other.  This bounds the stack used to validate a rule tree and the recursion used
+/// to simulate one.
+pub const MAX_RULE_DEPTH: usize = 16;
+
+/// The SPL Token program, which must own the token account checked by an
+/// `AmountFromTokenAccount` rule.
+pub const SPL_TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// The offset of the little-endian `u64` amount in the data of an SPL Token account, after the
+/// mint and owner `Pubkey`s.
+pub const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 /// Operators that can be used to compare against an `Amount` rule.
@@ -29,20 +61,41 @@ pub enum CompareOp {
     Gt,
 }
 
+impl CompareOp {
+    /// Compare `lhs` to `rhs` using this operator.
+    pub fn compare(&self, lhs: u64, rhs: u64) -> bool {
+        match self {
+            CompareOp::Lt => lhs < rhs,
+            CompareOp::LtEq => lhs <= rhs,
+            CompareOp::Eq => lhs == rhs,
+            CompareOp::Gt => lhs > rhs,
+            CompareOp::GtEq => lhs >= rhs,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
-/// The struct containing every type of Rule and its associated data.
+/// The struct containing every type of Rule and its associated data.  Each variant's serialized
+/// name is set explicitly, so that renaming a variant does not break `RuleSet`s already on chain or
+/// written as JSON.  A variant whose name changes keeps its old name as a serde `alias`.
 pub enum Rule {
     /// Group AND, where every rule contained must pass.
+    #[serde(rename = "All")]
     All {
         /// The vector of Rules contained under All.
+        #[serde(deserialize_with = "deserialize_rules")]
         rules: Vec<Rule>,
     },
     /// Group OR, where at least one rule contained must pass.
+    #[serde(rename = "Any")]
     Any {
         /// The vector of Rules contained under Any.
+        #[serde(deserialize_with = "deserialize_rules")]
         rules: Vec<Rule>,
     },
-    /// Negation, where the contained rule must fail.
+    /// Negation, where the contained rule must fail.  The contained rule never updates rule
+    /// state, as it only passes when `Not` fails.
+    #[serde(rename = "Not")]
     Not {
         /// The Rule contained under Not.
         rule: Box<Rule>,
@@ -51,6 +104,7 @@ pub enum Rule {
     /// does not require any `Payload` values, but the additional signer account must be provided
     /// to `Validate` via the `additional_rule_accounts` argument so that whether it is a signer
     /// can be retrieved from its `AccountInfo` struct.
+    #[serde(rename = "AdditionalSigner")]
     AdditionalSigner {
         /// The public key that must have also signed the transaction.
         #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
@@ -59,6 +113,7 @@ pub enum Rule {
     /// Direct comparison between `Pubkey`s.  When the `Validate` instruction is called, this rule
     /// requires a `PayloadType` value of `PayloadType::Pubkey`.  The `field` value in the rule is
     /// used to locate the `Pubkey` in the payload to compare to the `Pubkey` in the rule.
+    #[serde(rename = "PubkeyMatch")]
     PubkeyMatch {
         /// The public key to be compared against.
         #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
@@ -69,9 +124,11 @@ pub enum Rule {
     /// The comparing `Pubkey` must be in the list of `Pubkey`s.  When the `Validate` instruction
     /// is called, this rule requires a `PayloadType` value of `PayloadType::Pubkey`.  The `field`
     /// value in the Rule is used to locate the `Pubkey` in the payload to compare to the `Pubkey`
-    /// list in the rule.
+    /// list in the rule.  The list can hold at most `MAX_PUBKEY_LIST_LEN` `Pubkey`s.
+    #[serde(rename = "PubkeyListMatch")]
     PubkeyListMatch {
         /// The list of public keys to be compared against.
+        #[serde(deserialize_with = "deserialize_pubkeys")]
         pubkeys: Vec<Pubkey>,
         /// The field in the `Payload` to be compared.
         field: String,
@@ -80,7 +137,9 @@ pub enum Rule {
     /// `Validate` instruction is called, this rule requires `PayloadType` values of
     /// `PayloadType::Pubkey` and `PayloadType::MerkleProof`.  The `field` values in the Rule are
     /// used to locate them in the `Payload`.  The `Pubkey` and the proof are used to calculate
-    /// a Merkle root which is compared against the root stored in the rule.
+    /// a Merkle root which is compared against the root stored in the rule.  See the `merkle`
+    /// module for the tree conventions.
+    #[serde(rename = "PubkeyTreeMatch")]
     PubkeyTreeMatch {
         /// The root of the Merkle tree.
         root: [u8; 32],
@@ -90,12 +149,17 @@ pub enum Rule {
         /// The field in the `Payload` to be compared
         /// when looking for the Merkle proof.
         proof_field: String,
+        /// Whether the tree leaves are hashed `Pubkey`s rather than raw `Pubkey`s.  Defaults to
+        /// `false` for `RuleSet`s serialized before this field was added.
+        #[serde(default)]
+        leaf_is_hashed: bool,
     },
     /// A resulting PDA derivation of seeds must prove the account is a PDA.  When the `Validate`
     /// instruction is called, this rule requires `PayloadType` values of `PayloadType::Seeds`.
     /// The `field` values in the Rule are used to locate them in the `Payload`.  The seeds in the
     /// `Payload` and the program ID stored in the Rule are used to derive the PDA from the
     /// `Payload`.
+    #[serde(rename = "PDAMatch")]
     PDAMatch {
         /// The program used for the PDA derivation.  If
         /// `None` then the account owner is used.
@@ -113,6 +177,7 @@ pub enum Rule {
     /// program in the rule.  Note this same `Pubkey` account must also be provided to `Validate`
     /// via the `additional_rule_accounts` argument.  This is so that the `Pubkey`'s owner can be
     /// found from its `AccountInfo` struct.
+    #[serde(rename = "ProgramOwned")]
     ProgramOwned {
         /// The program that must own the `Pubkey`.
         #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
@@ -126,8 +191,10 @@ pub enum Rule {
     /// owner must be a program in the list in the rule.  Note this same `Pubkey` account must also
     /// be provided to `Validate` via the `additional_rule_accounts` argument.  This is so that the
     /// `Pubkey`'s owner can be found from its `AccountInfo` struct.
+    #[serde(rename = "ProgramOwnedList")]
     ProgramOwnedList {
         /// The program that must own the `Pubkey`.
+        #[serde(deserialize_with = "deserialize_pubkeys")]
         programs: Vec<Pubkey>,
         /// The field in the `Payload` to be compared.
         field: String,
@@ -139,6 +206,7 @@ pub enum Rule {
     /// `additional_rule_accounts` argument.  This is so that the `Pubkey`'s owner can be found
     /// from its `AccountInfo` struct.  The owner and the proof are then used to calculate a Merkle
     /// root, which is compared against the root stored in the rule.
+    #[serde(rename = "ProgramOwnedTree")]
     ProgramOwnedTree {
         /// The root of the Merkle tree.
         root: [u8; 32],
@@ -153,6 +221,7 @@ pub enum Rule {
     /// instruction is called, this rule requires a `PayloadType` value of `PayloadType::Amount`.
     /// The `field` value in the Rule is used to locate the numerical amount in the payload to
     /// compare to the amount stored in the rule, using the comparison operator stored in the rule.
+    #[serde(rename = "Amount")]
     Amount {
         /// The amount to be compared against.
         amount: u64,
@@ -161,27 +230,40 @@ pub enum Rule {
         /// The field the amount is stored in.
         field: String,
     },
-    /// Comparison based on time between operations.  Currently not implemented.  This rule
-    /// is planned check to ensure a certain amount of time has passed.  This rule will make use
-    /// of the `rule_set_state_pda` optional account passed into `Validate`, and will require
-    /// the optional `rule_authority` account to sign.
+    /// Comparison based on time between operations.  This rule checks that at least `period`
+    /// seconds have passed since the `last_update` time stored in the `FrequencyAccount`.  This
+    /// rule makes use of the `rule_set_state_pda` optional account passed into `Validate`, and
+    /// requires the optional `rule_authority` account to sign.  When `update_rule_state` is set,
+    /// a passing validation stores the current `Clock` time as the new `last_update`.  Rules with
+    /// different names keep separate `last_update` times in the same `FrequencyAccount`, so that,
+    /// for example, transfers and delegates can be limited to different periods.
+    #[serde(rename = "Frequency")]
     Frequency {
         /// The authority of the frequency account.
         #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
         authority: Pubkey,
+        /// The number of seconds which must transpire before the rule will succeed again.
+        period: i64,
+        /// The name of the rule's slot in the `FrequencyAccount`, such as the operation it
+        /// guards.  Rules without a name share the account's original `last_update`.
+        #[serde(default)]
+        name: String,
     },
     /// The true test if a pubkey can be signed from a client and therefore is a true wallet account.
-    /// The details of this rule are as follows: a wallet is defined as being both owned by the
-    /// System Program and the address is on-curve.  The `field` value in the rule is used to
-    /// locate the `Pubkey` in the payload that must be on-curve and for which the owner must be
-    /// the System Program.  Note this same `Pubkey` account must also be provided to `Validate`
-    /// via the `additional_rule_accounts` argument.  This is so that the `Pubkey`'s owner can be
-    /// found from its `AccountInfo` struct.
+    /// The details of this rule are as follows: a wallet is defined as being owned by the System
+    /// Program and holding no data.  The address will also be required to be on-curve once the
+    /// on-curve syscall is available.  The `field` value in the rule is used to locate the
+    /// `Pubkey` in the payload for which the owner must be the System Program.  Note this same
+    /// `Pubkey` account must also be provided to `Validate` via the `additional_rule_accounts`
+    /// argument.  This is so that the `Pubkey`'s owner and data can be found from its
+    /// `AccountInfo` struct.
+    #[serde(rename = "IsWallet")]
     IsWallet {
         /// The field in the `Payload` to be checked.
         field: String,
     },
     /// An operation that always succeeds.
+    #[serde(rename = "Pass")]
     Pass,
     /// The `Pubkey` must be owned by a program in the set of `Pubkey`s.  When the `Validate`
     /// instruction is called, this rule requires a `PayloadType` value of `PayloadType::Pubkey`.
@@ -189,28 +271,255 @@ pub enum Rule {
     /// owner must be a program in the set in the rule.  Note this same `Pubkey` account must also
     /// be provided to `Validate` via the `additional_rule_accounts` argument.  This is so that the
     /// `Pubkey`'s owner can be found from its `AccountInfo` struct.
+    #[serde(rename = "ProgramOwnedSet")]
     ProgramOwnedSet {
         /// The program that must own the `Pubkey`.
+        #[serde(serialize_with = "serialize_pubkey_set")]
         programs: HashSet<Pubkey>,
         /// The field in the `Payload` to be compared.
         field: String,
     },
     /// A rule that tells the operation finder to use the default namespace rule.
+    #[serde(rename = "Namespace")]
     Namespace,
+    /// Comparison against the current `Clock` time.  This rule passes only if the current
+    /// `unix_timestamp` is within the inclusive window from `start` to `end`.  A `None` bound
+    /// leaves that side of the window open.
+    #[serde(rename = "Timestamp")]
+    Timestamp {
+        /// The earliest time at which the rule passes.
+        start: Option<i64>,
+        /// The latest time at which the rule passes.
+        end: Option<i64>,
+    },
+    /// Comparison against the current `Clock` slot.  This rule passes only if the current slot is
+    /// within the inclusive range from `min` to `max`.  A `None` bound leaves that side of the
+    /// range open.
+    #[serde(rename = "Slot")]
+    Slot {
+        /// The earliest slot at which the rule passes.
+        min: Option<u64>,
+        /// The latest slot at which the rule passes.
+        max: Option<u64>,
+    },
+    /// The `Pubkey` must be owned by a given program and its account must hold at least
+    /// `min_data_len` bytes of data.  This is the `ProgramOwned` check with an added data length
+    /// check, so that an account assigned to the program without the expected data (for example
+    /// an escrow account that was never initialized) does not pass.  The same `Pubkey` account
+    /// must also be provided to `Validate` via the `additional_rule_accounts` argument.
+    #[serde(rename = "ProgramOwnedWithData")]
+    ProgramOwnedWithData {
+        /// The program that must own the `Pubkey`.
+        #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
+        program: Pubkey,
+        /// The field in the `Payload` to be compared.
+        field: String,
+        /// The minimum account data length.
+        min_data_len: usize,
+    },
+    /// Comparison against the sum of the amounts stored under several fields.  This rule adds up
+    /// the amounts in the `Payload` under all `fields`, failing if any is missing or the sum
+    /// overflows, and compares the total against `amount` using `operator`.
+    #[serde(rename = "AmountSum")]
+    AmountSum {
+        /// The fields the amounts to sum are stored in.
+        fields: Vec<String>,
+        /// The operator to be used in the comparison.
+        operator: CompareOp,
+        /// The amount to be compared against.
+        amount: u64,
+    },
+    /// The `Pubkey` must be a member of the Merkle tree in the rule, and its account must be
+    /// provided.  This is the `PubkeyTreeMatch` check, except that the leaf is always the key of
+    /// an account passed to `Validate` via the `additional_rule_accounts` argument, so a caller
+    /// cannot prove membership for a `Pubkey` that does not correspond to a real account in the
+    /// transaction.  The Merkle proof is still located in the `Payload` using `proof_field`.
+    #[serde(rename = "PubkeyTreeMatchFromAccount")]
+    PubkeyTreeMatchFromAccount {
+        /// The root of the Merkle tree.
+        root: [u8; 32],
+        /// The field in the `Payload` to be compared
+        /// when looking for the account `Pubkey`.
+        pubkey_field: String,
+        /// The field in the `Payload` to be compared
+        /// when looking for the Merkle proof.
+        proof_field: String,
+    },
+    /// A timestamp stored in an account's data must be recent.  This rule reads a little-endian
+    /// `i64` Unix timestamp at `offset` in the data of the account whose `Pubkey` is located in
+    /// the `Payload` using `field`, and passes only if it is no more than `max_age` seconds
+    /// before the current `Clock` time.  The account must also be provided to `Validate` via the
+    /// `additional_rule_accounts` argument, and its data must hold the whole timestamp.
+    #[serde(rename = "LastUpdateWithin")]
+    LastUpdateWithin {
+        /// The field in the `Payload` to be compared.
+        field: String,
+        /// The offset of the timestamp in the account data.
+        offset: usize,
+        /// The maximum age of the timestamp, in seconds.
+        max_age: i64,
+    },
+    /// At least `threshold` of the listed `Pubkey`s must have signed.  When the `Validate`
+    /// instruction is called, this rule does not require any `Payload` values, but the signing
+    /// accounts must be provided to `Validate` via the `additional_rule_accounts` argument.  A
+    /// listed account that is not provided counts as not signing, and a `Pubkey` listed more than
+    /// once is only counted once.  The threshold must be between one and the number of signers.
+    #[serde(rename = "AdditionalSignerList")]
+    AdditionalSignerList {
+        /// The public keys that can sign.
+        #[serde(deserialize_with = "deserialize_pubkeys")]
+        signers: Vec<Pubkey>,
+        /// The number of listed public keys that must sign.
+        threshold: u8,
+    },
+    /// Bytes stored in an account's data must match bytes in the `Payload`.  When the `Validate`
+    /// instruction is called, this rule requires `PayloadType` values of `PayloadType::Pubkey`
+    /// and `PayloadType::Bytes`.  The `field` values in the Rule are used to locate them in the
+    /// `Payload`.  The rule passes only if the bytes are `expected_len` long and equal to the
+    /// `expected_len` bytes at `offset` in the data of the `Pubkey`'s account, which must also be
+    /// provided to `Validate` via the `additional_rule_accounts` argument.
+    #[serde(rename = "DataMatch")]
+    DataMatch {
+        /// The field in the `Payload` to be compared
+        /// when looking for the account `Pubkey`.
+        account_field: String,
+        /// The field in the `Payload` to be compared
+        /// when looking for the expected bytes.
+        bytes_field: String,
+        /// The offset of the bytes in the account data.
+        offset: usize,
+        /// The number of bytes to compare.
+        expected_len: usize,
+    },
+    /// The comparing `Pubkey` must NOT be in the list of `Pubkey`s in the rule, for blocking
+    /// known addresses.  When the `Validate` instruction is called, this rule requires a
+    /// `PayloadType` value of `PayloadType::Pubkey`.  The `field` value in the rule is used to
+    /// locate the `Pubkey` in the payload to compare to the list.  As with `PubkeyListMatch`,
+    /// several fields can be given separated by `|`, and the rule fails if any of their `Pubkey`s
+    /// is in the list.  The list can hold at most `MAX_PUBKEY_LIST_LEN` `Pubkey`s.
+    #[serde(rename = "PubkeyListDeny")]
+    PubkeyListDeny {
+        /// The list of public keys that are denied.
+        #[serde(deserialize_with = "deserialize_pubkeys")]
+        pubkeys: Vec<Pubkey>,
+        /// The field in the `Payload` to be compared.
+        field: String,
+    },
+    /// The account at `account_index` in the `additional_rule_accounts` passed to `Validate`
+    /// must be owned by a given program.  Unlike `ProgramOwned`, this rule does not require any
+    /// `Payload` values, so the account's `Pubkey` does not need to be repeated in the payload.
+    #[serde(rename = "ProgramOwnedAtIndex")]
+    ProgramOwnedAtIndex {
+        /// The program that must own the account.
+        #[cfg_attr(feature = "serde-with-feature", serde(with = "As::<DisplayFromStr>"))]
+        program: Pubkey,
+        /// The index of the account in the `additional_rule_accounts`.
+        account_index: u8,
+    },
+    /// Conditional, where the rule to apply depends on whether `condition` passes.  If it does,
+    /// `then_rule` must pass.  Otherwise `else_rule` must pass, or the rule passes if there is no
+    /// `else_rule`.  The result of `condition` itself never fails validation, and `condition` is
+    /// checked without updating rule state, so a `Frequency` condition does not record a use.
+    #[serde(rename = "IfThen")]
+    IfThen {
+        /// The Rule deciding which branch applies.
+        condition: Box<Rule>,
+        /// The Rule that must pass when `condition` passes.
+        then_rule: Box<Rule>,
+        /// The Rule that must pass when `condition` fails, if any.
+        else_rule: Option<Box<Rule>>,
+    },
+    /// Comparison against the amount held by an SPL Token account, read from the account data
+    /// rather than trusted from the `Payload`.  When the `Validate` instruction is called, this
+    /// rule requires a `PayloadType` value of `PayloadType::Pubkey`.  The `field` value in the
+    /// rule is used to locate the token account's `Pubkey` in the `Payload`, and the account
+    /// must also be provided to `Validate` via the `additional_rule_accounts` argument.  The
+    /// account must be owned by the SPL Token program.
+    #[serde(rename = "AmountFromTokenAccount")]
+    AmountFromTokenAccount {
+        /// The field in the `Payload` to be compared
+        /// when looking for the token account `Pubkey`.
+        field: String,
+        /// The operator to be used in the comparison.
+        operator: CompareOp,
+        /// The amount to be compared against.
+        amount: u64,
+    },
+    /// The comparing `Pubkey` must be a member of one of several Merkle trees in the rule, for
+    /// example one tree per marketplace category.  When the `Validate` instruction is called,
+    /// this rule requires `PayloadType` values of `PayloadType::Pubkey`,
+    /// `PayloadType::MerkleProof` and `PayloadType::Number`.  The number is the index in `roots`
+    /// of the tree the proof is for, and the check is otherwise the `PubkeyTreeMatch` check
+    /// against that root.  The rule can hold at most `MAX_TREE_ROOTS` roots.
+    #[serde(rename = "PubkeyTreeMatchAny")]
+    PubkeyTreeMatchAny {
+        /// The roots of the Merkle trees.
+        roots: Vec<[u8; 32]>,
+        /// The field in the `Payload` to be compared
+        /// when looking for the `Pubkey`.
+        pubkey_field: String,
+        /// The field in the `Payload` to be compared
+        /// when looking for the Merkle proof.
+        proof_field: String,
+        /// The field in the `Payload` to be compared
+        /// when looking for the index of the root in `roots`.
+        root_index_field: String,
+    },
+    /// Direct comparison between a `Pubkey` in the `Payload` and one stored in an account's
+    /// data, such as a mint's update authority, so that the expected `Pubkey` is not trusted
+    /// from the `Payload`.  When the `Validate` instruction is called, this rule requires
+    /// `PayloadType` values of `PayloadType::Pubkey`.  The rule reads the 32 bytes at `offset` in
+    /// the data of the account located in the `Payload` using `source_field`, which must also be
+    /// provided to `Validate` via the `additional_rule_accounts` argument, and passes only if
+    /// they are the `Pubkey` located using `field`.
+    #[serde(rename = "PubkeyMatchFromData")]
+    PubkeyMatchFromData {
+        /// The field in the `Payload` to be compared.
+        field: String,
+        /// The field in the `Payload` to be compared
+        /// when looking for the account holding the expected `Pubkey`.
+        source_field: String,
+        /// The offset of the expected `Pubkey` in the account data.
+        offset: usize,
+    },
+}
+
+/// A visitor over the rules of a rule tree, passed to `Rule::visit`.  Each method does nothing by
+/// default, so a visitor only implements the ones it needs.
+pub trait RuleVisitor {
+    /// Visit a composite rule (`All`, `Any`, `Not` or `IfThen`), before any of its children.
+    fn visit_composite(&mut self, _rule: &Rule) {}
+
+    /// Visit a leaf rule, which contains no other rules.
+    fn visit_leaf(&mut self, _rule: &Rule) {}
 }
 
 impl Rule {
+    /// Deserialize a MessagePack-serialized `Rule`, such as one serialized by `rmp_serde`.  The
+    /// `Rule` and `Pubkey` lists are checked against their maximum lengths, as with
+    /// `from_msgpack_slice`, and trailing bytes are rejected, as with
+    /// `from_msgpack_slice_strict`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, RuleSetError> {
+        from_msgpack_slice_strict(data)
+    }
+
     /// The top level validation function which parses an entire rule tree.
     pub fn validate(
         &self,
-        accounts: &HashMap<Pubkey, &AccountInfo>,
+        context: &dyn ValidationContext,
         payload: &Payload,
         update_rule_state: bool,
         rule_set_state_pda: &Option<&AccountInfo>,
         rule_authority: &Option<&AccountInfo>,
-    ) -> ProgramResult {
-        let (status, rollup_err) = self.low_level_validate(
-            accounts,
+    ) -> Result<(), RuleError> {
+        // Rule sets put on chain without going through `RuleSetV1::add` may be arbitrarily deep.
+        self.assert_depth()?;
+
+        // Resolve each account's owner once, however many rules in the tree check it.
+        let context = OwnerCachingContext::new(context);
+
+        let (status, rollup_err, mut failure_path) = self.validate_node(
+            &context,
             payload,
             update_rule_state,
             rule_set_state_pda,
@@ -218,103 +527,242 @@ impl Rule {
         );
 
         if status {
-            ProgramResult::Ok(())
+            Ok(())
         } else {
-            ProgramResult::Err(rollup_err)
+            log_failure_path(self, &mut failure_path);
+            Err(rollup_err)
         }
     }
 
-    /// Lower level validation function which iterates through a rule tree and applies boolean logic to rule results.
-    pub fn low_level_validate(
+    /// Validate every rule in the tree, without stopping at the first result that decides the
+    /// outcome, and log whether each one passed or failed.  Each rule is logged with its path in
+    /// the tree, for example `All[1].Amount`.  No rule state is updated.  Returns whether the
+    /// whole tree passed.
+    pub fn simulate(
         &self,
-        accounts: &HashMap<Pubkey, &AccountInfo>,
+        context: &dyn ValidationContext,
         payload: &Payload,
-        _update_rule_state: bool,
-        _rule_set_state_pda: &Option<&AccountInfo>,
+        rule_set_state_pda: &Option<&AccountInfo>,
         rule_authority: &Option<&AccountInfo>,
-    ) -> (bool, ProgramError) {
-        match self {
-            Rule::All { rules } => {
-                msg!("Validating All");
-                for rule in rules {
-                    let result = rule.low_level_validate(
-                        accounts,
-                        payload,
-                        _update_rule_state,
-                        _rule_set_state_pda,
-                        rule_authority,
-                    );
-                    // Return failure on the first failing rule.
-                    if !result.0 {
-                        return result;
-                    }
-                }
+    ) -> bool {
+        if let Err(err) = self.assert_depth() {
+            msg!("{} failed: {}", self.name(), err);
+            return false;
+        }
 
-                // Return pass if and only if all rules passed.
-                (true, self.to_error())
-            }
-            Rule::Any { rules } => {
-                msg!("Validating Any");
-                let mut last: Option<ProgramError> = None;
-                for rule in rules {
-                    let result = rule.low_level_validate(
-                        accounts,
+        let context = OwnerCachingContext::new(context);
+        self.simulate_node(
+            &context,
+            payload,
+            rule_set_state_pda,
+            rule_authority,
+            self.name(),
+        )
+    }
+
+    // Simulate one node of a rule tree, logging its result under `path`.
+    fn simulate_node(
+        &self,
+        context: &dyn ValidationContext,
+        payload: &Payload,
+        rule_set_state_pda: &Option<&AccountInfo>,
+        rule_authority: &Option<&AccountInfo>,
+        path: &str,
+    ) -> bool {
+        let simulate_child = |index: usize, rule: &Rule| {
+            rule.simulate_node(
+                context,
+                payload,
+                rule_set_state_pda,
+                rule_authority,
+                &format!("{}[{}].{}", path, index, rule.name()),
+            )
+        };
+
+        let passed = match self {
+            // Collect all results before combining them so that every rule is logged.
+            Rule::All { rules } => rules
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| simulate_child(index, rule))
+                .collect::<Vec<_>>()
+                .iter()
+                .all(|passed| *passed),
+            Rule::Any { rules } => rules
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| simulate_child(index, rule))
+                .collect::<Vec<_>>()
+                .iter()
+                .any(|passed| *passed),
+            Rule::Not { rule } => !rule.simulate_node(
+                context,
+                payload,
+                rule_set_state_pda,
+                rule_authority,
+                &format!("{}.{}", path, rule.name()),
+            ),
+            Rule::IfThen {
+                condition,
+                then_rule,
+                else_rule,
+            } => {
+                let simulate_branch = |segment: &str, rule: &Rule| {
+                    rule.simulate_node(
+                        context,
                         payload,
-                        _update_rule_state,
-                        _rule_set_state_pda,
+                        rule_set_state_pda,
                         rule_authority,
-                    );
-                    if result.0 {
-                        // Return pass on the first passing rule.
-                        return result;
-                    } else {
-                        // Save the last failure, but don't overwrite an existing last failure with
-                        // `RuleSetError::NotImplemented` as it can lead to a confusing result when
-                        // using `RuleSets` that have unimplemented rules in them.
-                        if last.is_none() || result.1 != RuleSetError::NotImplemented.into() {
-                            last = Some(result.1);
-                        }
+                        &format!("{}.{}.{}", path, segment, rule.name()),
+                    )
+                };
+
+                if simulate_branch("condition", condition) {
+                    simulate_branch("then_rule", then_rule)
+                } else {
+                    match else_rule {
+                        Some(else_rule) => simulate_branch("else_rule", else_rule),
+                        None => true,
                     }
                 }
-
-                // Return failure if and only if all rules failed.  Use the last failure.
-                match last {
-                    Some(last) => (false, last),
-                    None => (false, RuleSetError::UnexpectedRuleSetFailure.into()),
+            }
+            _ => {
+                let (passed, err) =
+                    self.validate_leaf(context, payload, false, rule_set_state_pda, rule_authority);
+                if !passed {
+                    msg!("{} failed: {}", path, err);
+                    return false;
                 }
+                true
             }
-            Rule::Not { rule } => {
-                let result = rule.low_level_validate(
-                    accounts,
-                    payload,
-                    _update_rule_state,
-                    _rule_set_state_pda,
-                    rule_authority,
-                );
+        };
+
+        if passed {
+            msg!("{} passed", path);
+        } else {
+            msg!("{} failed", path);
+        }
+        passed
+    }
+
+    /// Lower level validation function which iterates through a rule tree and applies boolean logic to rule results.
+    pub fn low_level_validate(
+        &self,
+        context: &dyn ValidationContext,
+        payload: &Payload,
+        update_rule_state: bool,
+        rule_set_state_pda: &Option<&AccountInfo>,
+        rule_authority: &Option<&AccountInfo>,
+    ) -> (bool, ProgramError) {
+        let context = OwnerCachingContext::new(context);
+        let (status, err, _) = self.validate_node(
+            &context,
+            payload,
+            update_rule_state,
+            rule_set_state_pda,
+            rule_authority,
+        );
+        (status, err.into())
+    }
+
+    // Validate a rule tree, using an explicit stack of the composite rules being validated
+    // instead of recursion.  On failure, the path to the failing rule is returned from the
+    // innermost rule outwards, when the `log-rule-path` feature is on.
+    fn validate_node(
+        &self,
+        context: &dyn ValidationContext,
+        payload: &Payload,
+        update_rule_state: bool,
+        rule_set_state_pda: &Option<&AccountInfo>,
+        rule_authority: &Option<&AccountInfo>,
+    ) -> NodeResult {
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut step = Step::Enter(self, update_rule_state);
 
-                // Negate the result.
-                (!result.0, result.1)
+        loop {
+            step = match step {
+                Step::Enter(rule, update_rule_state) => match Frame::new(rule, update_rule_state) {
+                    // Only keep the frame while it still has rules to validate.
+                    Some(mut frame) => {
+                        let step = frame.resume(None);
+                        if let Step::Enter(..) = step {
+                            stack.push(frame);
+                        }
+                        step
+                    }
+                    None => {
+                        let (passed, err) = rule.validate_leaf(
+                            context,
+                            payload,
+                            update_rule_state,
+                            rule_set_state_pda,
+                            rule_authority,
+                        );
+                        Step::Done((passed, err, Vec::new()))
+                    }
+                },
+                Step::Done(result) => match stack.last_mut() {
+                    Some(frame) => {
+                        let step = frame.resume(Some(result));
+                        if let Step::Done(_) = step {
+                            stack.pop();
+                        }
+                        step
+                    }
+                    None => return result,
+                },
+            };
+        }
+    }
+
+    // Validate a rule that contains no other rules.
+    fn validate_leaf(
+        &self,
+        context: &dyn ValidationContext,
+        payload: &Payload,
+        update_rule_state: bool,
+        rule_set_state_pda: &Option<&AccountInfo>,
+        rule_authority: &Option<&AccountInfo>,
+    ) -> (bool, RuleError) {
+        match self {
+            // Composite rules are validated by `validate_node`.
+            Rule::All { .. } | Rule::Any { .. } | Rule::Not { .. } | Rule::IfThen { .. } => {
+                (false, RuleSetError::UnexpectedRuleSetFailure.into())
             }
             Rule::AdditionalSigner { account } => {
                 msg!("Validating AdditionalSigner");
-                if let Some(signer) = accounts.get(account) {
-                    (signer.is_signer, self.to_error())
+                if let Some(is_signer) = context.is_signer(account) {
+                    (is_signer, self.to_error().into())
                 } else {
-                    (false, RuleSetError::MissingAccount.into())
+                    (false, RuleError::MissingAccount { key: *account })
                 }
             }
+            Rule::AdditionalSignerList { signers, threshold } => {
+                msg!("Validating AdditionalSignerList");
+                let signed = signers
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, signer)| {
+                        // Skip repeats of a `Pubkey` listed earlier.
+                        !signers[..*index].contains(signer)
+                            && context.is_signer(signer).unwrap_or(false)
+                    })
+                    .count();
+
+                (signed >= *threshold as usize, self.to_error().into())
+            }
             Rule::PubkeyMatch { pubkey, field } => {
                 msg!("Validating PubkeyMatch");
 
                 let key = match payload.get_pubkey(field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(field)),
                 };
 
                 if key == pubkey {
-                    (true, self.to_error())
+                    (true, self.to_error().into())
                 } else {
-                    (false, self.to_error())
+                    (false, self.to_error().into())
                 }
             }
             Rule::PubkeyListMatch { pubkeys, field } => {
@@ -325,41 +773,71 @@ impl Rule {
                 for field in fields {
                     let key = match payload.get_pubkey(&field.to_owned()) {
                         Some(pubkey) => pubkey,
-                        _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                        _ => return (false, RuleError::missing_payload_value(field)),
                     };
 
                     if pubkeys.iter().any(|pubkey| pubkey == key) {
-                        return (true, self.to_error());
+                        return (true, self.to_error().into());
                     }
                 }
 
-                (false, self.to_error())
+                (false, self.to_error().into())
+            }
+            Rule::PubkeyListDeny { pubkeys, field } => {
+                msg!("Validating PubkeyListDeny");
+
+                let fields = field.split('|').collect::<Vec<&str>>();
+
+                for field in fields {
+                    let key = match payload.get_pubkey(&field.to_owned()) {
+                        Some(pubkey) => pubkey,
+                        _ => return (false, RuleError::missing_payload_value(field)),
+                    };
+
+                    if pubkeys.iter().any(|pubkey| pubkey == key) {
+                        return (false, self.to_error().into());
+                    }
+                }
+
+                (true, self.to_error().into())
             }
             Rule::PubkeyTreeMatch {
                 root,
                 pubkey_field,
                 proof_field,
+                leaf_is_hashed,
             } => {
                 msg!("Validating PubkeyTreeMatch");
 
                 // Get the `Pubkey` we are checking from the payload.
                 let leaf = match payload.get_pubkey(pubkey_field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(pubkey_field)),
                 };
 
                 // Get the Merkle proof from the payload.
                 let merkle_proof = match payload.get_merkle_proof(proof_field) {
                     Some(merkle_proof) => merkle_proof,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(proof_field)),
+                };
+
+                // Bound the work done hashing the proof.
+                if merkle_proof.proof.len() > MAX_PROOF_DEPTH {
+                    return (false, RuleSetError::ProofTooLong.into());
+                }
+
+                // Hash the leaf first if the tree stores hashed leaves.
+                let leaf = if *leaf_is_hashed {
+                    hash_leaf(leaf)
+                } else {
+                    leaf.to_bytes()
                 };
 
                 // Check if the computed hash (root) is equal to the root in the rule.
-                let computed_root = compute_merkle_root(leaf, merkle_proof);
-                if computed_root == *root {
-                    (true, self.to_error())
+                if verify(&leaf, merkle_proof.proof(), root) {
+                    (true, self.to_error().into())
                 } else {
-                    (false, self.to_error())
+                    (false, self.to_error().into())
                 }
             }
             Rule::PDAMatch {
@@ -372,24 +850,24 @@ impl Rule {
                 // Get the PDA from the payload.
                 let account = match payload.get_pubkey(pda_field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(pda_field)),
                 };
 
                 // Get the derivation seeds from the payload.
                 let seeds = match payload.get_seeds(seeds_field) {
                     Some(seeds) => seeds,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(seeds_field)),
                 };
 
                 // Get the program ID to use for the PDA derivation from the Rule.
                 let program = match program {
                     // If the Pubkey is stored in the rule, use that value.
-                    Some(program) => program,
+                    Some(program) => *program,
                     None => {
                         // If one is not stored, then assume the program ID is the account owner.
-                        match accounts.get(account) {
-                            Some(account) => account.owner,
-                            _ => return (false, RuleSetError::MissingAccount.into()),
+                        match context.account_owner(account) {
+                            Some(owner) => owner,
+                            _ => return (false, RuleError::MissingAccount { key: *account }),
                         }
                     }
                 };
@@ -401,10 +879,10 @@ impl Rule {
                     .map(Vec::as_slice)
                     .collect::<Vec<&[u8]>>();
 
-                if let Ok(_bump) = assert_derivation(program, account, &vec_of_slices) {
-                    (true, self.to_error())
+                if let Ok(_bump) = assert_derivation(&program, account, &vec_of_slices) {
+                    (true, self.to_error().into())
                 } else {
-                    (false, self.to_error())
+                    (false, self.to_error().into())
                 }
             }
             Rule::ProgramOwned { program, field } => {
@@ -412,33 +890,71 @@ impl Rule {
 
                 let key = match payload.get_pubkey(field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(field)),
                 };
 
-                if let Some(account) = accounts.get(key) {
-                    let data = match account.data.try_borrow() {
-                        Ok(data) => data,
-                        Err(_) => return (false, ProgramError::AccountBorrowFailed),
-                    };
+                let (owner, data_is_zeroed) = match (
+                    context.account_owner(key),
+                    context.account_data_is_zeroed(key),
+                ) {
+                    (Some(owner), Some(data_is_zeroed)) => (owner, data_is_zeroed),
+                    _ => return (false, RuleError::MissingAccount { key: *key }),
+                };
 
-                    if is_zeroed(&data) {
-                        // Print helpful errors.
-                        if data.len() == 0 {
-                            msg!("Account data is empty");
-                        } else {
-                            msg!("Account data is zeroed");
-                        }
+                if data_is_zeroed {
+                    msg!("Account data is empty or zeroed");
 
-                        // Account must have nonzero data to count as program-owned.
-                        return (false, self.to_error());
-                    } else if *account.owner == *program {
-                        return (true, self.to_error());
-                    }
+                    // Account must have nonzero data to count as program-owned.
+                    (false, self.to_error().into())
+                } else if owner == *program {
+                    (true, self.to_error().into())
                 } else {
-                    return (false, RuleSetError::MissingAccount.into());
+                    (
+                        false,
+                        RuleError::ProgramOwnerMismatch {
+                            key: *key,
+                            expected: *program,
+                            actual: owner,
+                        },
+                    )
                 }
+            }
+            Rule::ProgramOwnedAtIndex {
+                program,
+                account_index,
+            } => {
+                msg!("Validating ProgramOwnedAtIndex");
+
+                let key = match context.account_key_at(*account_index as usize) {
+                    Some(key) => key,
+                    _ => return (false, RuleSetError::MissingAccount.into()),
+                };
 
-                (false, self.to_error())
+                let (owner, data_is_zeroed) = match (
+                    context.account_owner(&key),
+                    context.account_data_is_zeroed(&key),
+                ) {
+                    (Some(owner), Some(data_is_zeroed)) => (owner, data_is_zeroed),
+                    _ => return (false, RuleError::MissingAccount { key }),
+                };
+
+                if data_is_zeroed {
+                    msg!("Account data is empty or zeroed");
+
+                    // Account must have nonzero data to count as program-owned.
+                    (false, self.to_error().into())
+                } else if owner == *program {
+                    (true, self.to_error().into())
+                } else {
+                    (
+                        false,
+                        RuleError::ProgramOwnerMismatch {
+                            key,
+                            expected: *program,
+                            actual: owner,
+                        },
+                    )
+                }
             }
             Rule::ProgramOwnedList { programs, field } => {
                 msg!("Validating ProgramOwnedList");
@@ -448,32 +964,25 @@ impl Rule {
                 for field in fields {
                     let key = match payload.get_pubkey(&field.to_string()) {
                         Some(pubkey) => pubkey,
-                        _ => return (false, RuleSetError::MissingPayloadValue.into()),
-                    };
-
-                    let account = match accounts.get(key) {
-                        Some(account) => account,
-                        _ => return (false, RuleSetError::MissingAccount.into()),
+                        _ => return (false, RuleError::missing_payload_value(field)),
                     };
 
-                    let data = match account.data.try_borrow() {
-                        Ok(data) => data,
-                        Err(_) => return (false, ProgramError::AccountBorrowFailed),
+                    let (owner, data_is_zeroed) = match (
+                        context.account_owner(key),
+                        context.account_data_is_zeroed(key),
+                    ) {
+                        (Some(owner), Some(data_is_zeroed)) => (owner, data_is_zeroed),
+                        _ => return (false, RuleError::MissingAccount { key: *key }),
                     };
 
-                    if is_zeroed(&data) {
-                        // Print helpful errors.
-                        if data.len() == 0 {
-                            msg!("Account data is empty");
-                        } else {
-                            msg!("Account data is zeroed");
-                        }
-                    } else if programs.iter().any(|program| *account.owner == *program) {
+                    if data_is_zeroed {
+                        msg!("Account data is empty or zeroed");
+                    } else if programs.iter().any(|program| owner == *program) {
                         // Account owner must be on the list.
-                        return (true, self.to_error());
+                        return (true, self.to_error().into());
                     }
                 }
-                (false, self.to_error())
+                (false, self.to_error().into())
             }
             Rule::ProgramOwnedTree {
                 root,
@@ -485,47 +994,43 @@ impl Rule {
                 // Get the `Pubkey` we are checking from the payload.
                 let key = match payload.get_pubkey(pubkey_field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
-                };
-
-                // Get the `AccountInfo` struct for the `Pubkey`.
-                let account = match accounts.get(key) {
-                    Some(account) => account,
-                    _ => return (false, RuleSetError::MissingAccount.into()),
+                    _ => return (false, RuleError::missing_payload_value(pubkey_field)),
                 };
 
-                let data = match account.data.try_borrow() {
-                    Ok(data) => data,
-                    Err(_) => return (false, ProgramError::AccountBorrowFailed),
+                // Get the owner of the account for the `Pubkey`.
+                let (owner, data_is_zeroed) = match (
+                    context.account_owner(key),
+                    context.account_data_is_zeroed(key),
+                ) {
+                    (Some(owner), Some(data_is_zeroed)) => (owner, data_is_zeroed),
+                    _ => return (false, RuleError::MissingAccount { key: *key }),
                 };
 
                 // Account must have nonzero data to count as program-owned.
-                if is_zeroed(&data) {
-                    // Print helpful errors.
-                    if data.len() == 0 {
-                        msg!("Account data is empty");
-                    } else {
-                        msg!("Account data is zeroed");
-                    }
-
-                    return (false, self.to_error());
+                if data_is_zeroed {
+                    msg!("Account data is empty or zeroed");
+                    return (false, self.to_error().into());
                 }
 
                 // The account owner is the leaf.
-                let leaf = account.owner;
+                let leaf = &owner;
 
                 // Get the Merkle proof from the payload.
                 let merkle_proof = match payload.get_merkle_proof(proof_field) {
                     Some(merkle_proof) => merkle_proof,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(proof_field)),
                 };
 
+                // Bound the work done hashing the proof.
+                if merkle_proof.proof.len() > MAX_PROOF_DEPTH {
+                    return (false, RuleSetError::ProofTooLong.into());
+                }
+
                 // Check if the computed hash (root) is equal to the root in the rule.
-                let computed_root = compute_merkle_root(leaf, merkle_proof);
-                if computed_root == *root {
-                    (true, self.to_error())
+                if verify_proof(leaf, merkle_proof, root) {
+                    (true, self.to_error().into())
                 } else {
-                    (false, self.to_error())
+                    (false, self.to_error().into())
                 }
             }
             Rule::Amount {
@@ -534,25 +1039,269 @@ impl Rule {
                 field,
             } => {
                 msg!("Validating Amount");
-                if let Some(payload_amount) = &payload.get_amount(field) {
-                    let operator_fn = match operator {
-                        CompareOp::Lt => PartialOrd::lt,
-                        CompareOp::LtEq => PartialOrd::le,
-                        CompareOp::Eq => PartialEq::eq,
-                        CompareOp::Gt => PartialOrd::gt,
-                        CompareOp::GtEq => PartialOrd::ge,
+                if let Some(payload_amount) = payload.get_amount(field) {
+                    (
+                        operator.compare(payload_amount, *rule_amount),
+                        self.to_error().into(),
+                    )
+                } else {
+                    (false, RuleError::missing_payload_value(field))
+                }
+            }
+            Rule::AmountSum {
+                fields,
+                operator,
+                amount: rule_amount,
+            } => {
+                msg!("Validating AmountSum");
+                let mut total: u64 = 0;
+                for field in fields {
+                    let payload_amount = match payload.get_amount(field) {
+                        Some(amount) => amount,
+                        None => return (false, RuleError::missing_payload_value(field)),
                     };
+                    total = match total.checked_add(payload_amount) {
+                        Some(total) => total,
+                        None => return (false, RuleSetError::NumericalOverflow.into()),
+                    };
+                }
 
-                    if operator_fn(payload_amount, rule_amount) {
-                        (true, self.to_error())
-                    } else {
-                        (false, self.to_error())
-                    }
-                } else {
-                    (false, RuleSetError::MissingPayloadValue.into())
+                (
+                    operator.compare(total, *rule_amount),
+                    self.to_error().into(),
+                )
+            }
+            Rule::AmountFromTokenAccount {
+                field,
+                operator,
+                amount: rule_amount,
+            } => {
+                msg!("Validating AmountFromTokenAccount");
+
+                let key = match payload.get_pubkey(field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(field)),
+                };
+
+                let owner = match context.account_owner(key) {
+                    Some(owner) => owner,
+                    None => return (false, RuleError::MissingAccount { key: *key }),
+                };
+
+                // Only the SPL Token program can be trusted to keep the amount up to date.
+                if owner != SPL_TOKEN_PROGRAM_ID {
+                    return (
+                        false,
+                        RuleError::ProgramOwnerMismatch {
+                            key: *key,
+                            expected: SPL_TOKEN_PROGRAM_ID,
+                            actual: owner,
+                        },
+                    );
+                }
+
+                let mut amount = [0u8; 8];
+                if !context.read_account_data(key, TOKEN_ACCOUNT_AMOUNT_OFFSET, &mut amount) {
+                    msg!("Account data is too short");
+                    return (false, self.to_error().into());
                 }
+
+                (
+                    operator.compare(u64::from_le_bytes(amount), *rule_amount),
+                    self.to_error().into(),
+                )
             }
-            Rule::Frequency { authority } => {
+            Rule::PubkeyTreeMatchAny {
+                roots,
+                pubkey_field,
+                proof_field,
+                root_index_field,
+            } => {
+                msg!("Validating PubkeyTreeMatchAny");
+
+                // Get the `Pubkey` we are checking from the payload.
+                let leaf = match payload.get_pubkey(pubkey_field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(pubkey_field)),
+                };
+
+                // Get the Merkle proof from the payload.
+                let merkle_proof = match payload.get_merkle_proof(proof_field) {
+                    Some(merkle_proof) => merkle_proof,
+                    _ => return (false, RuleError::missing_payload_value(proof_field)),
+                };
+
+                // Get the index of the root the proof is for from the payload.
+                let root_index = match payload.get_amount(root_index_field) {
+                    Some(root_index) => root_index,
+                    _ => return (false, RuleError::missing_payload_value(root_index_field)),
+                };
+
+                // An index past the end of `roots` selects no tree.
+                let root = match usize::try_from(root_index)
+                    .ok()
+                    .and_then(|root_index| roots.get(root_index))
+                {
+                    Some(root) => root,
+                    None => return (false, self.to_error().into()),
+                };
+
+                // Bound the work done hashing the proof.
+                if merkle_proof.proof.len() > MAX_PROOF_DEPTH {
+                    return (false, RuleSetError::ProofTooLong.into());
+                }
+
+                // Check if the computed hash (root) is equal to the selected root in the rule.
+                (
+                    verify_proof(leaf, merkle_proof, root),
+                    self.to_error().into(),
+                )
+            }
+            Rule::PubkeyMatchFromData {
+                field,
+                source_field,
+                offset,
+            } => {
+                msg!("Validating PubkeyMatchFromData");
+
+                let key = match payload.get_pubkey(field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(field)),
+                };
+
+                let source = match payload.get_pubkey(source_field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(source_field)),
+                };
+
+                if context.account_data_len(source).is_none() {
+                    return (false, RuleError::MissingAccount { key: *source });
+                }
+
+                let mut expected = [0u8; PUBKEY_BYTES];
+                if !context.read_account_data(source, *offset, &mut expected) {
+                    msg!("Account data is too short");
+                    return (false, self.to_error().into());
+                }
+
+                (key.as_ref() == expected.as_slice(), self.to_error().into())
+            }
+            Rule::PubkeyTreeMatchFromAccount {
+                root,
+                pubkey_field,
+                proof_field,
+            } => {
+                msg!("Validating PubkeyTreeMatchFromAccount");
+
+                // Get the account we are checking from the payload.
+                let key = match payload.get_pubkey(pubkey_field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(pubkey_field)),
+                };
+
+                // The leaf is only trusted if the account itself was provided.
+                if context.account_owner(key).is_none() {
+                    return (false, RuleError::MissingAccount { key: *key });
+                }
+
+                // Get the Merkle proof from the payload.
+                let merkle_proof = match payload.get_merkle_proof(proof_field) {
+                    Some(merkle_proof) => merkle_proof,
+                    _ => return (false, RuleError::missing_payload_value(proof_field)),
+                };
+
+                // Bound the work done hashing the proof.
+                if merkle_proof.proof.len() > MAX_PROOF_DEPTH {
+                    return (false, RuleSetError::ProofTooLong.into());
+                }
+
+                // Check if the computed hash (root) is equal to the root in the rule.
+                (
+                    verify_proof(key, merkle_proof, root),
+                    self.to_error().into(),
+                )
+            }
+            Rule::LastUpdateWithin {
+                field,
+                offset,
+                max_age,
+            } => {
+                msg!("Validating LastUpdateWithin");
+
+                let key = match payload.get_pubkey(field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(field)),
+                };
+
+                if context.account_data_len(key).is_none() {
+                    return (false, RuleError::MissingAccount { key: *key });
+                }
+
+                let mut timestamp = [0u8; 8];
+                if !context.read_account_data(key, *offset, &mut timestamp) {
+                    msg!("Account data is too short");
+                    return (false, self.to_error().into());
+                }
+                let last_update = i64::from_le_bytes(timestamp);
+
+                let current_time = match context.clock() {
+                    Some(clock) => clock.unix_timestamp,
+                    None => return (false, ProgramError::UnsupportedSysvar.into()),
+                };
+
+                match current_time.checked_sub(last_update) {
+                    Some(age) => (age <= *max_age, self.to_error().into()),
+                    None => (false, RuleSetError::NumericalOverflow.into()),
+                }
+            }
+            Rule::DataMatch {
+                account_field,
+                bytes_field,
+                offset,
+                expected_len,
+            } => {
+                msg!("Validating DataMatch");
+
+                let key = match payload.get_pubkey(account_field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(account_field)),
+                };
+
+                let expected = match payload.get_bytes(bytes_field) {
+                    Some(bytes) => bytes,
+                    _ => return (false, RuleError::missing_payload_value(bytes_field)),
+                };
+
+                let data_len = match context.account_data_len(key) {
+                    Some(data_len) => data_len,
+                    None => return (false, RuleError::MissingAccount { key: *key }),
+                };
+
+                // Check the lengths before anything is allocated for the account data.
+                if expected.len() != *expected_len {
+                    msg!("Payload bytes are not the expected length");
+                    return (false, self.to_error().into());
+                }
+                if offset
+                    .checked_add(*expected_len)
+                    .map_or(true, |end| end > data_len)
+                {
+                    msg!("Account data is too short");
+                    return (false, self.to_error().into());
+                }
+
+                let mut data = vec![0; *expected_len];
+                if !context.read_account_data(key, *offset, &mut data) {
+                    return (false, self.to_error().into());
+                }
+
+                (data == expected, self.to_error().into())
+            }
+            Rule::Frequency {
+                authority,
+                period,
+                name,
+            } => {
                 msg!("Validating Frequency");
 
                 if let Some(rule_authority) = rule_authority {
@@ -565,11 +1314,62 @@ impl Rule {
                     return (false, RuleSetError::MissingAccount.into());
                 }
 
-                (false, RuleSetError::NotImplemented.into())
+                let rule_set_state_pda = match rule_set_state_pda {
+                    Some(rule_set_state_pda) => rule_set_state_pda,
+                    None => return (false, RuleSetError::MissingAccount.into()),
+                };
+
+                let current_time = match context.clock() {
+                    Some(clock) => clock.unix_timestamp,
+                    None => return (false, ProgramError::UnsupportedSysvar.into()),
+                };
+
+                // An empty state account means the rule has never been used.
+                if rule_set_state_pda.data_is_empty() {
+                    return (true, self.to_error().into());
+                }
+
+                let mut frequency_account =
+                    match FrequencyAccount::from_account_info(rule_set_state_pda) {
+                        Ok(frequency_account) => frequency_account,
+                        Err(err) => return (false, err.into()),
+                    };
+
+                let next_valid_time =
+                    match frequency_account.last_update_of(name).checked_add(*period) {
+                        Some(next_valid_time) => next_valid_time,
+                        None => return (false, RuleSetError::NumericalOverflow.into()),
+                    };
+
+                if current_time < next_valid_time {
+                    return (false, self.to_error().into());
+                }
+
+                if update_rule_state {
+                    frequency_account.update(name, current_time, *period);
+
+                    // Grow the account when a new slot is added.  The processor tops up its rent
+                    // once validation is done.
+                    let len = match frequency_account.try_to_vec() {
+                        Ok(data) => data.len(),
+                        Err(err) => return (false, ProgramError::from(err).into()),
+                    };
+                    if len > rule_set_state_pda.data_len() {
+                        if let Err(err) = rule_set_state_pda.realloc(len, false) {
+                            return (false, err.into());
+                        }
+                    }
+
+                    if let Err(err) = frequency_account.to_account_data(rule_set_state_pda) {
+                        return (false, err.into());
+                    }
+                }
+
+                (true, self.to_error().into())
             }
             Rule::Pass => {
                 msg!("Validating Pass");
-                (true, self.to_error())
+                (true, self.to_error().into())
             }
             Rule::IsWallet { field } => {
                 msg!("Validating IsWallet");
@@ -577,25 +1377,22 @@ impl Rule {
                 // Get the `Pubkey` we are checking from the payload.
                 let key = match payload.get_pubkey(field) {
                     Some(pubkey) => pubkey,
-                    _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                    _ => return (false, RuleError::missing_payload_value(field)),
                 };
 
-                // Get the `AccountInfo` struct for the `Pubkey` and verify that
-                // its owner is the System Program.
-                if let Some(account) = accounts.get(key) {
-                    if *account.owner != system_program::ID {
-                        // TODO: Change error return to commented line after on-curve syscall
-                        // available.
-                        return (false, RuleSetError::NotImplemented.into());
-                        //return (false, self.to_error());
+                // Get the owner of the account for the `Pubkey` and verify that it is the
+                // System Program and that the account holds no data.
+                match (context.account_owner(key), context.account_data_len(key)) {
+                    (Some(owner), Some(data_len)) => {
+                        // TODO: Also check `is_on_curve()` after on-curve syscall available, so
+                        // that system-owned PDAs do not count as wallets.
+                        (
+                            owner == system_program::ID && data_len == 0,
+                            self.to_error().into(),
+                        )
                     }
-                } else {
-                    return (false, RuleSetError::MissingAccount.into());
+                    _ => (false, RuleError::MissingAccount { key: *key }),
                 }
-
-                // TODO: Uncomment call to `is_on_curve()` after on-curve sycall available.
-                (false, RuleSetError::NotImplemented.into())
-                //(is_on_curve(key), self.to_error())
             }
             Rule::ProgramOwnedSet { programs, field } => {
                 msg!("Validating ProgramOwnedSet");
@@ -605,61 +1402,520 @@ impl Rule {
                 for field in fields {
                     let key = match payload.get_pubkey(&field.to_string()) {
                         Some(pubkey) => pubkey,
-                        _ => return (false, RuleSetError::MissingPayloadValue.into()),
+                        _ => return (false, RuleError::missing_payload_value(field)),
                     };
 
-                    let account = match accounts.get(key) {
-                        Some(account) => account,
-                        _ => return (false, RuleSetError::MissingAccount.into()),
+                    let (owner, data_is_zeroed) = match (
+                        context.account_owner(key),
+                        context.account_data_is_zeroed(key),
+                    ) {
+                        (Some(owner), Some(data_is_zeroed)) => (owner, data_is_zeroed),
+                        _ => return (false, RuleError::MissingAccount { key: *key }),
                     };
 
-                    let data = match account.data.try_borrow() {
-                        Ok(data) => data,
-                        Err(_) => return (false, ProgramError::AccountBorrowFailed),
-                    };
-
-                    if is_zeroed(&data) {
-                        // Print helpful errors.
-                        if data.len() == 0 {
-                            msg!("Account data is empty");
-                        } else {
-                            msg!("Account data is zeroed");
-                        }
-                    } else if programs.contains(account.owner) {
+                    if data_is_zeroed {
+                        msg!("Account data is empty or zeroed");
+                    } else if programs.contains(&owner) {
                         // Account owner must be in the set.
-                        return (true, self.to_error());
+                        return (true, self.to_error().into());
                     }
                 }
 
-                (false, self.to_error())
+                (false, self.to_error().into())
             }
             Rule::Namespace => {
                 msg!("Validating Namespace");
-                (false, self.to_error())
+                (false, self.to_error().into())
+            }
+            Rule::Timestamp { start, end } => {
+                msg!("Validating Timestamp");
+
+                let current_time = match context.clock() {
+                    Some(clock) => clock.unix_timestamp,
+                    None => return (false, ProgramError::UnsupportedSysvar.into()),
+                };
+
+                let after_start = start.map_or(true, |start| current_time >= start);
+                let before_end = end.map_or(true, |end| current_time <= end);
+
+                if after_start && before_end {
+                    (true, self.to_error().into())
+                } else {
+                    (false, self.to_error().into())
+                }
+            }
+            Rule::Slot { min, max } => {
+                msg!("Validating Slot");
+
+                let current_slot = match context.clock() {
+                    Some(clock) => clock.slot,
+                    None => return (false, ProgramError::UnsupportedSysvar.into()),
+                };
+
+                let above_min = min.map_or(true, |min| current_slot >= min);
+                let below_max = max.map_or(true, |max| current_slot <= max);
+
+                if above_min && below_max {
+                    (true, self.to_error().into())
+                } else {
+                    (false, self.to_error().into())
+                }
+            }
+            Rule::ProgramOwnedWithData {
+                program,
+                field,
+                min_data_len,
+            } => {
+                msg!("Validating ProgramOwnedWithData");
+
+                let key = match payload.get_pubkey(field) {
+                    Some(pubkey) => pubkey,
+                    _ => return (false, RuleError::missing_payload_value(field)),
+                };
+
+                let (owner, data_is_zeroed, data_len) = match (
+                    context.account_owner(key),
+                    context.account_data_is_zeroed(key),
+                    context.account_data_len(key),
+                ) {
+                    (Some(owner), Some(data_is_zeroed), Some(data_len)) => {
+                        (owner, data_is_zeroed, data_len)
+                    }
+                    _ => return (false, RuleError::MissingAccount { key: *key }),
+                };
+
+                if data_is_zeroed {
+                    msg!("Account data is empty or zeroed");
+
+                    // Account must have nonzero data to count as program-owned.
+                    (false, self.to_error().into())
+                } else if data_len < *min_data_len {
+                    msg!("Account data is too short");
+                    (false, self.to_error().into())
+                } else if owner == *program {
+                    (true, self.to_error().into())
+                } else {
+                    (
+                        false,
+                        RuleError::ProgramOwnerMismatch {
+                            key: *key,
+                            expected: *program,
+                            actual: owner,
+                        },
+                    )
+                }
             }
         }
     }
 
+    /// Check that the rule tree is well-formed, recursing into any contained rules.  This is
+    /// called when a rule is added to a `RuleSet` so that malformed rules are caught before the
+    /// `RuleSet` is put on chain.
+    pub fn assert_valid(&self) -> ProgramResult {
+        // Check the depth first so that the recursion below is bounded.
+        self.assert_depth()?;
+        self.assert_node_valid()
+    }
+
+    /// The rules directly contained in a composite rule, in order, or `None` for a leaf rule.
+    pub fn children(&self) -> Option<Vec<&Rule>> {
+        match self {
+            Rule::All { rules } | Rule::Any { rules } => Some(rules.iter().collect()),
+            Rule::Not { rule } => Some(vec![rule]),
+            Rule::IfThen {
+                condition,
+                then_rule,
+                else_rule,
+            } => Some(
+                [condition, then_rule]
+                    .into_iter()
+                    .chain(else_rule)
+                    .map(Box::as_ref)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Walk the rule tree depth first, passing each rule to `visitor`.  A composite rule is
+    /// visited before its children, which are visited in order.  The tree is walked with an
+    /// explicit stack, so this is safe to call on a tree of any depth.
+    pub fn visit<V: RuleVisitor>(&self, visitor: &mut V) {
+        let mut stack = vec![self];
+        while let Some(rule) = stack.pop() {
+            match rule.children() {
+                Some(rules) => {
+                    visitor.visit_composite(rule);
+                    stack.extend(rules.into_iter().rev());
+                }
+                None => visitor.visit_leaf(rule),
+            }
+        }
+    }
+
+    /// Check that no more than `MAX_RULE_DEPTH` composite rules are nested inside each other.
+    /// The tree is walked with an explicit stack, so this is safe to call on a tree of any depth.
+    pub fn assert_depth(&self) -> ProgramResult {
+        // Each entry holds a rule and the number of composite rules it is nested in.
+        let mut stack = vec![(self, 0)];
+        while let Some((rule, depth)) = stack.pop() {
+            let rules = match rule.children() {
+                Some(rules) => rules,
+                None => continue,
+            };
+
+            if depth == MAX_RULE_DEPTH {
+                return Err(RuleSetError::RuleTreeTooDeep.into());
+            }
+            stack.extend(rules.into_iter().map(|rule| (rule, depth + 1)));
+        }
+
+        Ok(())
+    }
+
+    // Check that one node of a rule tree is well-formed, recursing into any contained rules.
+    fn assert_node_valid(&self) -> ProgramResult {
+        match self {
+            Rule::All { rules } => rules.iter().try_for_each(Rule::assert_node_valid),
+            Rule::Any { rules } => {
+                // An empty `Any` could never pass.
+                if rules.is_empty() {
+                    return Err(RuleSetError::EmptyAnyRule.into());
+                }
+                rules.iter().try_for_each(Rule::assert_node_valid)
+            }
+            Rule::Not { rule } => rule.assert_node_valid(),
+            Rule::IfThen {
+                condition,
+                then_rule,
+                else_rule,
+            } => [condition, then_rule]
+                .into_iter()
+                .chain(else_rule)
+                .try_for_each(|rule| rule.assert_node_valid()),
+            Rule::PubkeyListMatch { pubkeys, .. } | Rule::PubkeyListDeny { pubkeys, .. }
+                if pubkeys.len() > MAX_PUBKEY_LIST_LEN =>
+            {
+                Err(RuleSetError::PubkeyListTooLong.into())
+            }
+            Rule::AdditionalSignerList { signers, threshold }
+                if *threshold == 0 || *threshold as usize > signers.len() =>
+            {
+                Err(RuleSetError::InvalidSignerThreshold.into())
+            }
+            Rule::PubkeyTreeMatchAny { roots, .. } if roots.len() > MAX_TREE_ROOTS => {
+                Err(RuleSetError::TooManyTreeRoots.into())
+            }
+            // The name is stored in the `FrequencyAccount` each time a new slot is added.
+            Rule::Frequency { name, .. } if name.len() > MAX_NAME_LENGTH => {
+                Err(RuleSetError::NameTooLong.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The name of the rule variant, as used in validation logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::All { .. } => "All",
+            Rule::Any { .. } => "Any",
+            Rule::Not { .. } => "Not",
+            Rule::AdditionalSigner { .. } => "AdditionalSigner",
+            Rule::PubkeyMatch { .. } => "PubkeyMatch",
+            Rule::PubkeyListMatch { .. } => "PubkeyListMatch",
+            Rule::PubkeyTreeMatch { .. } => "PubkeyTreeMatch",
+            Rule::PDAMatch { .. } => "PDAMatch",
+            Rule::ProgramOwned { .. } => "ProgramOwned",
+            Rule::ProgramOwnedList { .. } => "ProgramOwnedList",
+            Rule::ProgramOwnedTree { .. } => "ProgramOwnedTree",
+            Rule::Amount { .. } => "Amount",
+            Rule::Frequency { .. } => "Frequency",
+            Rule::IsWallet { .. } => "IsWallet",
+            Rule::Pass => "Pass",
+            Rule::ProgramOwnedSet { .. } => "ProgramOwnedSet",
+            Rule::Namespace => "Namespace",
+            Rule::Timestamp { .. } => "Timestamp",
+            Rule::Slot { .. } => "Slot",
+            Rule::ProgramOwnedWithData { .. } => "ProgramOwnedWithData",
+            Rule::AmountSum { .. } => "AmountSum",
+            Rule::PubkeyTreeMatchFromAccount { .. } => "PubkeyTreeMatchFromAccount",
+            Rule::LastUpdateWithin { .. } => "LastUpdateWithin",
+            Rule::AdditionalSignerList { .. } => "AdditionalSignerList",
+            Rule::DataMatch { .. } => "DataMatch",
+            Rule::PubkeyListDeny { .. } => "PubkeyListDeny",
+            Rule::ProgramOwnedAtIndex { .. } => "ProgramOwnedAtIndex",
+            Rule::IfThen { .. } => "IfThen",
+            Rule::AmountFromTokenAccount { .. } => "AmountFromTokenAccount",
+            Rule::PubkeyTreeMatchAny { .. } => "PubkeyTreeMatchAny",
+            Rule::PubkeyMatchFromData { .. } => "PubkeyMatchFromData",
+        }
+    }
+
     /// Convert the rule to a corresponding error resulting from the rule failure.
     pub fn to_error(&self) -> ProgramError {
         match self {
             Rule::All { .. }
             | Rule::Any { .. }
-            | Rule::Not { .. }
+            | Rule::IfThen { .. }
             | Rule::Pass
             | Rule::Namespace => RuleSetError::UnexpectedRuleSetFailure.into(),
+            Rule::Not { .. } => RuleSetError::NotCheckFailed.into(),
             Rule::AdditionalSigner { .. } => RuleSetError::AdditionalSignerCheckFailed.into(),
-            Rule::PubkeyMatch { .. } => RuleSetError::PubkeyMatchCheckFailed.into(),
+            Rule::PubkeyMatch { .. } | Rule::PubkeyMatchFromData { .. } => {
+                RuleSetError::PubkeyMatchCheckFailed.into()
+            }
             Rule::PubkeyListMatch { .. } => RuleSetError::PubkeyListMatchCheckFailed.into(),
-            Rule::PubkeyTreeMatch { .. } => RuleSetError::PubkeyTreeMatchCheckFailed.into(),
+            Rule::PubkeyTreeMatch { .. }
+            | Rule::PubkeyTreeMatchFromAccount { .. }
+            | Rule::PubkeyTreeMatchAny { .. } => RuleSetError::PubkeyTreeMatchCheckFailed.into(),
             Rule::PDAMatch { .. } => RuleSetError::PDAMatchCheckFailed.into(),
-            Rule::ProgramOwned { .. } => RuleSetError::ProgramOwnedCheckFailed.into(),
+            Rule::ProgramOwned { .. }
+            | Rule::ProgramOwnedWithData { .. }
+            | Rule::ProgramOwnedAtIndex { .. } => RuleSetError::ProgramOwnedCheckFailed.into(),
             Rule::ProgramOwnedList { .. } => RuleSetError::ProgramOwnedListCheckFailed.into(),
             Rule::ProgramOwnedTree { .. } => RuleSetError::ProgramOwnedTreeCheckFailed.into(),
-            Rule::Amount { .. } => RuleSetError::AmountCheckFailed.into(),
+            Rule::Amount { .. } | Rule::AmountSum { .. } | Rule::AmountFromTokenAccount { .. } => {
+                RuleSetError::AmountCheckFailed.into()
+            }
             Rule::Frequency { .. } => RuleSetError::FrequencyCheckFailed.into(),
             Rule::IsWallet { .. } => RuleSetError::IsWalletCheckFailed.into(),
             Rule::ProgramOwnedSet { .. } => RuleSetError::ProgramOwnedSetCheckFailed.into(),
+            Rule::Timestamp { .. } => RuleSetError::TimestampCheckFailed.into(),
+            Rule::Slot { .. } => RuleSetError::SlotCheckFailed.into(),
+            Rule::LastUpdateWithin { .. } => RuleSetError::LastUpdateCheckFailed.into(),
+            Rule::AdditionalSignerList { .. } => {
+                RuleSetError::AdditionalSignerListCheckFailed.into()
+            }
+            Rule::DataMatch { .. } => RuleSetError::DataMatchCheckFailed.into(),
+            Rule::PubkeyListDeny { .. } => RuleSetError::PubkeyListDenyCheckFailed.into(),
         }
     }
 }
+
+// The result of validating a rule: whether it passed, the error to return if it failed, and the
+// path to the failing rule, innermost rule first.
+type NodeResult = (bool, RuleError, Vec<String>);
+
+// The next thing `Rule::validate_node` does: start validating a rule, with whether it may update
+// rule state, or hand a result to the composite rule on top of its stack.
+enum Step<'a> {
+    Enter(&'a Rule, bool),
+    Done(NodeResult),
+}
+
+// A composite rule on the stack of `Rule::validate_node`, with how far it got through the rules
+// it contains.
+enum Frame<'a> {
+    All {
+        rule: &'a Rule,
+        rules: &'a [Rule],
+        index: usize,
+        update_rule_state: bool,
+    },
+    Any {
+        rules: &'a [Rule],
+        index: usize,
+        update_rule_state: bool,
+        last: Option<(RuleError, Vec<String>)>,
+    },
+    Not {
+        rule: &'a Rule,
+        negated: &'a Rule,
+    },
+    IfThen {
+        rule: &'a Rule,
+        condition: &'a Rule,
+        then_rule: &'a Rule,
+        else_rule: Option<&'a Rule>,
+        update_rule_state: bool,
+        // The branch being validated and its path segment, once `condition` was checked.
+        branch: Option<(&'a Rule, &'static str)>,
+    },
+}
+
+impl<'a> Frame<'a> {
+    // Start validating `rule`, or return `None` if it contains no other rules.
+    fn new(rule: &'a Rule, update_rule_state: bool) -> Option<Self> {
+        match rule {
+            Rule::All { rules } => {
+                msg!("Validating All");
+                Some(Frame::All {
+                    rule,
+                    rules,
+                    index: 0,
+                    update_rule_state,
+                })
+            }
+            Rule::Any { rules } => {
+                msg!("Validating Any");
+                Some(Frame::Any {
+                    rules,
+                    index: 0,
+                    update_rule_state,
+                    last: None,
+                })
+            }
+            Rule::Not { rule: negated } => {
+                msg!("Validating Not");
+                Some(Frame::Not { rule, negated })
+            }
+            Rule::IfThen {
+                condition,
+                then_rule,
+                else_rule,
+            } => {
+                msg!("Validating IfThen");
+                Some(Frame::IfThen {
+                    rule,
+                    condition,
+                    then_rule,
+                    else_rule: else_rule.as_deref(),
+                    update_rule_state,
+                    branch: None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Take the result of the contained rule validated last, or `None` when the frame was just
+    // entered, and decide which rule to validate next or the result of the whole frame.
+    fn resume(&mut self, result: Option<NodeResult>) -> Step<'a> {
+        match self {
+            Frame::All {
+                rule,
+                rules,
+                index,
+                update_rule_state,
+            } => {
+                if let Some(mut result) = result {
+                    // Return failure on the first failing rule.
+                    if !result.0 {
+                        let failed = *index;
+                        push_failure_path(&mut result.2, &rules[failed], || {
+                            format!("All[{}]", failed)
+                        });
+                        return Step::Done(result);
+                    }
+                    *index += 1;
+                }
+
+                // Return pass if and only if all rules passed.
+                match rules.get(*index) {
+                    Some(next) => Step::Enter(next, *update_rule_state),
+                    None => Step::Done((true, rule.to_error().into(), Vec::new())),
+                }
+            }
+            Frame::Any {
+                rules,
+                index,
+                update_rule_state,
+                last,
+            } => {
+                if let Some((passed, err, mut path)) = result {
+                    // Return pass on the first passing rule.
+                    if passed {
+                        return Step::Done((passed, err, path));
+                    }
+
+                    // Save the last failure, but don't overwrite an existing last failure with
+                    // `RuleSetError::NotImplemented` as it can lead to a confusing result when
+                    // using `RuleSets` that have unimplemented rules in them.
+                    if last.is_none() || err != RuleSetError::NotImplemented.into() {
+                        let failed = *index;
+                        push_failure_path(&mut path, &rules[failed], || format!("Any[{}]", failed));
+                        *last = Some((err, path));
+                    }
+                    *index += 1;
+                }
+
+                if let Some(next) = rules.get(*index) {
+                    return Step::Enter(next, *update_rule_state);
+                }
+
+                // Return failure if and only if all rules failed.  Use the last failure.
+                match last.take() {
+                    Some((err, path)) => Step::Done((false, err, path)),
+                    None => Step::Done((
+                        false,
+                        RuleSetError::UnexpectedRuleSetFailure.into(),
+                        Vec::new(),
+                    )),
+                }
+            }
+            Frame::Not { rule, negated } => match result {
+                // The contained rule only passes when `Not` fails, so it must not update rule state.
+                None => Step::Enter(negated, false),
+                // Negate the result.  The contained rule's error is not returned because it
+                // describes a passing check.
+                Some((passed, _, _)) => Step::Done((!passed, rule.to_error().into(), Vec::new())),
+            },
+            Frame::IfThen {
+                rule,
+                condition,
+                then_rule,
+                else_rule,
+                update_rule_state,
+                branch,
+            } => match (result, *branch) {
+                // Only the branch that applies may update rule state.
+                (None, _) => Step::Enter(condition, false),
+                (Some((condition_passed, _, _)), None) => {
+                    // Pick the branch, passing if `condition` failed and there is no `else_rule`.
+                    let picked = match (condition_passed, *else_rule) {
+                        (true, _) => (*then_rule, "IfThen.then_rule"),
+                        (false, Some(else_rule)) => (else_rule, "IfThen.else_rule"),
+                        (false, None) => {
+                            return Step::Done((true, rule.to_error().into(), Vec::new()))
+                        }
+                    };
+                    *branch = Some(picked);
+                    Step::Enter(picked.0, *update_rule_state)
+                }
+                (Some(mut result), Some((branch, segment))) => {
+                    if !result.0 {
+                        push_failure_path(&mut result.2, branch, || segment.to_string());
+                    }
+                    Step::Done(result)
+                }
+            },
+        }
+    }
+}
+
+// Add a composite rule's `segment` to the failure path after one of its contained rules failed.
+// If the contained rule is not itself a composite, its path is still empty, so its name is added
+// first.
+#[cfg(feature = "log-rule-path")]
+fn push_failure_path(
+    failure_path: &mut Vec<String>,
+    failed_rule: &Rule,
+    segment: impl FnOnce() -> String,
+) {
+    if failure_path.is_empty() {
+        failure_path.push(failed_rule.name().to_string());
+    }
+    failure_path.push(segment());
+}
+
+#[cfg(not(feature = "log-rule-path"))]
+fn push_failure_path(
+    _failure_path: &mut [String],
+    _failed_rule: &Rule,
+    _segment: impl FnOnce() -> String,
+) {
+}
+
+// Log the path to the rule that failed validation, outermost rule first, for example
+// `All[1].Any[0].Amount failed`.
+#[cfg(feature = "log-rule-path")]
+fn log_failure_path(rule: &Rule, failure_path: &mut Vec<String>) {
+    if failure_path.is_empty() {
+        failure_path.push(rule.name().to_string());
+    }
+    failure_path.reverse();
+    msg!("{} failed", failure_path.join("."));
+}
+
+#[cfg(not(feature = "log-rule-path"))]
+fn log_failure_path(_rule: &Rule, _failure_path: &mut [String]) {}
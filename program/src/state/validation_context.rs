@@ -0,0 +1,268 @@
+use crate::utils::is_zeroed;
+use solana_program::{account_info::AccountInfo, clock::Clock, pubkey::Pubkey, sysvar::Sysvar};
+use std::{cell::RefCell, collections::HashMap};
+
+/// The account lookups needed by `Rule` validation.  Abstracting these lets the same validation
+/// logic run on-chain against the instruction's account infos, and off-chain against accounts
+/// the client already knows about.
+pub trait ValidationContext {
+    /// Get the owner of an account, or `None` if the account was not provided.
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey>;
+
+    /// Whether an account's data is empty or all zeroes, or `None` if the account was not
+    /// provided.
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool>;
+
+    /// Get the length of an account's data, or `None` if the account was not provided.
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize>;
+
+    /// Copy an account's data starting at `offset` into `buf`.  Returns `false` if the account
+    /// was not provided or its data does not cover `buf.len()` bytes from `offset`.
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool;
+
+    /// Whether an account signed, or `None` if the account was not provided.
+    fn is_signer(&self, key: &Pubkey) -> Option<bool>;
+
+    /// Get the current `Clock`, or `None` if it is not available.
+    fn clock(&self) -> Option<Clock>;
+
+    /// Get the key of the account at `index` in the order the accounts were provided, or `None`
+    /// if there is no account at that index or the order is not known.
+    fn account_key_at(&self, index: usize) -> Option<Pubkey>;
+}
+
+/// A `ValidationContext` backed by a map of account infos.  A map does not keep the order the
+/// accounts were passed in, so accounts cannot be looked up by index.
+impl ValidationContext for HashMap<Pubkey, &AccountInfo<'_>> {
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey> {
+        self.get(key).map(|account| *account.owner)
+    }
+
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool> {
+        // If the data cannot be borrowed, treat it as zeroed so that the account does not count
+        // as program-owned.
+        self.get(key)
+            .map(|account| match account.data.try_borrow() {
+                Ok(data) => is_zeroed(&data),
+                Err(_) => true,
+            })
+    }
+
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize> {
+        self.get(key).map(|account| account.data_len())
+    }
+
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool {
+        match self.get(key).map(|account| account.data.try_borrow()) {
+            Some(Ok(data)) => copy_data(&data, offset, buf),
+            _ => false,
+        }
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> Option<bool> {
+        self.get(key).map(|account| account.is_signer)
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        Clock::get().ok()
+    }
+
+    fn account_key_at(&self, _index: usize) -> Option<Pubkey> {
+        None
+    }
+}
+
+/// The on-chain `ValidationContext`, backed by the account infos passed to the instruction after
+/// its named accounts.  Accounts can be looked up by key or by their index in that list.
+pub struct AccountInfosContext<'a, 'info> {
+    keys: Vec<Pubkey>,
+    accounts: HashMap<Pubkey, &'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> AccountInfosContext<'a, 'info> {
+    /// Create a new `AccountInfosContext` from the accounts in the order they were passed in.
+    pub fn new(account_infos: &[&'a AccountInfo<'info>]) -> Self {
+        Self {
+            keys: account_infos.iter().map(|account| *account.key).collect(),
+            accounts: account_infos
+                .iter()
+                .map(|account| (*account.key, *account))
+                .collect(),
+        }
+    }
+}
+
+impl ValidationContext for AccountInfosContext<'_, '_> {
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey> {
+        self.accounts.account_owner(key)
+    }
+
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool> {
+        self.accounts.account_data_is_zeroed(key)
+    }
+
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize> {
+        self.accounts.account_data_len(key)
+    }
+
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool {
+        self.accounts.read_account_data(key, offset, buf)
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> Option<bool> {
+        self.accounts.is_signer(key)
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.accounts.clock()
+    }
+
+    fn account_key_at(&self, index: usize) -> Option<Pubkey> {
+        self.keys.get(index).copied()
+    }
+}
+
+/// A `ValidationContext` that remembers the owner of each account it looks up, so that a rule
+/// tree checking the same account in several rules resolves its owner once per validation.  Only
+/// owners are cached: account data can be written during validation, for example by `Frequency`,
+/// so data lookups are passed through to the wrapped context.
+pub(crate) struct OwnerCachingContext<'a> {
+    inner: &'a dyn ValidationContext,
+    owners: RefCell<HashMap<Pubkey, Option<Pubkey>>>,
+}
+
+impl<'a> OwnerCachingContext<'a> {
+    /// Wrap `inner` with an empty cache.
+    pub(crate) fn new(inner: &'a dyn ValidationContext) -> Self {
+        Self {
+            inner,
+            owners: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl ValidationContext for OwnerCachingContext<'_> {
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey> {
+        *self
+            .owners
+            .borrow_mut()
+            .entry(*key)
+            .or_insert_with(|| self.inner.account_owner(key))
+    }
+
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool> {
+        self.inner.account_data_is_zeroed(key)
+    }
+
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize> {
+        self.inner.account_data_len(key)
+    }
+
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool {
+        self.inner.read_account_data(key, offset, buf)
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> Option<bool> {
+        self.inner.is_signer(key)
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.inner.clock()
+    }
+
+    fn account_key_at(&self, index: usize) -> Option<Pubkey> {
+        self.inner.account_key_at(index)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// An account known to an `InMemoryValidationContext`.
+pub struct InMemoryAccount {
+    /// The program that owns the account.
+    pub owner: Pubkey,
+    /// The account data.
+    pub data: Vec<u8>,
+    /// Whether the account will sign the transaction.
+    pub is_signer: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A `ValidationContext` holding account state in memory, used to validate a `RuleSet`
+/// off-chain, for example as a client pre-flight check.
+pub struct InMemoryValidationContext {
+    keys: Vec<Pubkey>,
+    accounts: HashMap<Pubkey, InMemoryAccount>,
+    clock: Option<Clock>,
+}
+
+impl InMemoryValidationContext {
+    /// Create a new empty `InMemoryValidationContext`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts an account into the context.  If the context already had an account with this
+    /// key, the account is updated and the old account is returned.  Accounts are indexed in the
+    /// order they were first inserted, matching the order they would be passed to `Validate`.
+    pub fn insert(&mut self, key: Pubkey, account: InMemoryAccount) -> Option<InMemoryAccount> {
+        let old_account = self.accounts.insert(key, account);
+        if old_account.is_none() {
+            self.keys.push(key);
+        }
+        old_account
+    }
+
+    /// Set the `Clock` used by time-based `Rule`s.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+}
+
+impl ValidationContext for InMemoryValidationContext {
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey> {
+        self.accounts.get(key).map(|account| account.owner)
+    }
+
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool> {
+        self.accounts
+            .get(key)
+            .map(|account| is_zeroed(&account.data))
+    }
+
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize> {
+        self.accounts.get(key).map(|account| account.data.len())
+    }
+
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool {
+        match self.accounts.get(key) {
+            Some(account) => copy_data(&account.data, offset, buf),
+            None => false,
+        }
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> Option<bool> {
+        self.accounts.get(key).map(|account| account.is_signer)
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.clock.clone()
+    }
+
+    fn account_key_at(&self, index: usize) -> Option<Pubkey> {
+        self.keys.get(index).copied()
+    }
+}
+
+// Copy `data` starting at `offset` into `buf`, returning `false` if `data` is too short.
+fn copy_data(data: &[u8], offset: usize, buf: &mut [u8]) -> bool {
+    match offset
+        .checked_add(buf.len())
+        .and_then(|end| data.get(offset..end))
+    {
+        Some(src) => {
+            buf.copy_from_slice(src);
+            true
+        }
+        None => false,
+    }
+}
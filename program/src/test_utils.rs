@@ -0,0 +1,127 @@
+//! Helpers for the integration tests of programs that use `RuleSet`s, enabled by the
+//! `test-utils` feature.  They cover the common steps of a test: starting a `ProgramTest` with
+//! this program loaded, putting a `RuleSet` on chain, and processing `Validate` instructions.
+//!
+//! ```no_run
+//! use mpl_token_auth_rules::{
+//!     payload::Payload,
+//!     state::{Rule, RuleSetV1},
+//!     test_utils, TRANSFER,
+//! };
+//! use solana_program_test::tokio;
+//! use solana_sdk::{pubkey::Pubkey, signature::Signer};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut context = test_utils::program_test().start_with_context().await;
+//!
+//!     let mut rule_set = RuleSetV1::new("rule_set".to_string(), context.payer.pubkey());
+//!     rule_set.add(TRANSFER, Rule::Pass).unwrap();
+//!     let rule_set_pda = test_utils::create_rule_set(&mut context, &rule_set)
+//!         .await
+//!         .unwrap();
+//!
+//!     let validate_ix = test_utils::validate_ix(
+//!         rule_set_pda,
+//!         Pubkey::new_unique(),
+//!         TRANSFER,
+//!         Payload::default(),
+//!         vec![],
+//!     );
+//!     test_utils::process_instructions(&mut context, &[validate_ix], &[])
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+use crate::{
+    instruction::{
+        builders::{CreateOrUpdateBuilder, ValidateBuilder},
+        CreateOrUpdateArgs, InstructionBuilder, ValidateArgs,
+    },
+    payload::Payload,
+    pda::find_rule_set_address,
+    state::RuleSetV1,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+
+/// Create a `ProgramTest` with this program loaded under its program ID.  The program is loaded
+/// from `mpl_token_auth_rules.so`, so it must have been built with `cargo build-bpf`.
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new("mpl_token_auth_rules", crate::id(), None)
+}
+
+/// Create or update `rule_set` on chain with the `CreateOrUpdate` instruction, returning the
+/// address of its `RuleSet` PDA.  The `RuleSet` owner must be the payer of `context`, which
+/// signs the transaction.
+pub async fn create_rule_set(
+    context: &mut ProgramTestContext,
+    rule_set: &RuleSetV1,
+) -> Result<Pubkey, BanksClientError> {
+    let (rule_set_pda, _bump) =
+        find_rule_set_address(context.payer.pubkey(), rule_set.name().to_string());
+
+    let serialized_rule_set =
+        rmp_serde::to_vec(rule_set).expect("a RuleSet can always be serialized");
+
+    let create_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_pda)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .expect("all required accounts are set")
+        .instruction();
+
+    process_instructions(context, &[create_ix], &[]).await?;
+
+    Ok(rule_set_pda)
+}
+
+/// Create a `Validate` instruction for `operation`, without updating any `Rule` state and
+/// validating against the latest `RuleSet` revision.  Accounts needed by the `Rule`s are passed
+/// in `additional_rule_accounts`.
+pub fn validate_ix(
+    rule_set_pda: Pubkey,
+    mint: Pubkey,
+    operation: impl Into<String>,
+    payload: Payload,
+    additional_rule_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_pda)
+        .mint(mint)
+        .additional_rule_accounts(additional_rule_accounts)
+        .build(ValidateArgs::V1 {
+            operation: operation.into(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .expect("all required accounts are set")
+        .instruction()
+}
+
+/// Process `instructions` in one transaction paid for by the payer of `context`, and also signed
+/// by `additional_signers`.
+pub async fn process_instructions(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    additional_signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    signers.extend(additional_signers);
+
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(tx).await
+}
@@ -1,9 +1,10 @@
 //! Utilities for the program
 use crate::{
     error::RuleSetError,
-    payload::ProofInfo,
     state::{
-        Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, RULE_SET_REV_MAP_VERSION,
+        Key, LegacyRuleSetHeader, Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1,
+        LEGACY_RULE_SET_SERIALIZED_HEADER_LEN, RULE_SET_BUMP_LOCATION,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_LIB_VERSION, RULE_SET_REV_MAP_VERSION,
         RULE_SET_SERIALIZED_HEADER_LEN,
     },
 };
@@ -109,6 +110,30 @@ pub fn assert_derivation(
     Ok(bump)
 }
 
+/// Assert that the given account is derived from the given program ID and seeds.  When the bump
+/// is already known, the address is created from it directly instead of searching for the bump
+/// with `find_program_address`.
+pub fn assert_derivation_with_bump(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    path: &[&[u8]],
+    bump: Option<u8>,
+) -> Result<u8, ProgramError> {
+    let bump = match bump {
+        Some(bump) => bump,
+        None => return assert_derivation(program_id, account, path),
+    };
+
+    let bump_seed = [bump];
+    let seeds = [path, &[&bump_seed]].concat();
+    let key = Pubkey::create_program_address(&seeds, program_id)
+        .map_err(|_| RuleSetError::DerivedKeyInvalid)?;
+    if key != *account {
+        return Err(RuleSetError::DerivedKeyInvalid.into());
+    }
+    Ok(bump)
+}
+
 /// Assert that the given account is owned by the given pubkey.
 pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
     if account.owner != owner {
@@ -118,26 +143,128 @@ pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
     }
 }
 
-/// Compute the root of a Merkle tree given a leaf and a proof.  Uses a constant value
-/// of 0x01 as an input to the hashing function along with the values to be hashed.
-pub fn compute_merkle_root(leaf: &Pubkey, merkle_proof: &ProofInfo) -> [u8; 32] {
-    let mut computed_hash = leaf.to_bytes();
-    for proof_element in merkle_proof.proof.iter() {
-        if computed_hash <= *proof_element {
-            // Hash(current computed hash + current element of the proof).
-            computed_hash =
-                solana_program::keccak::hashv(&[&[0x01], &computed_hash, proof_element]).0;
-        } else {
-            // Hash(current element of the proof + current computed hash).
-            computed_hash =
-                solana_program::keccak::hashv(&[&[0x01], proof_element, &computed_hash]).0;
-        }
+/// Assert that the signer is the current owner of an existing `RuleSet`, as stored in its
+/// header, and return the header.
+pub fn assert_rule_set_owner(
+    program_id: &Pubkey,
+    owner_info: &AccountInfo,
+    rule_set_pda_info: &AccountInfo,
+) -> Result<RuleSetHeader, ProgramError> {
+    if !owner_info.is_signer {
+        return Err(RuleSetError::OwnerIsNotSigner.into());
     }
 
-    computed_hash
+    // `RuleSet` must be owned by this program.
+    assert_owned_by(rule_set_pda_info, program_id)?;
+
+    // Only accounts that actually hold a `RuleSet` have an owner.
+    let header = get_existing_header(rule_set_pda_info)?;
+
+    if header.owner != *owner_info.key {
+        return Err(RuleSetError::RuleSetOwnerMismatch.into());
+    }
+
+    Ok(header)
+}
+
+/// Assert that the latest `RuleSet` revision stored in the PDA has the given name.  The name is
+/// part of the PDA derivation, so it must never change between revisions.
+pub fn assert_rule_set_name(rule_set_pda_info: &AccountInfo, rule_set_name: &str) -> ProgramResult {
+    let rule_set = get_latest_rule_set(rule_set_pda_info)?;
+    if rule_set.name() != rule_set_name {
+        return Err(RuleSetError::DerivedKeyInvalid.into());
+    }
+
+    Ok(())
+}
+
+/// Assert that an existing `RuleSet` PDA has the current header, which is needed to store the
+/// frozen flag or a new owner.  `RuleSet`s with the legacy header must be migrated first.
+pub fn assert_current_layout(rule_set_pda_info: &AccountInfo) -> ProgramResult {
+    let (revision_map, _) = get_existing_revision_map(rule_set_pda_info)?;
+    if is_legacy_rule_set(&revision_map) {
+        return Err(RuleSetError::RuleSetMigrationRequired.into());
+    }
+
+    Ok(())
+}
+
+/// Get the latest `RuleSet` revision stored in an existing `RuleSet` PDA, without its
+/// operations.
+pub fn get_latest_rule_set(rule_set_pda_info: &AccountInfo) -> Result<RuleSetV1, ProgramError> {
+    let (revision_map, rev_map_version_location) = get_existing_revision_map(rule_set_pda_info)?;
+
+    // Borrow the existing `RuleSet` PDA data.
+    let data = rule_set_pda_info
+        .data
+        .try_borrow()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?;
+
+    // The latest revision starts after its lib version and ends at the revision map version.
+    let start = check_latest_lib_version(&data, &revision_map)?
+        .checked_add(1)
+        .ok_or(RuleSetError::NumericalOverflow)?;
+    let end = rev_map_version_location;
+    if start > end || end > data.len() {
+        return Err(RuleSetError::DataSliceUnexpectedIndexError.into());
+    }
+
+    Ok(RuleSetV1::from_slice_without_operations(&data[start..end])?)
+}
+
+// Check the lib version stored at the start of the latest `RuleSet` revision and return its
+// location.
+fn check_latest_lib_version(
+    data: &[u8],
+    revision_map: &RuleSetRevisionMapV1,
+) -> Result<usize, ProgramError> {
+    let location = *revision_map
+        .rule_set_revisions
+        .last()
+        .ok_or(RuleSetError::RuleSetRevisionNotAvailable)?;
+
+    match data.get(location) {
+        Some(&RULE_SET_LIB_VERSION) => Ok(location),
+        Some(_) => Err(RuleSetError::InvalidVersion.into()),
+        None => Err(RuleSetError::DataSliceUnexpectedIndexError.into()),
+    }
 }
 
-/// Get a revision map by looking at the header, finding its location, and deserializing it.
+/// Get the header stored at the beginning of an existing `RuleSet` PDA.  For a `RuleSet` with
+/// the legacy header, which could be neither frozen nor given a new owner, the header is filled in
+/// as not frozen and owned by the owner stored in the `RuleSet`.
+pub fn get_existing_header(rule_set_pda_info: &AccountInfo) -> Result<RuleSetHeader, ProgramError> {
+    let (revision_map, rev_map_version_location) = get_existing_revision_map(rule_set_pda_info)?;
+    if is_legacy_rule_set(&revision_map) {
+        let rule_set = get_latest_rule_set(rule_set_pda_info)?;
+        return Ok(RuleSetHeader::new(
+            rev_map_version_location,
+            *rule_set.owner(),
+        ));
+    }
+
+    // Borrow the existing `RuleSet` PDA data.
+    let data = rule_set_pda_info
+        .data
+        .try_borrow()
+        .map_err(|_| ProgramError::AccountBorrowFailed)?;
+
+    // Only trust the header of a `RuleSet` whose latest revision has a known lib version.
+    check_latest_lib_version(&data, &revision_map)?;
+
+    // Deserialize header.
+    if data.len() >= RULE_SET_SERIALIZED_HEADER_LEN {
+        Ok(RuleSetHeader::try_from_slice(
+            &data[..RULE_SET_SERIALIZED_HEADER_LEN],
+        )?)
+    } else {
+        Err(RuleSetError::DataTypeMismatch.into())
+    }
+}
+
+/// Get a revision map by looking at the header, finding its location, and deserializing it.  The
+/// revision map location is stored at the same place in the legacy and the current header, so
+/// this works for either.
 pub fn get_existing_revision_map(
     rule_set_pda_info: &AccountInfo,
 ) -> Result<(RuleSetRevisionMapV1, usize), ProgramError> {
@@ -147,9 +274,15 @@ pub fn get_existing_revision_map(
         .try_borrow()
         .map_err(|_| ProgramError::AccountBorrowFailed)?;
 
-    // Deserialize header.
-    let header = if data.len() >= RULE_SET_SERIALIZED_HEADER_LEN {
-        RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN])?
+    // Check the `Key` before deserializing so that accounts not holding a `RuleSet` give a clear
+    // error.
+    if data.first() != Some(&(Key::RuleSet as u8)) {
+        return Err(RuleSetError::Uninitialized.into());
+    }
+
+    // Deserialize the part of the header shared by both layouts.
+    let header = if data.len() >= LEGACY_RULE_SET_SERIALIZED_HEADER_LEN {
+        LegacyRuleSetHeader::try_from_slice(&data[..LEGACY_RULE_SET_SERIALIZED_HEADER_LEN])?
     } else {
         return Err(RuleSetError::DataTypeMismatch.into());
     };
@@ -176,6 +309,41 @@ pub fn get_existing_revision_map(
     }
 }
 
+/// Get the creator of an existing `RuleSet` PDA, which is used with the `RuleSet` name to derive
+/// the PDA.  `RuleSet`s with the legacy header could not change owner, so their creator is the
+/// owner stored in any of their revisions, such as `rule_set`.
+pub fn get_creator(
+    data: &[u8],
+    revision_map: &RuleSetRevisionMapV1,
+    rule_set: &RuleSetV1,
+) -> Result<Pubkey, ProgramError> {
+    if is_legacy_rule_set(revision_map) {
+        return Ok(*rule_set.owner());
+    }
+
+    match data.get(..RULE_SET_SERIALIZED_HEADER_LEN) {
+        Some(header) => Ok(RuleSetHeader::try_from_slice(header)?.creator),
+        None => Err(RuleSetError::DataTypeMismatch.into()),
+    }
+}
+
+/// Whether a `RuleSet` PDA still has the legacy header, which holds only the `Key` and the
+/// revision map location.  Its first revision starts right after that header, which is before
+/// the end of the current header.
+pub fn is_legacy_rule_set(revision_map: &RuleSetRevisionMapV1) -> bool {
+    revision_map.rule_set_revisions.first() == Some(&LEGACY_RULE_SET_SERIALIZED_HEADER_LEN)
+}
+
+/// Get the PDA bump stored after the header of an existing `RuleSet` PDA.  Returns `None` for
+/// `RuleSet`s created before the bump was stored, whose first revision starts right after the
+/// header instead.
+pub fn get_stored_bump(data: &[u8], revision_map: &RuleSetRevisionMapV1) -> Option<u8> {
+    match revision_map.rule_set_revisions.first() {
+        Some(&RULE_SET_FIRST_REVISION_LOCATION) => data.get(RULE_SET_BUMP_LOCATION).copied(),
+        _ => None,
+    }
+}
+
 /// Get the latest revision number stored on the revision map.
 ///
 /// This will first deserialize the header to find the map location and then deserialize the
@@ -223,9 +391,10 @@ pub fn is_zeroed(buf: &[u8]) -> bool {
     }
 }
 
-/// This function returns the rule for an operation by recursively searching through fallbacks
+/// This function returns the rule for an operation by recursively searching through fallbacks.
+/// An exact match is used first, then the operation namespace, then the `RuleSet` default.
 pub fn get_operation(operation: String, rule_set: &RuleSetV1) -> Result<&Rule, ProgramError> {
-    let rule = rule_set.get(operation.to_string());
+    let rule = rule_set.get(&operation);
 
     match rule {
         Some(Rule::Namespace) => {
@@ -240,6 +409,37 @@ pub fn get_operation(operation: String, rule_set: &RuleSetV1) -> Result<&Rule, P
             }
         }
         Some(r) => Ok(r),
+        // Fall back to the default `Rule`, if the `RuleSet` has one.
+        None if operation != RuleSetV1::DEFAULT_OPERATION => {
+            get_operation(RuleSetV1::DEFAULT_OPERATION.to_owned(), rule_set)
+        }
         None => Err(RuleSetError::OperationNotFound.into()),
     }
 }
+
+/// This function returns the rule for an operation by recursively searching through fallbacks,
+/// deserializing only the `Rule`s it needs from a MessagePack-serialized `RuleSetV1`.
+pub fn get_operation_from_slice(operation: String, data: &[u8]) -> Result<Rule, ProgramError> {
+    let rule = match RuleSetV1::get_rule_for_operation(data, &operation) {
+        // Fall back to the default `Rule`, if the `RuleSet` has one.
+        Err(RuleSetError::OperationNotFound) if operation != RuleSetV1::DEFAULT_OPERATION => {
+            return get_operation_from_slice(RuleSetV1::DEFAULT_OPERATION.to_owned(), data)
+        }
+        rule => rule?,
+    };
+
+    match rule {
+        Rule::Namespace => {
+            // Check for a ':' namespace separator. If it exists try to operation namespace to see if
+            // a fallback exists. E.g. 'transfer:owner' will check for a fallback for 'transfer'.
+            // If it doesn't exist then fail.
+            let split = operation.split(':').collect::<Vec<&str>>();
+            if split.len() > 1 {
+                get_operation_from_slice(split[0].to_owned(), data)
+            } else {
+                Err(RuleSetError::OperationNotFound.into())
+            }
+        }
+        r => Ok(r),
+    }
+}
@@ -8,7 +8,11 @@ use mpl_token_auth_rules::{
     payload::Payload,
     state::{Rule, RuleSetV1},
 };
-use solana_program::instruction::AccountMeta;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
 use solana_program_test::tokio;
 use solana_sdk::{signature::Signer, signer::keypair::Keypair};
 use utils::{program_test, Operation};
@@ -122,3 +126,120 @@ async fn test_additional_signer() {
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![&adtl_signer], None).await;
 }
+
+#[tokio::test]
+async fn test_additional_signer_list() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule requiring 2 of 3 signers.
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let rule = Rule::AdditionalSignerList {
+        signers: signers.iter().map(|signer| signer.pubkey()).collect(),
+        threshold: 2,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail below threshold
+    // --------------------------------
+    // Provide all three accounts, but only sign with the first.
+    let validate_ix = signer_list_validate_ix(rule_set_addr, &signers, 1);
+
+    let err =
+        process_failing_validate_ix!(&mut context, validate_ix, vec![&signers[0]], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AdditionalSignerListCheckFailed);
+
+    // --------------------------------
+    // Validate pass at threshold
+    // --------------------------------
+    let validate_ix = signer_list_validate_ix(rule_set_addr, &signers, 2);
+
+    process_passing_validate_ix!(
+        &mut context,
+        validate_ix,
+        vec![&signers[0], &signers[1]],
+        None
+    )
+    .await;
+
+    // --------------------------------
+    // Validate pass above threshold
+    // --------------------------------
+    let validate_ix = signer_list_validate_ix(rule_set_addr, &signers, 3);
+
+    process_passing_validate_ix!(
+        &mut context,
+        validate_ix,
+        signers.iter().collect::<Vec<_>>(),
+        None
+    )
+    .await;
+}
+
+#[test]
+fn test_additional_signer_list_invalid_threshold() {
+    let signers = vec![Keypair::new().pubkey(), Keypair::new().pubkey()];
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // The threshold can be neither zero nor more than the number of signers.
+    for threshold in [0, 3] {
+        let rule = Rule::AdditionalSignerList {
+            signers: signers.clone(),
+            threshold,
+        };
+        assert_eq!(
+            rule_set.add(Operation::TransferNamespace.to_string(), rule),
+            Err(ProgramError::from(RuleSetError::InvalidSignerThreshold))
+        );
+    }
+}
+
+// Create a `validate` instruction passing every account in `signers`, with the first
+// `signed` of them marked as signers.
+fn signer_list_validate_ix(
+    rule_set_addr: Pubkey,
+    signers: &[Keypair],
+    signed: usize,
+) -> Instruction {
+    let additional_rule_accounts = signers
+        .iter()
+        .enumerate()
+        .map(|(index, signer)| AccountMeta::new_readonly(signer.pubkey(), index < signed))
+        .collect();
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(additional_rule_accounts)
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -119,3 +119,145 @@ async fn test_all() {
     // Validate Transfer operation since both Rule conditions were true.
     process_passing_validate_ix!(&mut context, validate_ix, vec![&second_signer], None).await;
 }
+
+#[tokio::test]
+async fn test_all_program_owned_and_amount() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create some rules.
+    let program_owned = Rule::ProgramOwned {
+        program: mpl_token_auth_rules::ID,
+        field: PayloadKey::Destination.to_string(),
+    };
+
+    let amount_check = Rule::Amount {
+        amount: 5,
+        operator: CompareOp::Lt,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    let overall_rule = Rule::All {
+        rules: vec![program_owned, amount_check],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            overall_rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.  The `RuleSet` PDA is owned by mpl-token-auth-rules and has
+    // nonzero data, so it is used as the program-owned destination below.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail program owned but WRONG amount
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    let payload = Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(5)),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(rule_set_addr),
+        ),
+    ]);
+
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(rule_set_addr, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+
+    // --------------------------------
+    // Validate fail CORRECT amount but not program owned
+    // --------------------------------
+    let wallet = Keypair::new().pubkey();
+
+    let payload = Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(4)),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(wallet),
+        ),
+    ]);
+
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(wallet, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let payload = Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(4)),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(rule_set_addr),
+        ),
+    ]);
+
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(rule_set_addr, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation since both Rule conditions were true.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
@@ -42,6 +42,26 @@ async fn test_greater_than_amount() {
     parametric_amount_check(CompareOp::Gt, 100, 100, 101).await;
 }
 
+#[tokio::test]
+async fn test_amount_under_cap() {
+    parametric_amount_check(CompareOp::LtEq, 10, 11, 5).await;
+}
+
+#[tokio::test]
+async fn test_amount_at_strict_cap_fails() {
+    parametric_amount_check(CompareOp::Lt, 5, 5, 4).await;
+}
+
+#[tokio::test]
+async fn test_max_amount() {
+    parametric_amount_check(CompareOp::GtEq, u64::MAX, u64::MAX - 1, u64::MAX).await;
+}
+
+#[tokio::test]
+async fn test_max_amount_strict_cap() {
+    parametric_amount_check(CompareOp::Lt, u64::MAX, u64::MAX, u64::MAX - 1).await;
+}
+
 async fn parametric_amount_check(
     operator: CompareOp,
     amount: u64,
@@ -0,0 +1,163 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1, SPL_TOKEN_PROGRAM_ID, TOKEN_ACCOUNT_AMOUNT_OFFSET},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::tokio;
+use solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+// The most tokens a transfer may move out of the source account.
+const CAP: u64 = 100;
+
+#[test]
+fn token_account_layout() {
+    assert_eq!(SPL_TOKEN_PROGRAM_ID, spl_token::ID);
+
+    // The amount follows the mint and owner in the SPL Token account layout.
+    let data = token_account_data(42);
+    assert_eq!(
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8],
+        42u64.to_le_bytes()
+    );
+}
+
+#[tokio::test]
+async fn amount_from_token_account() {
+    // Create mock token accounts below and above the cap, and one not owned by the SPL Token
+    // program.
+    let under_cap = Pubkey::new_unique();
+    let over_cap = Pubkey::new_unique();
+    let not_token_account = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    for (key, amount, owner) in [
+        (under_cap, CAP, spl_token::ID),
+        (over_cap, CAP + 1, spl_token::ID),
+        (not_token_account, CAP, Pubkey::new_unique()),
+    ] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: 1_000_000_000,
+                data: token_account_data(amount),
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The source token account must hold at most `CAP` tokens.
+    let rule = Rule::AmountFromTokenAccount {
+        field: PayloadKey::Source.to_string(),
+        operator: CompareOp::LtEq,
+        amount: CAP,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let validate_ix = amount_validate_ix(rule_set_addr, under_cap, vec![under_cap]);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail with a balance over the cap
+    // --------------------------------
+    let validate_ix = amount_validate_ix(rule_set_addr, over_cap, vec![over_cap]);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+
+    // --------------------------------
+    // Validate fail with an account not owned by the SPL Token program
+    // --------------------------------
+    let validate_ix = amount_validate_ix(rule_set_addr, not_token_account, vec![not_token_account]);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+
+    // --------------------------------
+    // Validate fail with the token account missing
+    // --------------------------------
+    let validate_ix = amount_validate_ix(rule_set_addr, under_cap, vec![]);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::MissingAccount);
+}
+
+// Create the data of an initialized SPL Token account holding `amount` tokens.
+fn token_account_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+    data
+}
+
+// Create a `validate` instruction checking the balance of the `source` token account.
+fn amount_validate_ix(
+    rule_set_addr: Pubkey,
+    source: Pubkey,
+    additional_rule_accounts: Vec<Pubkey>,
+) -> Instruction {
+    let payload = Payload::from([(PayloadKey::Source.to_string(), PayloadType::Pubkey(source))]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(
+            additional_rule_accounts
+                .into_iter()
+                .map(|key| AccountMeta::new_readonly(key, false))
+                .collect(),
+        )
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
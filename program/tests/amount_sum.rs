@@ -0,0 +1,136 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::{RuleError, RuleSetError},
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, InMemoryValidationContext, Rule, RuleSetV1},
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+// The payload field holding the fee paid on top of the amount.
+const FEE: &str = "Fee";
+
+#[tokio::test]
+async fn test_amount_sum_under_cap() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(transfer(), amount_plus_fee_cap(100)).unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store a payload whose amount and fee are each under the cap but add up to more than it.
+    let validate_ix = transfer_validate_ix(rule_set_addr, mint, amount_and_fee(60, 41));
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Store a payload whose amount and fee add up to the cap.
+    let validate_ix = transfer_validate_ix(rule_set_addr, mint, amount_and_fee(60, 40));
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[test]
+fn test_amount_sum_missing_field_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set.add(transfer(), amount_plus_fee_cap(100)).unwrap();
+
+    // Store a payload without the fee.
+    let payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]);
+
+    let err = rule_set
+        .validate(&transfer(), &payload, &InMemoryValidationContext::new())
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, RuleError::missing_payload_value(FEE));
+    assert_eq!(
+        ProgramError::from(err),
+        ProgramError::from(RuleSetError::MissingPayloadValue)
+    );
+}
+
+#[test]
+fn test_amount_sum_overflow_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(transfer(), amount_plus_fee_cap(u64::MAX))
+        .unwrap();
+
+    // Store a payload whose amount and fee add up to more than a `u64` can hold.
+    let err = rule_set
+        .validate(
+            &transfer(),
+            &amount_and_fee(u64::MAX, 1),
+            &InMemoryValidationContext::new(),
+        )
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, RuleError::from(RuleSetError::NumericalOverflow));
+}
+
+// A Rule capping the amount plus the fee at `cap`.
+fn amount_plus_fee_cap(cap: u64) -> Rule {
+    Rule::AmountSum {
+        fields: vec![PayloadKey::Amount.to_string(), FEE.to_string()],
+        operator: CompareOp::LtEq,
+        amount: cap,
+    }
+}
+
+fn amount_and_fee(amount: u64, fee: u64) -> Payload {
+    Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(amount)),
+        (FEE.to_string(), PayloadType::Number(fee)),
+    ])
+}
+
+fn transfer() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string()
+}
+
+// Create a `validate` instruction for the Transfer operation.
+fn transfer_validate_ix(rule_set_addr: Pubkey, mint: Pubkey, payload: Payload) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: transfer(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
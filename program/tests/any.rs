@@ -8,6 +8,7 @@ use mpl_token_auth_rules::{
     payload::{Payload, PayloadType},
     state::{CompareOp, Rule, RuleSetV1},
 };
+use solana_program::program_error::ProgramError;
 use solana_program_test::tokio;
 use solana_sdk::{signature::Signer, signer::keypair::Keypair};
 use utils::{program_test, Operation, PayloadKey};
@@ -110,3 +111,34 @@ async fn test_any() {
     // Validate Transfer operation since at least one Rule condition was true.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[test]
+fn test_empty_any_rejected() {
+    // Create an `Any` Rule that contains no Rules, nested inside an `All` Rule.
+    let overall_rule = Rule::All {
+        rules: vec![Rule::Pass, Rule::Any { rules: vec![] }],
+    };
+
+    // Fail to add it to a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    let err = rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            overall_rule,
+        )
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, ProgramError::from(RuleSetError::EmptyAnyRule));
+    assert!(rule_set
+        .get(
+            &Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string()
+        )
+        .is_none());
+}
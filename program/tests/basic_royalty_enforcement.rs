@@ -6,8 +6,13 @@ use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
     payload::{Payload, PayloadType},
-    state::{CompareOp, Rule, RuleSetV1},
+    state::{
+        from_msgpack_slice, ChangedOperation, CompareOp, Rule, RuleSetV1,
+        RULE_SET_FIRST_REVISION_LOCATION,
+    },
 };
+use rmp_serde::Serializer;
+use serde::Serialize;
 use solana_program::{instruction::InstructionError, pubkey, pubkey::Pubkey};
 use solana_program_test::{tokio, ProgramTestContext};
 use solana_sdk::{
@@ -344,6 +349,98 @@ async fn create_royalty_rule_set(context: &mut ProgramTestContext) -> Pubkey {
     .await
 }
 
+#[test]
+fn get_rule_from_deserialized_rule_set() {
+    let royalty_rule_set = get_royalty_rule_set(Keypair::new().pubkey());
+
+    // Serialize the `RuleSet` using RMP serde and deserialize it again.
+    let mut serialized_rule_set = Vec::new();
+    royalty_rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    let rule_set: RuleSetV1 = rmp_serde::from_slice(&serialized_rule_set).unwrap();
+
+    // Look up an operation that is in the `RuleSet`.
+    let transfer_owner_operation = Operation::Transfer {
+        scenario: TransferScenario::Holder,
+    };
+    assert!(matches!(
+        rule_set.get(&transfer_owner_operation.to_string()),
+        Some(Rule::All { .. })
+    ));
+
+    // Look up an operation that is not in the `RuleSet`.
+    assert!(rule_set.get("Missing").is_none());
+
+    // Every operation added to the `RuleSet` is listed.
+    assert_eq!(rule_set.operations().count(), 14);
+    assert!(rule_set
+        .operations()
+        .any(|operation| *operation == transfer_owner_operation.to_string()));
+}
+
+#[test]
+fn serialized_size_matches_serialized_rule_set() {
+    let royalty_rule_set = get_royalty_rule_set(Keypair::new().pubkey());
+
+    // Serialize the `RuleSet` using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    royalty_rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // The computed size matches the actual serialized length.
+    assert_eq!(
+        royalty_rule_set.serialized_size().unwrap(),
+        serialized_rule_set.len()
+    );
+}
+
+#[test]
+fn diff_against_updated_rule_set() {
+    let royalty_rule_set = get_royalty_rule_set(Keypair::new().pubkey());
+
+    // Create an updated `RuleSet` with one operation removed and one `Rule` changed.
+    let staking_operation = Operation::Delegate {
+        scenario: DelegateScenario::Token(TokenDelegateRole::Staking),
+    }
+    .to_string();
+    let wallet_to_wallet_operation = Operation::Transfer {
+        scenario: TransferScenario::WalletToWallet,
+    }
+    .to_string();
+
+    let mut updated_rule_set = royalty_rule_set.clone();
+    updated_rule_set.remove(&staking_operation).unwrap();
+    let old_rule = updated_rule_set
+        .remove(&wallet_to_wallet_operation)
+        .unwrap();
+    updated_rule_set
+        .add(wallet_to_wallet_operation.clone(), Rule::Pass)
+        .unwrap();
+
+    let diff = royalty_rule_set.diff(&updated_rule_set);
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed, vec![staking_operation.clone()]);
+    assert_eq!(
+        diff.changed,
+        vec![ChangedOperation {
+            operation: wallet_to_wallet_operation,
+            old: old_rule,
+            new: Rule::Pass,
+        }]
+    );
+
+    // The reverse diff adds the removed operation back.
+    let reverse_diff = updated_rule_set.diff(&royalty_rule_set);
+    assert_eq!(reverse_diff.added, vec![staking_operation]);
+    assert!(reverse_diff.removed.is_empty());
+    assert_eq!(reverse_diff.changed.len(), 1);
+
+    // A `RuleSet` has no differences with itself.
+    assert!(royalty_rule_set.diff(&royalty_rule_set).is_empty());
+}
+
 #[tokio::test]
 async fn create_rule_set() {
     let mut context = program_test().start_with_context().await;
@@ -351,7 +448,27 @@ async fn create_rule_set() {
 }
 
 #[tokio::test]
-async fn wallet_to_wallet_unimplemented() {
+async fn read_owner_and_name_from_chain() {
+    let mut context = program_test().start_with_context().await;
+    let rule_set_addr = create_royalty_rule_set(&mut context).await;
+
+    // The first revision follows the header, the stored bump and its one-byte version.
+    let rule_set_account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    let rule_set: RuleSetV1 =
+        from_msgpack_slice(&rule_set_account.data[RULE_SET_FIRST_REVISION_LOCATION + 1..]).unwrap();
+
+    // Check that the owner and name are those the `RuleSet` was created with.
+    assert_eq!(rule_set.name(), RULE_SET_NAME);
+    assert_eq!(*rule_set.owner(), context.payer.pubkey());
+}
+
+#[tokio::test]
+async fn wallet_to_wallet() {
     let mut context = program_test().start_with_context().await;
     let rule_set_addr = create_royalty_rule_set(&mut context).await;
 
@@ -396,20 +513,8 @@ async fn wallet_to_wallet_unimplemented() {
         .unwrap()
         .instruction();
 
-    // Validate fail operation.
-    let err =
-        process_failing_validate_ix!(&mut context, validate_ix, vec![], Some(ADDITIONAL_COMPUTE))
-            .await;
-
-    // Check that error is what we expect.  The `IsWallet` rule currently returns `NotImplemented`.
-    match err {
-        solana_program_test::BanksClientError::TransactionError(
-            TransactionError::InstructionError(_, InstructionError::Custom(error)),
-        ) => {
-            assert_eq!(error, RuleSetError::NotImplemented as u32);
-        }
-        _ => panic!("Unexpected error: {:?}", err),
-    }
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], Some(ADDITIONAL_COMPUTE)).await;
 }
 
 #[tokio::test]
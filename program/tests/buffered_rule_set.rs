@@ -6,7 +6,7 @@ use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
     payload::{Payload, PayloadType},
-    state::{Rule, RuleSetV1, RULE_SET_SERIALIZED_HEADER_LEN},
+    state::{Rule, RuleSetV1, RULE_SET_FIRST_REVISION_LOCATION},
 };
 use rmp_serde::Serializer;
 use serde::Serialize;
@@ -22,12 +22,21 @@ async fn buffered_rule_set() {
     // --------------------------------
     // Create RuleSet
     // --------------------------------
-    // Create a Rule.
-    let targets = (0..70).map(|_| system_program::ID).collect::<Vec<_>>();
-
-    let rule = Rule::PubkeyListMatch {
-        pubkeys: targets,
-        field: PayloadKey::Authority.to_string(),
+    // Create a Rule.  The 70 targets are split across two lists to stay within the maximum list
+    // length while still making a `RuleSet` large enough to need a buffer.
+    let targets = (0..35).map(|_| system_program::ID).collect::<Vec<_>>();
+
+    let rule = Rule::Any {
+        rules: vec![
+            Rule::PubkeyListMatch {
+                pubkeys: targets.clone(),
+                field: PayloadKey::Authority.to_string(),
+            },
+            Rule::PubkeyListMatch {
+                pubkeys: targets,
+                field: PayloadKey::Authority.to_string(),
+            },
+        ],
     };
 
     // Create a RuleSet.
@@ -62,10 +71,10 @@ async fn buffered_rule_set() {
         .unwrap()
         .data;
 
-    // Because there is only one RuleSet we can assume it exists right after the header.
+    // Because there is only one RuleSet we can assume it exists right after the header and bump.
     // TODO: Write utility function to provide the RuleSet location of a given revision.
-    let start = RULE_SET_SERIALIZED_HEADER_LEN + 1;
-    let end = RULE_SET_SERIALIZED_HEADER_LEN + 1 + serialized_rule_set.len();
+    let start = RULE_SET_FIRST_REVISION_LOCATION + 1;
+    let end = RULE_SET_FIRST_REVISION_LOCATION + 1 + serialized_rule_set.len();
     assert!(
         cmp_slice(&data[start..end], &serialized_rule_set),
         "The buffer doesn't match the serialized rule set.",
@@ -0,0 +1,131 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::state::{
+    CompareOp, Rule, RuleSetV1, RuleSetWarning, RuleSetWarningKind, MAX_RULE_DEPTH,
+};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn check_reports_each_problem() {
+    let holder = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+    let delegate = Operation::Transfer {
+        scenario: utils::TransferScenario::TransferDelegate,
+    }
+    .to_string();
+    let namespace = Operation::TransferNamespace.to_string();
+
+    // Create a RuleSet, inserting the Rules directly so that `add` does not reject them.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set.operations.insert(
+        holder.clone(),
+        Rule::All {
+            rules: vec![
+                Rule::Amount {
+                    amount: 1,
+                    operator: CompareOp::Lt,
+                    field: PayloadKey::Amount.to_string(),
+                },
+                Rule::Any { rules: vec![] },
+                Rule::Not {
+                    rule: Box::new(Rule::PubkeyTreeMatch {
+                        root: [0; 32],
+                        pubkey_field: PayloadKey::Authority.to_string(),
+                        proof_field: String::new(),
+                        leaf_is_hashed: false,
+                    }),
+                },
+            ],
+        },
+    );
+    rule_set.operations.insert(
+        delegate.clone(),
+        Rule::Any {
+            rules: vec![
+                Rule::AdditionalSignerList {
+                    signers: vec![Keypair::new().pubkey()],
+                    threshold: 2,
+                },
+                Rule::ProgramOwnedList {
+                    programs: vec![],
+                    field: PayloadKey::Destination.to_string(),
+                },
+                Rule::Timestamp {
+                    start: Some(200),
+                    end: Some(100),
+                },
+            ],
+        },
+    );
+    rule_set.operations.insert(
+        namespace.clone(),
+        (0..=MAX_RULE_DEPTH).fold(Rule::Pass, |rule, _| Rule::Not {
+            rule: Box::new(rule),
+        }),
+    );
+
+    let warning = |operation: &String, path: &str, kind| RuleSetWarning {
+        operation: operation.clone(),
+        path: path.to_string(),
+        kind,
+    };
+
+    // Check that each problem is reported, in operation order.
+    assert_eq!(
+        rule_set.check(),
+        vec![
+            warning(&namespace, "Not", RuleSetWarningKind::TreeTooDeep),
+            warning(&holder, "All[1].Any", RuleSetWarningKind::EmptyRuleList),
+            warning(
+                &holder,
+                "All[2].Not.PubkeyTreeMatch",
+                RuleSetWarningKind::EmptyField
+            ),
+            warning(
+                &delegate,
+                "Any[0].AdditionalSignerList",
+                RuleSetWarningKind::InvalidSignerThreshold
+            ),
+            warning(
+                &delegate,
+                "Any[1].ProgramOwnedList",
+                RuleSetWarningKind::EmptyPubkeyList
+            ),
+            warning(
+                &delegate,
+                "Any[2].Timestamp",
+                RuleSetWarningKind::EmptyRange
+            ),
+        ]
+    );
+}
+
+#[test]
+fn check_well_formed_rule_set() {
+    // A RuleSet built with `add` from well-formed Rules has no warnings.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Any {
+                rules: vec![
+                    Rule::Pass,
+                    Rule::ProgramOwned {
+                        program: Keypair::new().pubkey(),
+                        field: PayloadKey::Destination.to_string(),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+
+    assert!(rule_set.check().is_empty());
+}
@@ -159,5 +159,5 @@ async fn composed_rule() {
         process_failing_validate_ix!(&mut context, validate_ix, vec![&second_signer], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+    assert_custom_error!(err, RuleSetError::NotCheckFailed);
 }
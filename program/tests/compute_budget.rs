@@ -0,0 +1,175 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{
+    account::Account, compute_budget::ComputeBudgetInstruction, signature::Signer,
+    signer::keypair::Keypair, transaction::Transaction,
+};
+use utils::{create_test_merkle_tree_from_one_leaf, program_test, Operation, PayloadKey};
+
+// The compute unit limit requested for each `validate` transaction, the most a transaction can
+// use.
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+// The maximum compute units a `validate` of each `Rule` may use, including loading the `RuleSet`
+// and the compute budget instruction.  These leave generous headroom so that only a real
+// regression fails.  Compute is only metered when the program runs as BPF.
+const PROGRAM_OWNED_MAX_UNITS: u64 = 40_000;
+const PUBKEY_MATCH_MAX_UNITS: u64 = 40_000;
+const PUBKEY_TREE_MATCH_MAX_UNITS: u64 = 60_000;
+
+// The depth of the Merkle tree used for `PubkeyTreeMatch`.
+const TREE_LEVELS: usize = 10;
+
+#[tokio::test]
+async fn program_owned_compute_units() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create an account owned by a program, holding some data.
+    let program = Pubkey::new_unique();
+    let destination = Keypair::new().pubkey();
+    context.set_account(
+        &destination,
+        &Account {
+            lamports: 1_000_000_000,
+            data: vec![1; 8],
+            owner: program,
+            ..Account::default()
+        }
+        .into(),
+    );
+
+    let rule = Rule::ProgramOwned {
+        program,
+        field: PayloadKey::Destination.to_string(),
+    };
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(destination),
+    )]);
+    let accounts = vec![AccountMeta::new_readonly(destination, false)];
+
+    let units = validate_compute_units(&mut context, rule, payload, accounts).await;
+    assert_units("ProgramOwned", units, PROGRAM_OWNED_MAX_UNITS);
+}
+
+#[tokio::test]
+async fn pubkey_match_compute_units() {
+    let mut context = program_test().start_with_context().await;
+
+    let destination = Keypair::new().pubkey();
+    let rule = Rule::PubkeyMatch {
+        pubkey: destination,
+        field: PayloadKey::Destination.to_string(),
+    };
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(destination),
+    )]);
+
+    let units = validate_compute_units(&mut context, rule, payload, vec![]).await;
+    assert_units("PubkeyMatch", units, PUBKEY_MATCH_MAX_UNITS);
+}
+
+#[tokio::test]
+async fn pubkey_tree_match_compute_units() {
+    let mut context = program_test().start_with_context().await;
+
+    let authority = Keypair::new().pubkey();
+    let tree = create_test_merkle_tree_from_one_leaf(&authority, TREE_LEVELS);
+    let rule = Rule::PubkeyTreeMatch {
+        root: tree.root,
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+        leaf_is_hashed: false,
+    };
+    let payload = Payload::from([
+        (
+            PayloadKey::Authority.to_string(),
+            PayloadType::Pubkey(authority),
+        ),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(tree.proof),
+        ),
+    ]);
+
+    let units = validate_compute_units(&mut context, rule, payload, vec![]).await;
+    assert_units("PubkeyTreeMatch", units, PUBKEY_TREE_MATCH_MAX_UNITS);
+}
+
+// Put a `RuleSet` holding only `rule` on chain, then return the compute units used by a passing
+// `validate` of `payload` against it.
+async fn validate_compute_units(
+    context: &mut ProgramTestContext,
+    rule: Rule,
+    payload: Payload,
+    additional_rule_accounts: Vec<AccountMeta>,
+) -> u64 {
+    let operation = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(operation.clone(), rule).unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(additional_rule_accounts)
+        .build(ValidateArgs::V1 {
+            operation,
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction after a compute budget instruction.
+    let validate_tx = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT),
+            validate_ix,
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Simulate the transaction to get the compute units it used.
+    let simulation = context
+        .banks_client
+        .simulate_transaction(validate_tx)
+        .await
+        .unwrap();
+
+    assert_eq!(simulation.result.unwrap(), Ok(()));
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+// Check that `rule` used at most `max_units` compute units.
+fn assert_units(rule: &str, units: u64, max_units: u64) {
+    assert!(
+        units <= max_units,
+        "{} used {} compute units, over the limit of {}",
+        rule,
+        units,
+        max_units
+    );
+}
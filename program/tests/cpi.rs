@@ -0,0 +1,173 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    cpi::{validate_cpi, ValidateCpiContext},
+    error::RuleSetError,
+    payload::Payload,
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program_test::{processor, tokio, BanksClientError, ProgramTestContext};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+// The seed for the harness program's authority PDA.
+const AUTHORITY_SEED: &[u8] = b"authority";
+
+// A program that validates a Transfer operation through `validate_cpi`.  The instruction data is
+// the authority PDA bump, followed by whether to sign for the authority PDA.
+fn process_harness_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (auth_rules_program, rule_set_pda, mint, system_program, authority) = match accounts {
+        [auth_rules_program, rule_set_pda, mint, system_program, authority] => (
+            auth_rules_program,
+            rule_set_pda,
+            mint,
+            system_program,
+            authority,
+        ),
+        _ => panic!("unexpected accounts"),
+    };
+
+    // The authority is a PDA, so it signs through the seeds.
+    let mut authority = authority.clone();
+    authority.is_signer = data[1] != 0;
+
+    let ctx = ValidateCpiContext {
+        auth_rules_program,
+        rule_set_pda,
+        mint,
+        system_program,
+        payer: None,
+        rule_authority: None,
+        rule_set_state_pda: None,
+        update_rule_state: false,
+        rule_set_revision: None,
+    };
+
+    validate_cpi(
+        &ctx,
+        &[authority],
+        Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        }
+        .to_string(),
+        Payload::default(),
+        &[&[AUTHORITY_SEED, &[data[0]]]],
+    )
+}
+
+#[tokio::test]
+async fn validate_through_cpi() {
+    let harness_id = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_program(
+        "cpi_harness",
+        harness_id,
+        processor!(process_harness_instruction),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule that requires the harness program's authority PDA to sign.
+    let (authority, authority_bump) = Pubkey::find_program_address(&[AUTHORITY_SEED], &harness_id);
+    let rule = Rule::AdditionalSigner { account: authority };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // The harness signs for its authority PDA, so the Rule passes.
+    call_harness(
+        &mut context,
+        harness_id,
+        rule_set_addr,
+        authority,
+        authority_bump,
+        true,
+    )
+    .await
+    .unwrap();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Without signing for its authority PDA, the Rule fails.
+    let err = call_harness(
+        &mut context,
+        harness_id,
+        rule_set_addr,
+        authority,
+        authority_bump,
+        false,
+    )
+    .await
+    .expect_err("CPI should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AdditionalSignerCheckFailed);
+}
+
+// Call the harness program, which validates through CPI.  A new mint is used for each call so
+// that each transaction is unique.
+async fn call_harness(
+    context: &mut ProgramTestContext,
+    harness_id: Pubkey,
+    rule_set_addr: Pubkey,
+    authority: Pubkey,
+    authority_bump: u8,
+    sign: bool,
+) -> Result<(), BanksClientError> {
+    let mint = Keypair::new().pubkey();
+
+    let harness_ix = Instruction {
+        program_id: harness_id,
+        accounts: vec![
+            AccountMeta::new_readonly(mpl_token_auth_rules::ID, false),
+            AccountMeta::new_readonly(rule_set_addr, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(authority, false),
+        ],
+        data: vec![authority_bump, sign as u8],
+    };
+
+    // Add it to a transaction.
+    let harness_tx = Transaction::new_signed_with_payer(
+        &[harness_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context.banks_client.process_transaction(harness_tx).await
+}
@@ -5,14 +5,17 @@ pub mod utils;
 use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{
-        builders::{CreateOrUpdateBuilder, WriteToBufferBuilder},
+        builders::{CreateBuilder, CreateOrUpdateBuilder, WriteToBufferBuilder},
         CreateOrUpdateArgs, InstructionBuilder, WriteToBufferArgs,
     },
+    pda::rule_set_exists,
     state::{Rule, RuleSetV1},
+    MAX_NAME_LENGTH,
 };
 use rmp_serde::Serializer;
 use serde::Serialize;
-use solana_program_test::tokio;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{tokio, BanksClientError, ProgramTestContext};
 use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
 use utils::{program_test, Operation};
 
@@ -674,3 +677,218 @@ async fn create_rule_set_to_wrong_pda_fails() {
     // Check that error is what we expect.
     assert_custom_error!(err, RuleSetError::DerivedKeyInvalid);
 }
+
+#[test]
+fn create_args_from_rule_set_matches_manual_serialization() {
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Serialize the RuleSet using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // Check the args match the hand-serialized RuleSet byte-for-byte.
+    let args = CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap();
+    assert_eq!(args.serialized_len(), serialized_rule_set.len());
+    assert_eq!(
+        args,
+        CreateOrUpdateArgs::V1 {
+            serialized_rule_set
+        }
+    );
+}
+
+#[tokio::test]
+async fn create_rule_set_max_length_name() {
+    let name = "a".repeat(MAX_NAME_LENGTH);
+    create_rule_set_with_name(name.clone(), &name)
+        .await
+        .expect("Creation should succeed");
+}
+
+#[tokio::test]
+async fn create_rule_set_name_one_byte_too_long_fails() {
+    // This isn't the correct PDA but we expect to fail because the name in the serialized
+    // `RuleSet` is too long, and it cannot be used as a seed.
+    let err = create_rule_set_with_name("a".repeat(MAX_NAME_LENGTH + 1), "test rule_set")
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::NameTooLong);
+}
+
+#[tokio::test]
+async fn create_rule_set_name_with_nul_fails() {
+    let name = "test\0rule_set".to_string();
+    let err = create_rule_set_with_name(name.clone(), &name)
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::InvalidName);
+}
+
+// Create a `RuleSet` with the given name at the PDA derived from `pda_name`.
+async fn create_rule_set_with_name(name: String, pda_name: &str) -> Result<(), BanksClientError> {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new(name, context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Serialize the RuleSet using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        pda_name.to_string(),
+    );
+
+    // Create a `create` instruction.
+    let create_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context.banks_client.process_transaction(create_tx).await
+}
+
+#[tokio::test]
+async fn create_existing_rule_set_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        "test rule_set".to_string(),
+    );
+
+    // Create two revisions of a RuleSet with the same name.
+    let mut args = vec![];
+    for rule in [Rule::Pass, Rule::Namespace] {
+        let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+        rule_set
+            .add(Operation::TransferNamespace.to_string(), rule)
+            .unwrap();
+        args.push(CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap());
+    }
+
+    // The RuleSet does not exist yet.
+    let data = get_account_data(&mut context, rule_set_addr).await;
+    assert!(!rule_set_exists(
+        |_| data,
+        context.payer.pubkey(),
+        "test rule_set".to_string()
+    ));
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    let create_ix = CreateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(args[0].clone())
+        .unwrap()
+        .instruction();
+
+    process_create_ix(&mut context, create_ix).await.unwrap();
+
+    // The RuleSet now exists.
+    let data = get_account_data(&mut context, rule_set_addr).await;
+    assert!(rule_set_exists(
+        |_| data,
+        context.payer.pubkey(),
+        "test rule_set".to_string()
+    ));
+
+    // --------------------------------
+    // Fail to create RuleSet again
+    // --------------------------------
+    let create_ix = CreateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(args[1].clone())
+        .unwrap()
+        .instruction();
+
+    let err = process_create_ix(&mut context, create_ix)
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AlreadyInitialized);
+
+    // --------------------------------
+    // Update RuleSet
+    // --------------------------------
+    let update_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(args[1].clone())
+        .unwrap()
+        .instruction();
+
+    process_create_ix(&mut context, update_ix).await.unwrap();
+}
+
+// Get the data of `key`, or `None` if the account does not exist.
+async fn get_account_data(context: &mut ProgramTestContext, key: Pubkey) -> Option<Vec<u8>> {
+    context
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .map(|account| account.data)
+}
+
+// Process a `create` or `create_or_update` instruction signed by the payer.
+async fn process_create_ix(
+    context: &mut ProgramTestContext,
+    create_ix: Instruction,
+) -> Result<(), BanksClientError> {
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context.banks_client.process_transaction(create_tx).await
+}
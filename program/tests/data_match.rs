@@ -0,0 +1,141 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::tokio;
+use solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+// The payload field holding the expected bytes.
+const FLAGS: &str = "Flags";
+
+// The flag bytes stored in the mock account.
+const STORED_FLAGS: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+#[tokio::test]
+async fn data_match() {
+    // Create an account holding the flags at offset 8, and one too short to hold them.
+    let account = Pubkey::new_unique();
+    let short_account = Pubkey::new_unique();
+
+    let mut data = vec![0; 8];
+    data.extend_from_slice(&STORED_FLAGS);
+    let short_data = data[..10].to_vec();
+
+    let mut program_test = program_test();
+    for (key, data) in [(account, data), (short_account, short_data)] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: Pubkey::new_unique(),
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The 4 bytes at offset 8 must match the payload.
+    let rule = Rule::DataMatch {
+        account_field: PayloadKey::Destination.to_string(),
+        bytes_field: FLAGS.to_string(),
+        offset: 8,
+        expected_len: 4,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let validate_ix = data_match_validate_ix(rule_set_addr, account, STORED_FLAGS.to_vec());
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail with a one-bit difference
+    // --------------------------------
+    let mut flags = STORED_FLAGS;
+    flags[3] ^= 1;
+    let validate_ix = data_match_validate_ix(rule_set_addr, account, flags.to_vec());
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataMatchCheckFailed);
+
+    // --------------------------------
+    // Validate fail with bytes of the wrong length
+    // --------------------------------
+    let validate_ix = data_match_validate_ix(rule_set_addr, account, STORED_FLAGS[..3].to_vec());
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataMatchCheckFailed);
+
+    // --------------------------------
+    // Validate fail with account data too short
+    // --------------------------------
+    let validate_ix = data_match_validate_ix(rule_set_addr, short_account, STORED_FLAGS.to_vec());
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataMatchCheckFailed);
+}
+
+// Create a `validate` instruction comparing `flags` to the data of `account`.
+fn data_match_validate_ix(rule_set_addr: Pubkey, account: Pubkey, flags: Vec<u8>) -> Instruction {
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(account),
+        ),
+        (FLAGS.to_string(), PayloadType::Bytes(flags)),
+    ]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(account, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[test]
+fn set_default() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    assert_eq!(rule_set.default_rule(), None);
+
+    // The default is stored under the reserved operation.
+    rule_set.set_default(Rule::Pass).unwrap();
+    assert_eq!(rule_set.default_rule(), Some(&Rule::Pass));
+    assert_eq!(
+        rule_set.get(RuleSetV1::DEFAULT_OPERATION),
+        Some(&Rule::Pass)
+    );
+
+    // Like `add`, setting a second default fails.
+    assert!(rule_set.set_default(Rule::Pass).is_err());
+}
+
+#[tokio::test]
+async fn default_operation() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet where Transfer has its own Rule, Update falls back to its namespace, and
+    // every other operation uses the default Rule: the amount must be 1.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+    rule_set
+        .add(
+            Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            Rule::Namespace,
+        )
+        .unwrap();
+    rule_set
+        .add(Operation::UpdateNamespace.to_string(), Rule::Pass)
+        .unwrap();
+    rule_set
+        .set_default(Rule::Amount {
+            amount: 1,
+            operator: CompareOp::Eq,
+            field: PayloadKey::Amount.to_string(),
+        })
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass for listed operations that the default would fail
+    // --------------------------------
+    for operation in [
+        Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        },
+        Operation::Update {
+            scenario: utils::UpdateScenario::MetadataAuth,
+        },
+    ] {
+        let validate_ix = default_operation_validate_ix(rule_set_addr, operation.to_string(), 2);
+
+        process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+    }
+
+    // --------------------------------
+    // Validate an unlisted operation against the default
+    // --------------------------------
+    let delegate = Operation::Delegate {
+        scenario: utils::DelegateScenario::Token(utils::TokenDelegateRole::Sale),
+    }
+    .to_string();
+
+    let validate_ix = default_operation_validate_ix(rule_set_addr, delegate.clone(), 1);
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    let validate_ix = default_operation_validate_ix(rule_set_addr, delegate, 2);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+}
+
+#[tokio::test]
+async fn no_default_operation_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a RuleSet without a default Rule.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Fail to validate an unlisted operation.
+    let validate_ix =
+        default_operation_validate_ix(rule_set_addr, Operation::UpdateNamespace.to_string(), 1);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::OperationNotFound);
+}
+
+// Create a `validate` instruction for `operation` with `amount` in the payload.
+fn default_operation_validate_ix(
+    rule_set_addr: Pubkey,
+    operation: String,
+    amount: u64,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation,
+            payload: Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(amount))]),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
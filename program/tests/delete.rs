@@ -0,0 +1,215 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::DeleteBuilder, DeleteArgs, InstructionBuilder},
+    state::{Rule, RuleSetV1, RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_LIB_VERSION},
+};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn delete_rule_set() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Get the RuleSet PDA lamports.
+    let rule_set_lamports = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .lamports;
+
+    // --------------------------------
+    // Delete RuleSet
+    // --------------------------------
+    let refund_destination = Keypair::new().pubkey();
+
+    // Create a `delete` instruction.
+    let delete_ix = DeleteBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .refund_destination(refund_destination)
+        .build(DeleteArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let delete_tx = Transaction::new_signed_with_payer(
+        &[delete_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context
+        .banks_client
+        .process_transaction(delete_tx)
+        .await
+        .unwrap();
+
+    // Check that the RuleSet PDA is gone and the lamports were refunded.
+    assert!(context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .is_none());
+
+    let refund_destination_lamports = context
+        .banks_client
+        .get_balance(refund_destination)
+        .await
+        .unwrap();
+    assert_eq!(refund_destination_lamports, rule_set_lamports);
+}
+
+#[tokio::test]
+async fn delete_rule_set_wrong_owner_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Delete RuleSet with the wrong owner
+    // --------------------------------
+    let other_owner = Keypair::new();
+
+    // Create a `delete` instruction.
+    let delete_ix = DeleteBuilder::new()
+        .owner(other_owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .refund_destination(other_owner.pubkey())
+        .build(DeleteArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let delete_tx = Transaction::new_signed_with_payer(
+        &[delete_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &other_owner],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(delete_tx)
+        .await
+        .expect_err("delete should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetOwnerMismatch);
+}
+
+#[tokio::test]
+async fn delete_rule_set_with_wrong_lib_version_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Overwrite the lib version at the start of the only `RuleSet` revision.
+    let mut rule_set_account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    rule_set_account.data[RULE_SET_FIRST_REVISION_LOCATION] = RULE_SET_LIB_VERSION + 1;
+    context.set_account(&rule_set_addr, &rule_set_account.into());
+
+    // --------------------------------
+    // Delete RuleSet
+    // --------------------------------
+    // Create a `delete` instruction.
+    let delete_ix = DeleteBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .refund_destination(context.payer.pubkey())
+        .build(DeleteArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let delete_tx = Transaction::new_signed_with_payer(
+        &[delete_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(delete_tx)
+        .await
+        .expect_err("delete should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::InvalidVersion);
+}
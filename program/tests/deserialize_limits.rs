@@ -0,0 +1,242 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::CreateOrUpdateBuilder, CreateOrUpdateArgs, InstructionBuilder},
+    state::{
+        from_msgpack_slice, from_msgpack_slice_strict, Rule, RuleSetV1, MAX_DESERIALIZED_PUBKEYS,
+    },
+};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+// MessagePack markers for 32-bit length prefixes.
+const ARRAY32: u8 = 0xdd;
+const MAP32: u8 = 0xdf;
+
+#[test]
+fn oversized_operations_map_prefix_fails() {
+    // An empty RuleSet serializes with an empty fixmap as its last byte.
+    let rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    let mut data = serialize(&rule_set);
+    assert_eq!(data.pop(), Some(0x80));
+
+    // Claim a map of billions of operations.
+    data.push(MAP32);
+    data.extend_from_slice(&u32::MAX.to_be_bytes());
+
+    assert_eq!(
+        from_msgpack_slice::<RuleSetV1>(&data),
+        Err(RuleSetError::DataTypeMismatch)
+    );
+}
+
+#[test]
+fn oversized_rule_list_prefix_fails() {
+    // A RuleSet whose only Rule is an empty All serializes with an empty fixarray as its last
+    // byte.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::TransferNamespace.to_string(),
+            Rule::All { rules: vec![] },
+        )
+        .unwrap();
+    let mut data = serialize(&rule_set);
+    assert_eq!(data.pop(), Some(0x90));
+
+    // Claim a list of billions of Rules.
+    data.push(ARRAY32);
+    data.extend_from_slice(&u32::MAX.to_be_bytes());
+
+    assert_eq!(
+        from_msgpack_slice::<RuleSetV1>(&data),
+        Err(RuleSetError::DataTypeMismatch)
+    );
+
+    // The targeted lookup used by `Validate` fails the same way.
+    assert_eq!(
+        RuleSetV1::get_rule_for_operation(&data, &Operation::TransferNamespace.to_string()),
+        Err(RuleSetError::DataTypeMismatch)
+    );
+}
+
+#[test]
+fn too_many_pubkeys_fails() {
+    // A list one longer than the limit is rejected even though every element is present.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set.operations.insert(
+        Operation::TransferNamespace.to_string(),
+        Rule::ProgramOwnedList {
+            programs: vec![Pubkey::new_unique(); MAX_DESERIALIZED_PUBKEYS + 1],
+            field: "Destination".to_string(),
+        },
+    );
+    let data = serialize(&rule_set);
+
+    assert_eq!(
+        from_msgpack_slice::<RuleSetV1>(&data),
+        Err(RuleSetError::DataTypeMismatch)
+    );
+
+    // A list at the limit deserializes.
+    if let Some(Rule::ProgramOwnedList { programs, .. }) = rule_set
+        .operations
+        .get_mut(&Operation::TransferNamespace.to_string())
+    {
+        programs.pop();
+    }
+    let data = serialize(&rule_set);
+    assert_eq!(from_msgpack_slice::<RuleSetV1>(&data), Ok(rule_set));
+}
+
+#[test]
+fn truncated_data_fails() {
+    let rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    let data = serialize(&rule_set);
+
+    // Malformed data that is within the limits is still a deserialization error.
+    assert_eq!(
+        from_msgpack_slice::<RuleSetV1>(&data[..data.len() - 1]),
+        Err(RuleSetError::MessagePackDeserializationError)
+    );
+}
+
+#[test]
+fn trailing_bytes_fail_strict() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+    let mut data = serialize(&rule_set);
+
+    // The exact serialized bytes deserialize in strict mode.
+    assert_eq!(
+        from_msgpack_slice_strict::<RuleSetV1>(&data),
+        Ok(rule_set.clone())
+    );
+
+    // Junk after the `RuleSet` is ignored by the plain deserializer but rejected in strict mode.
+    data.extend_from_slice(&[0xc0, 0xde, 0xad]);
+    assert_eq!(from_msgpack_slice::<RuleSetV1>(&data), Ok(rule_set.clone()));
+    assert_eq!(
+        from_msgpack_slice_strict::<RuleSetV1>(&data),
+        Err(RuleSetError::DataTypeMismatch)
+    );
+
+    // The same applies to a single `Rule`.
+    let mut data = rmp_serde::to_vec(&Rule::Pass).unwrap();
+    data.push(0);
+    assert_eq!(Rule::from_bytes(&data), Err(RuleSetError::DataTypeMismatch));
+}
+
+#[tokio::test]
+async fn create_with_trailing_bytes_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Serialize a RuleSet with junk after it.
+    let rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    let mut serialized_rule_set = serialize(&rule_set);
+    serialized_rule_set.extend_from_slice(&[0; 8]);
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        "test rule_set".to_string(),
+    );
+
+    // Create a `create_or_update` instruction.
+    let create_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(create_tx)
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataTypeMismatch);
+}
+
+#[tokio::test]
+async fn create_with_oversized_prefix_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Serialize a RuleSet claiming a map of billions of operations.
+    let rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    let mut serialized_rule_set = serialize(&rule_set);
+    serialized_rule_set.pop();
+    serialized_rule_set.push(MAP32);
+    serialized_rule_set.extend_from_slice(&u32::MAX.to_be_bytes());
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        "test rule_set".to_string(),
+    );
+
+    // Create a `create_or_update` instruction.
+    let create_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(create_tx)
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataTypeMismatch);
+}
+
+// Serialize a RuleSet using RMP serde.
+fn serialize(rule_set: &RuleSetV1) -> Vec<u8> {
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    serialized_rule_set
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "test-bpf")]
+
+use mpl_token_auth_rules::error::RuleSetError;
+use num_traits::FromPrimitive;
+use solana_program::program_error::ProgramError;
+use std::collections::HashSet;
+
+#[test]
+fn program_owned_check_failed_error_code() {
+    let err: ProgramError = RuleSetError::ProgramOwnedCheckFailed.into();
+    assert_eq!(err, ProgramError::Custom(29));
+}
+
+#[test]
+fn rule_check_failed_error_codes_are_distinct() {
+    let codes = [
+        RuleSetError::AdditionalSignerCheckFailed,
+        RuleSetError::PubkeyMatchCheckFailed,
+        RuleSetError::PubkeyListMatchCheckFailed,
+        RuleSetError::PubkeyTreeMatchCheckFailed,
+        RuleSetError::PDAMatchCheckFailed,
+        RuleSetError::ProgramOwnedCheckFailed,
+        RuleSetError::ProgramOwnedListCheckFailed,
+        RuleSetError::ProgramOwnedTreeCheckFailed,
+        RuleSetError::AmountCheckFailed,
+        RuleSetError::FrequencyCheckFailed,
+        RuleSetError::IsWalletCheckFailed,
+        RuleSetError::ProgramOwnedSetCheckFailed,
+        RuleSetError::NotCheckFailed,
+    ]
+    .map(|err| err as u32);
+
+    assert_eq!(codes.iter().collect::<HashSet<_>>().len(), codes.len());
+}
+
+#[test]
+fn error_codes_are_stable() {
+    // These codes are returned on-chain and must never change.  New errors are appended.
+    let codes = [
+        (RuleSetError::NumericalOverflow, 0),
+        (RuleSetError::DataTypeMismatch, 1),
+        (RuleSetError::DataSliceUnexpectedIndexError, 2),
+        (RuleSetError::IncorrectOwner, 3),
+        (RuleSetError::PayloadVecIndexError, 4),
+        (RuleSetError::DerivedKeyInvalid, 5),
+        (RuleSetError::PayerIsNotSigner, 6),
+        (RuleSetError::NotImplemented, 7),
+        (RuleSetError::BorshSerializationError, 8),
+        (RuleSetError::BorshDeserializationError, 9),
+        (RuleSetError::ValueOccupied, 10),
+        (RuleSetError::DataIsEmpty, 11),
+        (RuleSetError::MessagePackSerializationError, 12),
+        (RuleSetError::MessagePackDeserializationError, 13),
+        (RuleSetError::MissingAccount, 14),
+        (RuleSetError::MissingPayloadValue, 15),
+        (RuleSetError::RuleSetOwnerMismatch, 16),
+        (RuleSetError::NameTooLong, 17),
+        (RuleSetError::OperationNotFound, 18),
+        (RuleSetError::RuleAuthorityIsNotSigner, 19),
+        (RuleSetError::UnsupportedRuleSetRevMapVersion, 20),
+        (RuleSetError::UnsupportedRuleSetVersion, 21),
+        (RuleSetError::UnexpectedRuleSetFailure, 22),
+        (RuleSetError::RuleSetRevisionNotAvailable, 23),
+        (RuleSetError::AdditionalSignerCheckFailed, 24),
+        (RuleSetError::PubkeyMatchCheckFailed, 25),
+        (RuleSetError::PubkeyListMatchCheckFailed, 26),
+        (RuleSetError::PubkeyTreeMatchCheckFailed, 27),
+        (RuleSetError::PDAMatchCheckFailed, 28),
+        (RuleSetError::ProgramOwnedCheckFailed, 29),
+        (RuleSetError::ProgramOwnedListCheckFailed, 30),
+        (RuleSetError::ProgramOwnedTreeCheckFailed, 31),
+        (RuleSetError::AmountCheckFailed, 32),
+        (RuleSetError::FrequencyCheckFailed, 33),
+        (RuleSetError::IsWalletCheckFailed, 34),
+        (RuleSetError::ProgramOwnedSetCheckFailed, 35),
+        (RuleSetError::EmptyAnyRule, 36),
+        (RuleSetError::NotCheckFailed, 37),
+        (RuleSetError::PubkeyListTooLong, 38),
+        (RuleSetError::OwnerIsNotSigner, 39),
+        (RuleSetError::JsonSerializationError, 40),
+        (RuleSetError::JsonDeserializationError, 41),
+        (RuleSetError::RuleSetIsFrozen, 42),
+        (RuleSetError::InvalidNewOwner, 43),
+        (RuleSetError::TimestampCheckFailed, 44),
+        (RuleSetError::SlotCheckFailed, 45),
+        (RuleSetError::InvalidName, 46),
+        (RuleSetError::Uninitialized, 47),
+        (RuleSetError::ProofTooLong, 48),
+        (RuleSetError::RuleSetTooLarge, 49),
+        (RuleSetError::RuleTreeTooDeep, 50),
+        (RuleSetError::LastUpdateCheckFailed, 51),
+        (RuleSetError::AdditionalSignerListCheckFailed, 52),
+        (RuleSetError::InvalidSignerThreshold, 53),
+        (RuleSetError::DataMatchCheckFailed, 54),
+        (RuleSetError::EmptyName, 55),
+        (RuleSetError::MissingOwner, 56),
+        (RuleSetError::AlreadyInitialized, 57),
+        (RuleSetError::PubkeyListDenyCheckFailed, 58),
+        (RuleSetError::PayloadTooLarge, 59),
+        (RuleSetError::TooManyTreeRoots, 60),
+        (RuleSetError::InvalidInstructionData, 61),
+        (RuleSetError::RuleSetMigrationRequired, 62),
+        (RuleSetError::DuplicatePayloadKey, 63),
+        (RuleSetError::InvalidVersion, 64),
+    ];
+
+    for (err, code) in codes {
+        assert_eq!(err.code(), code);
+        assert_eq!(ProgramError::from(err), ProgramError::Custom(code));
+    }
+}
+
+#[test]
+fn error_messages_describe_the_failure() {
+    // Errors print as human readable messages rather than their codes.
+    assert!(RuleSetError::ProgramOwnedCheckFailed
+        .to_string()
+        .contains("target account is not owned by the expected program"));
+
+    // Errors can be returned as standard errors.
+    let err: Box<dyn std::error::Error> = Box::new(RuleSetError::MissingAccount);
+    assert_eq!(
+        err.to_string(),
+        "An account needed by a rule was not passed in"
+    );
+}
+
+#[test]
+fn error_messages_are_distinct() {
+    let messages: Vec<String> = (0..)
+        .map_while(RuleSetError::from_u32)
+        .map(|err| err.to_string())
+        .collect();
+
+    assert!(messages.iter().all(|message| !message.is_empty()));
+    assert_eq!(
+        messages.iter().collect::<HashSet<_>>().len(),
+        messages.len()
+    );
+}
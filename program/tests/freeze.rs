@@ -0,0 +1,254 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{
+        builders::{CreateOrUpdateBuilder, DeleteBuilder, FreezeBuilder, ThawBuilder},
+        CreateOrUpdateArgs, DeleteArgs, FreezeArgs, InstructionBuilder, ThawArgs,
+    },
+    state::{Rule, RuleSetV1},
+};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn freeze_blocks_update_and_delete() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set.clone(), "test rule_set".to_string())
+            .await;
+
+    // --------------------------------
+    // Freeze RuleSet
+    // --------------------------------
+    // Create a `freeze` instruction.
+    let freeze_ix = FreezeBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(FreezeArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let freeze_tx = Transaction::new_signed_with_payer(
+        &[freeze_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context
+        .banks_client
+        .process_transaction(freeze_tx)
+        .await
+        .unwrap();
+
+    // --------------------------------
+    // Update fail
+    // --------------------------------
+    // Add an operation to the RuleSet.
+    rule_set
+        .add(
+            Operation::Delegate {
+                scenario: utils::DelegateScenario::Token(utils::TokenDelegateRole::Transfer),
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Serialize the RuleSet using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // Create a `create_or_update` instruction.
+    let update_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let update_tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Fail to update the frozen RuleSet.
+    let err = context
+        .banks_client
+        .process_transaction(update_tx)
+        .await
+        .expect_err("update should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetIsFrozen);
+
+    // --------------------------------
+    // Delete fail
+    // --------------------------------
+    let refund_destination = Keypair::new().pubkey();
+
+    // Create a `delete` instruction.
+    let delete_ix = DeleteBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .refund_destination(refund_destination)
+        .build(DeleteArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let delete_tx = Transaction::new_signed_with_payer(
+        &[delete_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Fail to delete the frozen RuleSet.
+    let err = context
+        .banks_client
+        .process_transaction(delete_tx)
+        .await
+        .expect_err("delete should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetIsFrozen);
+
+    // --------------------------------
+    // Thaw RuleSet
+    // --------------------------------
+    // Create a `thaw` instruction.
+    let thaw_ix = ThawBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(ThawArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let thaw_tx = Transaction::new_signed_with_payer(
+        &[thaw_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context
+        .banks_client
+        .process_transaction(thaw_tx)
+        .await
+        .unwrap();
+
+    // --------------------------------
+    // Update pass
+    // --------------------------------
+    // Add another operation so the update transaction differs from the failed one.
+    rule_set
+        .add(
+            Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Update the thawed RuleSet.
+    create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+}
+
+#[tokio::test]
+async fn freeze_wrong_owner_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Freeze RuleSet with the wrong owner
+    // --------------------------------
+    let other_owner = Keypair::new();
+
+    // Create a `freeze` instruction.
+    let freeze_ix = FreezeBuilder::new()
+        .owner(other_owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(FreezeArgs::V1 {
+            rule_set_name: "test rule_set".to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let freeze_tx = Transaction::new_signed_with_payer(
+        &[freeze_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &other_owner],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(freeze_tx)
+        .await
+        .expect_err("freeze should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetOwnerMismatch);
+}
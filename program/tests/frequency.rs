@@ -2,17 +2,21 @@
 
 pub mod utils;
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
     payload::Payload,
-    pda::find_rule_set_state_address,
-    state::{Rule, RuleSetV1},
+    pda::find_frequency_address,
+    state::{FrequencyAccount, FrequencySlot, Rule, RuleSetV1},
+};
+use solana_program::{
+    clock::Clock, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey,
+};
+use solana_program_test::{tokio, BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::Account, signature::Signer, signer::keypair::Keypair, transaction::TransactionError,
 };
-use solana_program::program_error::ProgramError;
-use solana_program_test::tokio;
-use solana_program_test::BanksClientError;
-use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::TransactionError};
 use utils::{program_test, Operation};
 
 #[tokio::test]
@@ -26,6 +30,8 @@ async fn test_frequency() {
     let rule_authority = Keypair::new();
     let rule = Rule::Frequency {
         authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
     };
 
     // Create a RuleSet.
@@ -89,7 +95,7 @@ async fn test_frequency() {
     // Validate wrong authority
     // --------------------------------
     let (rule_set_state_addr, _rule_set_bump) =
-        find_rule_set_state_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
+        find_frequency_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
 
     // Create a `validate` instruction.
     let validate_ix = ValidateBuilder::new()
@@ -118,8 +124,7 @@ async fn test_frequency() {
     assert_custom_error!(err, RuleSetError::RuleAuthorityIsNotSigner);
 
     // --------------------------------
-    // Validate not implemented
-    // (this will become pass later)
+    // Validate pass on first use
     // --------------------------------
     // Create a `validate` instruction.
     let validate_ix = ValidateBuilder::new()
@@ -141,10 +146,355 @@ async fn test_frequency() {
         .unwrap()
         .instruction();
 
+    // Validate Transfer operation.
+    process_passing_validate_ix!(
+        &mut context,
+        validate_ix.clone(),
+        vec![&rule_authority],
+        None
+    )
+    .await;
+
+    // Check that the Frequency account was created and the last update time was stored.
+    let frequency_account = context
+        .banks_client
+        .get_account(rule_set_state_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    let frequency_account = FrequencyAccount::try_from_slice(&frequency_account.data).unwrap();
+    assert!(frequency_account.last_update > 0);
+    assert_eq!(frequency_account.period, 3600);
+
+    // --------------------------------
+    // Validate fail on immediate re-use
+    // --------------------------------
+    // Warp ahead a few slots (far less than the period) so the same instruction can be sent in a
+    // new transaction with a new blockhash.
+    context.warp_to_slot(10).unwrap();
+
     // Fail to validate Transfer operation.
     let err =
         process_failing_validate_ix!(&mut context, validate_ix, vec![&rule_authority], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::NotImplemented);
+    assert_custom_error!(err, RuleSetError::FrequencyCheckFailed);
+}
+
+#[tokio::test]
+async fn frequency_max_period_overflows() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule with a period so large that adding it to any later time overflows.
+    let rule_authority = Keypair::new();
+    let rule = Rule::Frequency {
+        authority: rule_authority.pubkey(),
+        period: i64::MAX,
+        name: String::new(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Store a previous use
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    let (rule_set_state_addr, _rule_set_bump) =
+        find_frequency_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
+
+    // Write a Frequency account as if the Rule had already been used.
+    let frequency_account = FrequencyAccount::new(1, i64::MAX);
+    context.set_account(
+        &rule_set_state_addr,
+        &Account {
+            lamports: 1_000_000_000,
+            data: frequency_account.try_to_vec().unwrap(),
+            owner: mpl_token_auth_rules::ID,
+            ..Account::default()
+        }
+        .into(),
+    );
+
+    // --------------------------------
+    // Validate overflow
+    // --------------------------------
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .payer(context.payer.pubkey())
+        .rule_authority(rule_authority.pubkey())
+        .rule_set_state_pda(rule_set_state_addr)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: true,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err =
+        process_failing_validate_ix!(&mut context, validate_ix, vec![&rule_authority], None).await;
+
+    // The next valid time overflows, which is reported instead of panicking.
+    assert_custom_error!(err, RuleSetError::NumericalOverflow);
+}
+
+#[tokio::test]
+async fn frequency_per_operation() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create Rules allowing a transfer every 60 seconds and a delegate every 10 seconds.
+    let rule_authority = Keypair::new();
+    let transfer = Operation::TransferNamespace.to_string();
+    let delegate = Operation::DelegateNamespace.to_string();
+
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    for (operation, period) in [(&transfer, 60), (&delegate, 10)] {
+        rule_set
+            .add(
+                operation.clone(),
+                Rule::Frequency {
+                    authority: rule_authority.pubkey(),
+                    period,
+                    name: operation.clone(),
+                },
+            )
+            .unwrap();
+    }
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    let mint = Keypair::new().pubkey();
+    let (rule_set_state_addr, _rule_set_bump) =
+        find_frequency_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
+
+    let payer = context.payer.pubkey();
+    let validate_ix = |operation: &String| {
+        frequency_validate_ix(
+            payer,
+            rule_set_addr,
+            mint,
+            rule_set_state_addr,
+            rule_authority.pubkey(),
+            operation.clone(),
+        )
+    };
+
+    // --------------------------------
+    // Validate pass on first use of each operation
+    // --------------------------------
+    set_time(&mut context, 2, 1_000).await;
+    for operation in [&transfer, &delegate] {
+        process_passing_validate_ix!(
+            &mut context,
+            validate_ix(operation),
+            vec![&rule_authority],
+            None
+        )
+        .await;
+    }
+
+    // Each operation has its own slot in the Frequency account.
+    let frequency_account = context
+        .banks_client
+        .get_account(rule_set_state_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        FrequencyAccount::try_from_slice(&frequency_account.data)
+            .unwrap()
+            .slots,
+        vec![
+            FrequencySlot {
+                name: transfer.clone(),
+                last_update: 1_000,
+            },
+            FrequencySlot {
+                name: delegate.clone(),
+                last_update: 1_000,
+            },
+        ]
+    );
+
+    // --------------------------------
+    // Validate only the delegate interval has passed
+    // --------------------------------
+    set_time(&mut context, 4, 1_015).await;
+    process_passing_validate_ix!(
+        &mut context,
+        validate_ix(&delegate),
+        vec![&rule_authority],
+        None
+    )
+    .await;
+
+    let err = process_failing_validate_ix!(
+        &mut context,
+        validate_ix(&transfer),
+        vec![&rule_authority],
+        None
+    )
+    .await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::FrequencyCheckFailed);
+
+    // --------------------------------
+    // Validate pass once the transfer interval has passed
+    // --------------------------------
+    set_time(&mut context, 6, 1_060).await;
+    process_passing_validate_ix!(
+        &mut context,
+        validate_ix(&transfer),
+        vec![&rule_authority],
+        None
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn frequency_under_failed_not_does_not_update_state() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule that passes through `Pass` because the negated Frequency check passes.
+    let rule_authority = Keypair::new();
+    let transfer = Operation::TransferNamespace.to_string();
+
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            transfer.clone(),
+            Rule::Any {
+                rules: vec![
+                    Rule::Not {
+                        rule: Box::new(Rule::Frequency {
+                            authority: rule_authority.pubkey(),
+                            period: 60,
+                            name: transfer.clone(),
+                        }),
+                    },
+                    Rule::Pass,
+                ],
+            },
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    let mint = Keypair::new().pubkey();
+    let (rule_set_state_addr, _rule_set_bump) =
+        find_frequency_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    set_time(&mut context, 2, 1_000).await;
+    let validate_ix = frequency_validate_ix(
+        context.payer.pubkey(),
+        rule_set_addr,
+        mint,
+        rule_set_state_addr,
+        rule_authority.pubkey(),
+        transfer.clone(),
+    );
+    process_passing_validate_ix!(&mut context, validate_ix, vec![&rule_authority], None).await;
+
+    // The Frequency check under the failed `Not` did not record the transfer.
+    let last_update = context
+        .banks_client
+        .get_account(rule_set_state_addr)
+        .await
+        .unwrap()
+        .map(|account| {
+            FrequencyAccount::try_from_slice(&account.data)
+                .unwrap()
+                .last_update_of(&transfer)
+        });
+    assert_eq!(last_update.unwrap_or_default(), 0);
+}
+
+#[test]
+fn frequency_account_without_slots() {
+    // An account written before named slots were added still deserializes.
+    let mut data = FrequencyAccount::new(5, 60).try_to_vec().unwrap();
+    data.truncate(17);
+
+    let frequency_account = FrequencyAccount::try_from_slice(&data).unwrap();
+    assert_eq!(frequency_account, FrequencyAccount::new(5, 60));
+    assert_eq!(frequency_account.last_update_of(""), 5);
+    assert_eq!(frequency_account.last_update_of("Transfer"), 0);
+}
+
+// Warp to `slot`, for a new blockhash, and set the `Clock` time to `unix_timestamp`.
+async fn set_time(context: &mut ProgramTestContext, slot: u64, unix_timestamp: i64) {
+    context.warp_to_slot(slot).unwrap();
+
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    context.set_sysvar(&Clock {
+        unix_timestamp,
+        ..clock
+    });
+}
+
+// Create a `validate` instruction that updates the Frequency account for `operation`.
+fn frequency_validate_ix(
+    payer: Pubkey,
+    rule_set_addr: Pubkey,
+    mint: Pubkey,
+    rule_set_state_addr: Pubkey,
+    rule_authority: Pubkey,
+    operation: String,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .payer(payer)
+        .rule_authority(rule_authority)
+        .rule_set_state_pda(rule_set_state_addr)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation,
+            payload: Payload::default(),
+            update_rule_state: true,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
 }
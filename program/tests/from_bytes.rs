@@ -0,0 +1,111 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[test]
+fn rule_from_bytes() {
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Amount {
+                amount: 1,
+                operator: CompareOp::Eq,
+                field: PayloadKey::Amount.to_string(),
+            },
+            Rule::AdditionalSigner {
+                account: Keypair::new().pubkey(),
+            },
+        ],
+    };
+
+    let data = rmp_serde::to_vec(&rule).unwrap();
+    assert_eq!(Rule::from_bytes(&data).unwrap(), rule);
+
+    // Truncated data fails with the crate's error type.
+    assert_eq!(
+        Rule::from_bytes(&data[..data.len() - 1]).unwrap_err(),
+        RuleSetError::MessagePackDeserializationError
+    );
+}
+
+#[tokio::test]
+async fn rule_set_from_account_data() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::ProgramOwned {
+                program: Keypair::new().pubkey(),
+                field: PayloadKey::Destination.to_string(),
+            },
+        )
+        .unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set.clone(), "test rule_set".to_string())
+            .await;
+
+    // Read the raw account data back into an equal RuleSet.
+    let data = get_account_data(&mut context, rule_set_addr).await;
+    assert_eq!(RuleSetV1::try_from(data.as_slice()).unwrap(), rule_set);
+
+    // --------------------------------
+    // Update RuleSet
+    // --------------------------------
+    rule_set
+        .add(Operation::DelegateNamespace.to_string(), Rule::Pass)
+        .unwrap();
+
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set.clone(), "test rule_set".to_string())
+            .await;
+
+    // The latest revision is read.
+    let data = get_account_data(&mut context, rule_set_addr).await;
+    assert_eq!(RuleSetV1::try_from(data.as_slice()).unwrap(), rule_set);
+
+    // --------------------------------
+    // Fail on data not holding a RuleSet
+    // --------------------------------
+    assert_eq!(
+        RuleSetV1::try_from([0; 64].as_slice()).unwrap_err(),
+        RuleSetError::Uninitialized
+    );
+    assert_eq!(
+        RuleSetV1::try_from(&data[..10]).unwrap_err(),
+        RuleSetError::DataTypeMismatch
+    );
+}
+
+async fn get_account_data(
+    context: &mut solana_program_test::ProgramTestContext,
+    address: Pubkey,
+) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
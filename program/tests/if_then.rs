@@ -0,0 +1,214 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::BorshDeserialize;
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    pda::find_frequency_address,
+    state::{CompareOp, FrequencyAccount, Rule, RuleSetV1},
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn if_then() {
+    let mut context = program_test().start_with_context().await;
+    let marketplace = Keypair::new().pubkey();
+    let other = Keypair::new().pubkey();
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // The marketplace may transfer less than 10, anyone else less than 2.
+    let amount_below = |amount| Rule::Amount {
+        amount,
+        operator: CompareOp::Lt,
+        field: PayloadKey::Amount.to_string(),
+    };
+    let is_marketplace = Box::new(Rule::PubkeyMatch {
+        pubkey: marketplace,
+        field: PayloadKey::Authority.to_string(),
+    });
+
+    // Create a RuleSet, where Update only limits the marketplace.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            transfer(),
+            Rule::IfThen {
+                condition: is_marketplace.clone(),
+                then_rule: Box::new(amount_below(10)),
+                else_rule: Some(Box::new(amount_below(2))),
+            },
+        )
+        .unwrap();
+    rule_set
+        .add(
+            update(),
+            Rule::IfThen {
+                condition: is_marketplace,
+                then_rule: Box::new(amount_below(10)),
+                else_rule: None,
+            },
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate the then branch
+    // --------------------------------
+    let validate_ix = if_then_validate_ix(rule_set_addr, transfer(), marketplace, 5);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    let validate_ix = if_then_validate_ix(rule_set_addr, transfer(), marketplace, 20);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+
+    // --------------------------------
+    // Validate the else branch
+    // --------------------------------
+    let validate_ix = if_then_validate_ix(rule_set_addr, transfer(), other, 1);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    let validate_ix = if_then_validate_ix(rule_set_addr, transfer(), other, 5);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+
+    // --------------------------------
+    // Validate pass with no else branch
+    // --------------------------------
+    let validate_ix = if_then_validate_ix(rule_set_addr, update(), other, 20);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    let validate_ix = if_then_validate_ix(rule_set_addr, update(), marketplace, 20);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+}
+
+#[tokio::test]
+async fn if_then_condition_does_not_update_rule_state() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // A `Frequency` condition guarding a branch that fails without an amount, with a `Pass` as
+    // the fallback so that validation still succeeds.
+    let rule_authority = Keypair::new();
+    let rule = Rule::Any {
+        rules: vec![
+            Rule::IfThen {
+                condition: Box::new(Rule::Frequency {
+                    authority: rule_authority.pubkey(),
+                    period: 3600,
+                    name: String::new(),
+                }),
+                then_rule: Box::new(Rule::Amount {
+                    amount: 1,
+                    operator: CompareOp::GtEq,
+                    field: PayloadKey::Amount.to_string(),
+                }),
+                else_rule: None,
+            },
+            Rule::Pass,
+        ],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(transfer(), rule).unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    let (rule_set_state_addr, _rule_set_bump) =
+        find_frequency_address(context.payer.pubkey(), "test rule_set".to_string(), mint);
+
+    // Create a `validate` instruction updating rule state.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .payer(context.payer.pubkey())
+        .rule_authority(rule_authority.pubkey())
+        .rule_set_state_pda(rule_set_state_addr)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: transfer(),
+            payload: Payload::default(),
+            update_rule_state: true,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![&rule_authority], None).await;
+
+    // The condition passed, but it did not record a use, as the branch it guards failed.
+    let frequency_account = context
+        .banks_client
+        .get_account(rule_set_state_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    let frequency_account = FrequencyAccount::try_from_slice(&frequency_account.data).unwrap();
+    assert_eq!(frequency_account.last_update, 0);
+}
+
+fn transfer() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string()
+}
+
+fn update() -> String {
+    Operation::Update {
+        scenario: utils::UpdateScenario::MetadataAuth,
+    }
+    .to_string()
+}
+
+// Create a `validate` instruction for `operation` by `authority` with the given amount.
+fn if_then_validate_ix(
+    rule_set_addr: Pubkey,
+    operation: String,
+    authority: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let payload = Payload::from([
+        (
+            PayloadKey::Authority.to_string(),
+            PayloadType::Pubkey(authority),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(amount)),
+    ]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation,
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
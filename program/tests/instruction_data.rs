@@ -0,0 +1,97 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+    state::{Rule, RuleSetV1},
+};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn unknown_instruction_data_version_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // An unknown argument version follows the instruction tag.
+    let mut unknown_version_ix = validate_ix.clone();
+    unknown_version_ix.data[1] = u8::MAX;
+
+    // An unknown instruction tag.
+    let mut unknown_instruction_ix = validate_ix.clone();
+    unknown_instruction_ix.data[0] = u8::MAX;
+
+    // Bytes left over after the arguments.
+    let mut trailing_bytes_ix = validate_ix.clone();
+    trailing_bytes_ix.data.push(0);
+
+    for ix in [
+        unknown_version_ix,
+        unknown_instruction_ix,
+        trailing_bytes_ix,
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .expect_err("validate should fail");
+
+        // Check that error is what we expect.
+        assert_custom_error!(err, RuleSetError::InvalidInstructionData);
+    }
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
@@ -8,13 +8,37 @@ use mpl_token_auth_rules::{
     payload::{Payload, PayloadType},
     state::{Rule, RuleSetV1},
 };
+use solana_program::system_program;
 use solana_program_test::tokio;
-use solana_sdk::{instruction::AccountMeta, signature::Signer, signer::keypair::Keypair};
+use solana_sdk::{
+    account::Account, instruction::AccountMeta, signature::Signer, signer::keypair::Keypair,
+};
 use utils::{program_test, Operation, PayloadKey};
 
 #[tokio::test]
 async fn is_wallet() {
-    let mut context = program_test().start_with_context().await;
+    // Create a funded wallet, a System Program account holding data, and a program-owned account.
+    let wallet = Keypair::new().pubkey();
+    let data_account = Keypair::new().pubkey();
+    let program_owned_account = Keypair::new().pubkey();
+
+    let mut program_test = program_test();
+    for (account, data, owner) in [
+        (wallet, vec![], system_program::ID),
+        (data_account, vec![1; 8], system_program::ID),
+        (program_owned_account, vec![], Keypair::new().pubkey()),
+    ] {
+        program_test.add_account(
+            account,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
 
     // --------------------------------
     // Create RuleSet
@@ -40,26 +64,50 @@ async fn is_wallet() {
     let rule_set_addr =
         create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
 
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
     // --------------------------------
-    // Validate not implemented
-    // (this will become pass later)
+    // Validate fail
     // --------------------------------
-    // Keypair to check.
-    let wallet = Keypair::new();
+    for source in [data_account, program_owned_account] {
+        let payload =
+            Payload::from([(PayloadKey::Source.to_string(), PayloadType::Pubkey(source))]);
 
-    // Create a Keypair to simulate a token mint address.
-    let mint = Keypair::new().pubkey();
+        // Create a `validate` instruction.
+        let validate_ix = ValidateBuilder::new()
+            .rule_set_pda(rule_set_addr)
+            .mint(mint)
+            .additional_rule_accounts(vec![AccountMeta::new_readonly(source, false)])
+            .build(ValidateArgs::V1 {
+                operation: Operation::Transfer {
+                    scenario: utils::TransferScenario::Holder,
+                }
+                .to_string(),
+                payload,
+                update_rule_state: false,
+                rule_set_revision: None,
+            })
+            .unwrap()
+            .instruction();
+
+        // Fail to validate Transfer operation.
+        let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+        // Check that error is what we expect.
+        assert_custom_error!(err, RuleSetError::IsWalletCheckFailed);
+    }
 
-    let payload = Payload::from([(
-        PayloadKey::Source.to_string(),
-        PayloadType::Pubkey(wallet.pubkey()),
-    )]);
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let payload = Payload::from([(PayloadKey::Source.to_string(), PayloadType::Pubkey(wallet))]);
 
     // Create a `validate` instruction.
     let validate_ix = ValidateBuilder::new()
         .rule_set_pda(rule_set_addr)
         .mint(mint)
-        .additional_rule_accounts(vec![AccountMeta::new_readonly(wallet.pubkey(), false)])
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(wallet, false)])
         .build(ValidateArgs::V1 {
             operation: Operation::Transfer {
                 scenario: utils::TransferScenario::Holder,
@@ -72,9 +120,6 @@ async fn is_wallet() {
         .unwrap()
         .instruction();
 
-    // Fail to validate Transfer operation.
-    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
-
-    // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::NotImplemented);
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
@@ -0,0 +1,71 @@
+#![cfg(all(feature = "test-bpf", feature = "serde-with-feature"))]
+
+pub mod utils;
+
+use mpl_token_auth_rules::state::{CompareOp, Rule, RuleSetV1};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn json_round_trip() {
+    // Create some rules, including nested composites.
+    let adtl_signer = Rule::AdditionalSigner {
+        account: Keypair::new().pubkey(),
+    };
+    let amount_check = Rule::Amount {
+        amount: 5,
+        operator: CompareOp::LtEq,
+        field: PayloadKey::Amount.to_string(),
+    };
+    let not_pubkey_match = Rule::Not {
+        rule: Box::new(Rule::PubkeyMatch {
+            pubkey: Keypair::new().pubkey(),
+            field: PayloadKey::Destination.to_string(),
+        }),
+    };
+    let overall_rule = Rule::All {
+        rules: vec![
+            Rule::Any {
+                rules: vec![adtl_signer, amount_check],
+            },
+            not_pubkey_match,
+        ],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            overall_rule,
+        )
+        .unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Namespace)
+        .unwrap();
+
+    // JSON -> RuleSet.
+    let json = rule_set.to_json_pretty().unwrap();
+    let from_json = RuleSetV1::from_json(&json).unwrap();
+    assert_eq!(from_json, rule_set);
+
+    // RuleSet -> rmp bytes -> RuleSet.
+    let mut serialized_rule_set = Vec::new();
+    from_json
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    let from_rmp: RuleSetV1 = rmp_serde::from_slice(&serialized_rule_set).unwrap();
+
+    // RuleSet -> JSON.  Operations are stored in a map so compare the parsed JSON values rather
+    // than the strings, which do not have a fixed key order.
+    let round_trip_json = from_rmp.to_json_pretty().unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&round_trip_json).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::state::{Key, Rule, RuleSetV1};
+use num_traits::FromPrimitive;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn rule_set_account_key() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Check the account key
+    // --------------------------------
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    // The first byte of the account identifies it as a RuleSet.
+    assert_eq!(Key::from_u8(data[0]), Some(Key::RuleSet));
+    assert_eq!(Key::from_account_data(&data), Some(Key::RuleSet));
+}
+
+#[test]
+fn key_from_account_data() {
+    assert_eq!(Key::from_account_data(&[]), None);
+    assert_eq!(Key::from_account_data(&[0; 8]), Some(Key::Uninitialized));
+    assert_eq!(
+        Key::from_account_data(&[Key::Frequency as u8, 1, 2]),
+        Some(Key::Frequency)
+    );
+    assert_eq!(Key::from_account_data(&[u8::MAX]), None);
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+// The on-chain time used by the test.
+const NOW: i64 = 1_000_000;
+
+// The offset of the timestamp in the mock account data.
+const OFFSET: usize = 8;
+
+// The maximum age of the timestamp, in seconds.
+const MAX_AGE: i64 = 3_600;
+
+#[tokio::test]
+async fn last_update_within() {
+    let mut context = program_test().start_with_context().await;
+
+    // Fix the on-chain time.
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    context.set_sysvar(&Clock {
+        unix_timestamp: NOW,
+        ..clock
+    });
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The destination must have been updated within the last hour.
+    let rule = Rule::LastUpdateWithin {
+        field: PayloadKey::Destination.to_string(),
+        offset: OFFSET,
+        max_age: MAX_AGE,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass just inside the window
+    // --------------------------------
+    let inside = add_mock_account(&mut context, timestamp_data(NOW - MAX_AGE));
+    let validate_ix = last_update_validate_ix(rule_set_addr, inside);
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail just outside the window
+    // --------------------------------
+    let outside = add_mock_account(&mut context, timestamp_data(NOW - MAX_AGE - 1));
+    let validate_ix = last_update_validate_ix(rule_set_addr, outside);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::LastUpdateCheckFailed);
+
+    // --------------------------------
+    // Validate fail with data too short to hold the timestamp
+    // --------------------------------
+    let mut data = timestamp_data(NOW);
+    data.pop();
+    let short = add_mock_account(&mut context, data);
+    let validate_ix = last_update_validate_ix(rule_set_addr, short);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::LastUpdateCheckFailed);
+}
+
+// Account data holding `timestamp` at `OFFSET`.
+fn timestamp_data(timestamp: i64) -> Vec<u8> {
+    let mut data = vec![0; OFFSET];
+    data.extend_from_slice(&timestamp.to_le_bytes());
+    data
+}
+
+// Add an account holding `data`, returning its key.
+fn add_mock_account(context: &mut ProgramTestContext, data: Vec<u8>) -> Pubkey {
+    let key = Keypair::new().pubkey();
+    context.set_account(
+        &key,
+        &Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        }
+        .into(),
+    );
+    key
+}
+
+// Create a `validate` instruction for a transfer to `destination`.
+fn last_update_validate_ix(rule_set_addr: Pubkey, destination: Pubkey) -> Instruction {
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(destination),
+    )]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(destination, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
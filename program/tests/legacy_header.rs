@@ -0,0 +1,237 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::BorshDeserialize;
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{
+        builders::{CreateOrUpdateBuilder, DeleteBuilder, FreezeBuilder, ValidateBuilder},
+        CreateOrUpdateArgs, DeleteArgs, FreezeArgs, InstructionBuilder, ValidateArgs,
+    },
+    payload::Payload,
+    pda::find_rule_set_address,
+    state::{
+        Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, RULE_SET_BUMP_LOCATION,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_SERIALIZED_HEADER_LEN,
+    },
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{
+    add_legacy_rule_set, program_test, Operation, LEGACY_REVISIONS,
+    LEGACY_REV_MAP_VERSION_LOCATION, LEGACY_RULE_SET, LEGACY_RULE_SET_NAME,
+};
+
+#[tokio::test]
+async fn legacy_header_update_and_freeze() {
+    let mut context = program_test().start_with_context().await;
+    let (owner, rule_set_addr) = add_legacy_rule_set(&mut context);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Both revisions are read through the legacy header.
+    let validate_ix = legacy_validate_ix(rule_set_addr, transfer_operation(), Some(0));
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    let validate_ix = legacy_validate_ix(rule_set_addr, delegate_operation(), None);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Freeze fail
+    // --------------------------------
+    // The legacy header has no room for the frozen flag.
+    let err = process_tx(&mut context, freeze_ix(&owner, rule_set_addr), &owner)
+        .await
+        .expect_err("freeze should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetMigrationRequired);
+
+    // --------------------------------
+    // Update pass
+    // --------------------------------
+    let mut rule_set = RuleSetV1::new(LEGACY_RULE_SET_NAME.to_string(), owner.pubkey());
+    rule_set.add(transfer_operation(), Rule::Pass).unwrap();
+    rule_set.add(delegate_operation(), Rule::Pass).unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+
+    process_tx(
+        &mut context,
+        update_ix(&owner, rule_set_addr, &rule_set),
+        &owner,
+    )
+    .await
+    .unwrap();
+
+    // The update moved the legacy revisions after the current header and the stored bump.
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    let header = RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    assert_eq!(header.owner, owner.pubkey());
+    assert!(!header.frozen);
+
+    let (_, bump) = find_rule_set_address(owner.pubkey(), LEGACY_RULE_SET_NAME.to_string());
+    assert_eq!(data[RULE_SET_BUMP_LOCATION], bump);
+
+    let shift = RULE_SET_FIRST_REVISION_LOCATION - LEGACY_REVISIONS[0];
+    let revision_map =
+        RuleSetRevisionMapV1::try_from_slice(&data[header.rev_map_version_location + 1..]).unwrap();
+    assert_eq!(
+        revision_map.rule_set_revisions,
+        vec![
+            LEGACY_REVISIONS[0] + shift,
+            LEGACY_REVISIONS[1] + shift,
+            LEGACY_REV_MAP_VERSION_LOCATION + shift,
+        ]
+    );
+
+    // The moved revisions are unchanged.
+    assert_eq!(
+        &data[LEGACY_REVISIONS[0] + shift..LEGACY_REV_MAP_VERSION_LOCATION + shift],
+        &LEGACY_RULE_SET[LEGACY_REVISIONS[0]..LEGACY_REV_MAP_VERSION_LOCATION]
+    );
+    assert_eq!(RuleSetV1::try_from(&data[..]).unwrap(), rule_set);
+
+    // The first legacy revision still validates.
+    let validate_ix = legacy_validate_ix(rule_set_addr, transfer_operation(), Some(0));
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Freeze pass
+    // --------------------------------
+    // Warp ahead for a new blockhash, so the transaction differs from the one that failed.
+    context.warp_to_slot(10).unwrap();
+    process_tx(&mut context, freeze_ix(&owner, rule_set_addr), &owner)
+        .await
+        .unwrap();
+
+    // A frozen `RuleSet` cannot be updated.
+    rule_set
+        .add(
+            Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+    let err = process_tx(
+        &mut context,
+        update_ix(&owner, rule_set_addr, &rule_set),
+        &owner,
+    )
+    .await
+    .expect_err("update should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetIsFrozen);
+}
+
+#[tokio::test]
+async fn legacy_header_delete() {
+    let mut context = program_test().start_with_context().await;
+    let (owner, rule_set_addr) = add_legacy_rule_set(&mut context);
+
+    // --------------------------------
+    // Delete pass
+    // --------------------------------
+    let delete_ix = DeleteBuilder::new()
+        .owner(owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .refund_destination(owner.pubkey())
+        .build(DeleteArgs::V1 {
+            rule_set_name: LEGACY_RULE_SET_NAME.to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    process_tx(&mut context, delete_ix, &owner).await.unwrap();
+
+    // The account was closed.
+    let account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap();
+    assert!(account.is_none());
+}
+
+fn transfer_operation() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string()
+}
+
+fn delegate_operation() -> String {
+    Operation::Delegate {
+        scenario: utils::DelegateScenario::Token(utils::TokenDelegateRole::Transfer),
+    }
+    .to_string()
+}
+
+// Create a `validate` instruction for `operation` on the given revision.
+fn legacy_validate_ix(
+    rule_set_addr: Pubkey,
+    operation: String,
+    rule_set_revision: Option<usize>,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation,
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision,
+        })
+        .unwrap()
+        .instruction()
+}
+
+fn update_ix(owner: &Keypair, rule_set_addr: Pubkey, rule_set: &RuleSetV1) -> Instruction {
+    CreateOrUpdateBuilder::new()
+        .payer(owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::from_rule_set(rule_set).unwrap())
+        .unwrap()
+        .instruction()
+}
+
+fn freeze_ix(owner: &Keypair, rule_set_addr: Pubkey) -> Instruction {
+    FreezeBuilder::new()
+        .owner(owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(FreezeArgs::V1 {
+            rule_set_name: LEGACY_RULE_SET_NAME.to_string(),
+        })
+        .unwrap()
+        .instruction()
+}
+
+// Process `ix` in a transaction paid for and signed by `owner`.
+async fn process_tx(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+    owner: &Keypair,
+) -> Result<(), solana_program_test::BanksClientError> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner.pubkey()),
+        &[owner],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
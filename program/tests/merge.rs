@@ -0,0 +1,112 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    state::{CompareOp, MergeStrategy, Rule, RuleSetV1},
+};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn merge_overwrite() {
+    let (mut base, overlay) = create_rule_sets();
+
+    base.merge(overlay, MergeStrategy::Overwrite).unwrap();
+
+    // The overlay's Rule replaces the base Rule, and the other operations are kept.
+    assert_eq!(base.get(&transfer()), Some(&overlay_rule()));
+    assert_eq!(base.get(&delegate()), Some(&Rule::Pass));
+    assert_eq!(base.get(&sale()), Some(&Rule::Pass));
+    assert_eq!(base.name(), "base rule_set");
+}
+
+#[test]
+fn merge_keep_existing() {
+    let (mut base, overlay) = create_rule_sets();
+
+    base.merge(overlay, MergeStrategy::KeepExisting).unwrap();
+
+    // The base Rule is kept, and the other operations are kept.
+    assert_eq!(base.get(&transfer()), Some(&base_rule()));
+    assert_eq!(base.get(&delegate()), Some(&Rule::Pass));
+    assert_eq!(base.get(&sale()), Some(&Rule::Pass));
+    assert_eq!(base.name(), "base rule_set");
+}
+
+#[test]
+fn merge_error() {
+    let (mut base, overlay) = create_rule_sets();
+    let original = base.clone();
+
+    let err = base.merge(overlay, MergeStrategy::Error).unwrap_err();
+
+    // Check that error is what we expect, and that nothing was merged.
+    assert_eq!(err, RuleSetError::ValueOccupied);
+    assert_eq!(base, original);
+    assert_eq!(base.get(&transfer()), Some(&base_rule()));
+    assert_eq!(base.get(&sale()), None);
+}
+
+#[test]
+fn merge_error_without_conflict() {
+    let (mut base, _) = create_rule_sets();
+
+    // Merge a RuleSet that only has an operation the base does not.
+    let mut overlay = RuleSetV1::new("overlay rule_set".to_string(), Keypair::new().pubkey());
+    overlay.add(sale(), Rule::Pass).unwrap();
+
+    base.merge(overlay, MergeStrategy::Error).unwrap();
+
+    assert_eq!(base.get(&transfer()), Some(&base_rule()));
+    assert_eq!(base.get(&sale()), Some(&Rule::Pass));
+}
+
+// Create a base RuleSet with Transfer and Delegate namespace operations, and an overlay RuleSet with
+// Transfer and Sale operations.
+fn create_rule_sets() -> (RuleSetV1, RuleSetV1) {
+    let mut base = RuleSetV1::new("base rule_set".to_string(), Keypair::new().pubkey());
+    base.add(transfer(), base_rule()).unwrap();
+    base.add(delegate(), Rule::Pass).unwrap();
+
+    let mut overlay = RuleSetV1::new("overlay rule_set".to_string(), Keypair::new().pubkey());
+    overlay.add(transfer(), overlay_rule()).unwrap();
+    overlay.add(sale(), Rule::Pass).unwrap();
+
+    (base, overlay)
+}
+
+fn base_rule() -> Rule {
+    Rule::Amount {
+        amount: 1,
+        operator: CompareOp::Eq,
+        field: PayloadKey::Amount.to_string(),
+    }
+}
+
+fn overlay_rule() -> Rule {
+    Rule::Amount {
+        amount: 5,
+        operator: CompareOp::LtEq,
+        field: PayloadKey::Amount.to_string(),
+    }
+}
+
+fn transfer() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string()
+}
+
+fn delegate() -> String {
+    Operation::DelegateNamespace.to_string()
+}
+
+fn sale() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::SaleDelegate,
+    }
+    .to_string()
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    merkle::{compute_merkle_root, hash_pair, verify, verify_proof},
+    payload::ProofInfo,
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{create_test_merkle_tree_from_leaves, create_test_merkle_tree_from_one_leaf};
+
+#[test]
+fn hash_pair_is_order_independent() {
+    let a = [1; 32];
+    let b = [2; 32];
+    assert_eq!(hash_pair(&a, &b), hash_pair(&b, &a));
+    assert_eq!(
+        hash_pair(&a, &b),
+        solana_program::keccak::hashv(&[&[0x01], &a, &b]).0
+    );
+}
+
+#[test]
+fn verify_proof_test_vectors() {
+    let leaves = [
+        Pubkey::new_from_array([1; 32]),
+        Pubkey::new_from_array([2; 32]),
+        Pubkey::new_from_array([3; 32]),
+    ];
+
+    // Every leaf of the tree verifies against the same root.
+    let root = create_test_merkle_tree_from_leaves(&leaves, 0).root;
+    for (index, leaf) in leaves.iter().enumerate() {
+        let tree = create_test_merkle_tree_from_leaves(&leaves, index);
+        assert_eq!(tree.root, root);
+        assert!(verify_proof(leaf, &tree.proof, &root));
+    }
+
+    // The proof for the middle leaf is its sibling followed by the hash of the last leaf with
+    // itself.
+    let proof = ProofInfo::new(vec![[1; 32], hash_pair(&[3; 32], &[3; 32])]);
+    assert_eq!(compute_merkle_root(&leaves[1], &proof), root);
+
+    // A leaf that is not in the tree does not verify.
+    let tree = create_test_merkle_tree_from_leaves(&leaves, 1);
+    assert!(!verify_proof(
+        &Pubkey::new_from_array([4; 32]),
+        &tree.proof,
+        &root
+    ));
+}
+
+#[test]
+fn verify_proof_one_leaf_tree() {
+    let leaf = Keypair::new().pubkey();
+    let tree = create_test_merkle_tree_from_one_leaf(&leaf, 4);
+    assert!(verify_proof(&leaf, &tree.proof, &tree.root));
+
+    // Corrupting the proof breaks verification.
+    let mut incorrect_proof = tree.proof.clone();
+    incorrect_proof.proof[1] = [1; 32];
+    assert!(!verify_proof(&leaf, &incorrect_proof, &tree.root));
+}
+
+#[test]
+fn verify_sample_proof() {
+    // Merkle tree root, leaf and proof generated in a different test program.
+    let root: [u8; 32] = [
+        132, 141, 27, 31, 23, 154, 145, 128, 32, 62, 122, 224, 248, 128, 37, 139, 200, 46, 163,
+        238, 76, 123, 155, 141, 73, 12, 111, 192, 122, 80, 126, 155,
+    ];
+    let leaf: [u8; 32] = [
+        2, 157, 245, 156, 21, 37, 147, 96, 42, 190, 206, 14, 24, 1, 106, 49, 167, 236, 38, 73, 98,
+        53, 60, 9, 154, 31, 240, 126, 210, 197, 76, 7,
+    ];
+    let proof: Vec<[u8; 32]> = vec![
+        [
+            246, 54, 96, 185, 234, 119, 124, 220, 54, 137, 25, 200, 18, 12, 114, 75, 211, 203, 154,
+            229, 197, 53, 164, 84, 38, 56, 20, 74, 192, 119, 37, 175,
+        ],
+        [
+            193, 84, 33, 232, 119, 107, 227, 166, 30, 233, 40, 10, 51, 229, 90, 59, 165, 212, 67,
+            193, 159, 126, 26, 200, 13, 209, 162, 98, 52, 125, 240, 77,
+        ],
+        [
+            238, 14, 13, 214, 124, 172, 89, 7, 66, 168, 226, 88, 92, 22, 18, 17, 94, 96, 37, 234,
+            101, 96, 129, 26, 137, 222, 96, 86, 245, 11, 199, 140,
+        ],
+    ];
+    assert!(verify(&leaf, &proof, &root));
+
+    // The accessor exposes the same hashes to off-chain callers.
+    let proof_info = ProofInfo::new(proof.clone());
+    assert_eq!(proof_info.proof(), proof.as_slice());
+    assert!(verify(&leaf, proof_info.proof(), &root));
+
+    // Tampering with any byte of the proof breaks verification.
+    let mut tampered_proof = proof;
+    tampered_proof[2][0] ^= 1;
+    assert!(!verify(&leaf, &tampered_proof, &root));
+}
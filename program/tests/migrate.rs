@@ -0,0 +1,196 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::BorshDeserialize;
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{
+        builders::{MigrateBuilder, ValidateBuilder},
+        InstructionBuilder, MigrateArgs, ValidateArgs,
+    },
+    payload::Payload,
+    pda::find_rule_set_address,
+    state::{
+        Key, RuleSetHeader, RuleSetRevisionMapV1, RULE_SET_BUMP_LOCATION,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_SERIALIZED_HEADER_LEN,
+    },
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{tokio, BanksClientError, ProgramTestContext};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{
+    add_legacy_rule_set, program_test, Operation, LEGACY_REVISIONS,
+    LEGACY_REV_MAP_VERSION_LOCATION, LEGACY_RULE_SET, LEGACY_RULE_SET_NAME,
+};
+
+#[tokio::test]
+async fn migrate_legacy_rule_set() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create legacy RuleSet
+    // --------------------------------
+    let (owner, rule_set_addr) = add_legacy_rule_set(&mut context);
+
+    // --------------------------------
+    // Migrate RuleSet
+    // --------------------------------
+    migrate(&mut context, &owner, rule_set_addr).await.unwrap();
+
+    // Check that the account now starts with the current header, followed by the bump.
+    let rule_set_account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    let data = &rule_set_account.data;
+
+    let header = RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    assert_eq!(header.key, Key::RuleSet);
+    assert_eq!(header.owner, owner.pubkey());
+    assert_eq!(header.creator, owner.pubkey());
+    assert!(!header.frozen);
+
+    let (_, bump) = find_rule_set_address(owner.pubkey(), LEGACY_RULE_SET_NAME.to_string());
+    assert_eq!(data[RULE_SET_BUMP_LOCATION], bump);
+
+    // Check that the revisions were moved unchanged and the revision map points at them.
+    let shift = RULE_SET_FIRST_REVISION_LOCATION - LEGACY_REVISIONS[0];
+    assert_eq!(
+        header.rev_map_version_location,
+        LEGACY_REV_MAP_VERSION_LOCATION + shift
+    );
+
+    let revision_map =
+        RuleSetRevisionMapV1::try_from_slice(&data[header.rev_map_version_location + 1..]).unwrap();
+    assert_eq!(
+        revision_map.rule_set_revisions,
+        vec![LEGACY_REVISIONS[0] + shift, LEGACY_REVISIONS[1] + shift]
+    );
+    assert_eq!(
+        &data[LEGACY_REVISIONS[0] + shift..LEGACY_REV_MAP_VERSION_LOCATION + shift],
+        &LEGACY_RULE_SET[LEGACY_REVISIONS[0]..LEGACY_REV_MAP_VERSION_LOCATION]
+    );
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // The operation only in the first revision.
+    let validate_ix = legacy_validate_ix(
+        rule_set_addr,
+        Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        }
+        .to_string(),
+        Some(0),
+    );
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // The operation added by the latest revision.
+    let validate_ix = legacy_validate_ix(
+        rule_set_addr,
+        Operation::Delegate {
+            scenario: utils::DelegateScenario::Token(utils::TokenDelegateRole::Transfer),
+        }
+        .to_string(),
+        None,
+    );
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Migrate again
+    // --------------------------------
+    // Warp ahead so the same instruction can be sent in a new transaction.
+    context.warp_to_slot(10).unwrap();
+
+    // Migrating an already migrated RuleSet succeeds without changing it.
+    migrate(&mut context, &owner, rule_set_addr).await.unwrap();
+
+    let migrated_again = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(migrated_again.data, rule_set_account.data);
+}
+
+#[tokio::test]
+async fn migrate_legacy_rule_set_wrong_owner_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create legacy RuleSet
+    // --------------------------------
+    let (_, rule_set_addr) = add_legacy_rule_set(&mut context);
+
+    // --------------------------------
+    // Migrate fail
+    // --------------------------------
+    let not_owner = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    let err = migrate(&mut context, &not_owner, rule_set_addr)
+        .await
+        .expect_err("migrate should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetOwnerMismatch);
+
+    // The account was left in the legacy layout.
+    let rule_set_account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rule_set_account.data, LEGACY_RULE_SET);
+}
+
+// Create a `validate` instruction for `operation` on the given revision.
+fn legacy_validate_ix(
+    rule_set_addr: Pubkey,
+    operation: String,
+    rule_set_revision: Option<usize>,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation,
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision,
+        })
+        .unwrap()
+        .instruction()
+}
+
+// Migrate the `RuleSet` at `rule_set_addr`, signing and paying as `owner`.
+async fn migrate(
+    context: &mut ProgramTestContext,
+    owner: &Keypair,
+    rule_set_addr: Pubkey,
+) -> Result<(), BanksClientError> {
+    // Create a `migrate` instruction.
+    let migrate_ix = MigrateBuilder::new()
+        .owner(owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(MigrateArgs::V1 {
+            rule_set_name: LEGACY_RULE_SET_NAME.to_string(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let migrate_tx = Transaction::new_signed_with_payer(
+        &[migrate_ix],
+        Some(&owner.pubkey()),
+        &[owner],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context.banks_client.process_transaction(migrate_tx).await
+}
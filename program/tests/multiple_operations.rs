@@ -6,10 +6,14 @@ use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
     payload::{Payload, PayloadType},
-    state::{CompareOp, Rule, RuleSetV1},
+    state::{CompareOp, Rule, RuleSetV1, RULE_SET_LIB_VERSION},
 };
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
 use solana_program_test::tokio;
 use solana_sdk::{instruction::AccountMeta, signature::Signer, signer::keypair::Keypair};
+use std::collections::HashMap;
 use utils::{program_test, Operation, PayloadKey};
 
 #[tokio::test]
@@ -199,3 +203,72 @@ async fn correct_rule_used_for_each_operation() {
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+// Same layout as `RuleSetV1`, but operations can hold values that are not valid `Rule`s.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleSetWithJunk {
+    lib_version: u8,
+    owner: Pubkey,
+    rule_set_name: String,
+    operations: HashMap<String, RuleOrJunk>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RuleOrJunk {
+    Rule(Rule),
+    Junk(Vec<u64>),
+}
+
+#[test]
+fn only_target_operation_is_deserialized() {
+    let target_rule = Rule::Amount {
+        amount: 1,
+        operator: CompareOp::Eq,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    // Create a large RuleSet where every operation but the target holds data that would fail to
+    // deserialize as a `Rule`.
+    let mut operations = HashMap::new();
+    for i in 0..49 {
+        operations.insert(format!("Operation{}", i), RuleOrJunk::Junk(vec![i; 8]));
+    }
+    operations.insert("Target".to_string(), RuleOrJunk::Rule(target_rule.clone()));
+
+    let rule_set = RuleSetWithJunk {
+        lib_version: RULE_SET_LIB_VERSION,
+        owner: Keypair::new().pubkey(),
+        rule_set_name: "test rule_set".to_string(),
+        operations,
+    };
+
+    // Serialize the RuleSet using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // Fully deserializing the RuleSet fails because of the junk operations.
+    assert!(rmp_serde::from_slice::<RuleSetV1>(&serialized_rule_set).is_err());
+
+    // The target operation can still be deserialized on its own.
+    assert_eq!(
+        RuleSetV1::get_rule_for_operation(&serialized_rule_set, "Target").unwrap(),
+        target_rule
+    );
+
+    // The rest of the RuleSet can be deserialized without its operations.
+    let without_operations =
+        RuleSetV1::from_slice_without_operations(&serialized_rule_set).unwrap();
+    assert_eq!(without_operations.name(), "test rule_set");
+    assert_eq!(without_operations.owner(), &rule_set.owner);
+    assert_eq!(without_operations.operations().count(), 0);
+
+    // A missing operation is reported as such.
+    assert_eq!(
+        RuleSetV1::get_rule_for_operation(&serialized_rule_set, "Missing").unwrap_err(),
+        RuleSetError::OperationNotFound
+    );
+}
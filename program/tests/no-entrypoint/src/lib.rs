@@ -0,0 +1,63 @@
+//! A program that depends on `mpl-token-auth-rules` with the `no-entrypoint` feature and defines
+//! its own entrypoint, the way a program that calls it through CPI would.  Building this checks
+//! that the instruction, state, payload, PDA and CPI modules are available without pulling in the
+//! `mpl-token-auth-rules` entrypoint.
+use mpl_token_auth_rules::{
+    cpi::{validate_cpi, ValidateCpiContext},
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+    pda::find_rule_set_address,
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+
+entrypoint!(process_instruction);
+fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let (auth_rules_program, rule_set_pda, mint, system_program) = match accounts {
+        [auth_rules_program, rule_set_pda, mint, system_program] => {
+            (auth_rules_program, rule_set_pda, mint, system_program)
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    let ctx = ValidateCpiContext {
+        auth_rules_program,
+        rule_set_pda,
+        mint,
+        system_program,
+        payer: None,
+        rule_authority: None,
+        rule_set_state_pda: None,
+        update_rule_state: false,
+        rule_set_revision: None,
+    };
+
+    validate_cpi(&ctx, &[], "Transfer".to_string(), Payload::default(), &[])
+}
+
+/// Build a `validate` instruction for a `RuleSet` with a single `Pass` rule.
+pub fn validate_pass_instruction(owner: Pubkey, mint: Pubkey) -> Instruction {
+    let mut rule_set = RuleSetV1::new("rule_set".to_string(), owner);
+    rule_set.add("Transfer".to_string(), Rule::Pass).unwrap();
+    let (rule_set_pda, _bump) = find_rule_set_address(owner, rule_set.name().to_string());
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_pda)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: "Transfer".to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
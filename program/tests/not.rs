@@ -8,6 +8,8 @@ use mpl_token_auth_rules::{
     payload::{Payload, PayloadType},
     state::{CompareOp, Rule, RuleSetV1},
 };
+use rmp_serde::Serializer;
+use serde::Serialize;
 use solana_program_test::tokio;
 use solana_sdk::{signature::Signer, signer::keypair::Keypair};
 use utils::{program_test, Operation, PayloadKey};
@@ -77,7 +79,7 @@ async fn test_not() {
     let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+    assert_custom_error!(err, RuleSetError::NotCheckFailed);
 
     // --------------------------------
     // Validate pass
@@ -105,3 +107,118 @@ async fn test_not() {
     // Validate Transfer operation since because the Amount Rule was NOT'd.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn test_not_pubkey_match() {
+    let mut context = program_test().start_with_context().await;
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a rule that denies a single pubkey.
+    let denied = Keypair::new().pubkey();
+    let not_pubkey_match = Rule::Not {
+        rule: Box::new(Rule::PubkeyMatch {
+            pubkey: denied,
+            field: PayloadKey::Destination.to_string(),
+        }),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            not_pubkey_match,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store a payload of data with the denied pubkey.
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(denied),
+    )]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation because the PubkeyMatch Rule was NOT'd.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::NotCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Store a payload of data with some other pubkey.
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(Keypair::new().pubkey()),
+    )]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation since the pubkey does not match the denied pubkey.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[test]
+fn test_nested_not_serialization() {
+    // Create a doubly-negated Rule.
+    let not_not_pass = Rule::Not {
+        rule: Box::new(Rule::Not {
+            rule: Box::new(Rule::Pass),
+        }),
+    };
+
+    // Serialize the Rule using RMP serde and deserialize it again.
+    let mut serialized_rule = Vec::new();
+    not_not_pass
+        .serialize(&mut Serializer::new(&mut serialized_rule))
+        .unwrap();
+    let deserialized: Rule = rmp_serde::from_slice(&serialized_rule).unwrap();
+
+    assert_eq!(deserialized, not_not_pass);
+}
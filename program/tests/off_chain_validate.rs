@@ -0,0 +1,473 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::{RuleError, RuleSetError},
+    payload::{Payload, PayloadType},
+    state::{
+        CompareOp, InMemoryAccount, InMemoryValidationContext, Rule, RuleSetV1, ValidationContext,
+        MAX_RULE_DEPTH,
+    },
+};
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey::Pubkey, system_program};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use std::cell::Cell;
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn off_chain_validate_program_owned_and_amount() {
+    let program = Keypair::new().pubkey();
+    let destination = Keypair::new().pubkey();
+
+    // Create a RuleSet requiring a program-owned destination and a small amount.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::ProgramOwned {
+                program,
+                field: PayloadKey::Destination.to_string(),
+            },
+            Rule::Amount {
+                amount: 10,
+                operator: CompareOp::Lt,
+                field: PayloadKey::Amount.to_string(),
+            },
+        ],
+    };
+
+    let operation = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set.add(operation.clone(), rule).unwrap();
+
+    // The destination account is owned by the program and holds data.
+    let mut context = InMemoryValidationContext::new();
+    context.insert(
+        destination,
+        InMemoryAccount {
+            owner: program,
+            data: vec![1; 8],
+            is_signer: false,
+        },
+    );
+
+    // Validate pass.
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(5)),
+    ]);
+    rule_set.validate(&operation, &payload, &context).unwrap();
+
+    // Validate fail with too large of an amount.
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(10)),
+    ]);
+    assert_eq!(
+        rule_set.validate(&operation, &payload, &context),
+        Err(RuleError::from(RuleSetError::AmountCheckFailed))
+    );
+
+    // Validate fail with an account the context does not know about.
+    let unknown = Keypair::new().pubkey();
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(unknown),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(5)),
+    ]);
+    let err = rule_set
+        .validate(&operation, &payload, &context)
+        .unwrap_err();
+    assert_eq!(err, RuleError::MissingAccount { key: unknown });
+    assert_eq!(
+        ProgramError::from(err),
+        ProgramError::from(RuleSetError::MissingAccount)
+    );
+
+    // Validate fail once the destination is owned by a different program.
+    let other_program = Keypair::new().pubkey();
+    context.insert(
+        destination,
+        InMemoryAccount {
+            owner: other_program,
+            data: vec![1; 8],
+            is_signer: false,
+        },
+    );
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(5)),
+    ]);
+    let err = rule_set
+        .validate(&operation, &payload, &context)
+        .unwrap_err();
+
+    // The off-chain error names the account and both owners, and flattens to the on-chain error.
+    assert_eq!(
+        err,
+        RuleError::ProgramOwnerMismatch {
+            key: destination,
+            expected: program,
+            actual: other_program,
+        }
+    );
+    assert_eq!(
+        ProgramError::from(err),
+        ProgramError::from(RuleSetError::ProgramOwnedCheckFailed)
+    );
+}
+
+#[test]
+fn off_chain_validate_same_account_in_several_rules() {
+    let program = Keypair::new().pubkey();
+    let other_program = Keypair::new().pubkey();
+    let destination = Keypair::new().pubkey();
+    let wallet = Keypair::new().pubkey();
+
+    // The destination is owned by the program and holds data, and the wallet is a plain
+    // system-owned account.
+    let mut context = InMemoryValidationContext::new();
+    context.insert(
+        destination,
+        InMemoryAccount {
+            owner: program,
+            data: vec![1; 8],
+            is_signer: false,
+        },
+    );
+    context.insert(
+        wallet,
+        InMemoryAccount {
+            owner: system_program::ID,
+            ..InMemoryAccount::default()
+        },
+    );
+
+    let program_owned = |program| Rule::ProgramOwned {
+        program,
+        field: PayloadKey::Destination.to_string(),
+    };
+    let payload = |key| {
+        Payload::from([(
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(key),
+        )])
+    };
+
+    // Every rule checking the destination sees its real owner.
+    let rule = Rule::All {
+        rules: vec![
+            program_owned(program),
+            Rule::Not {
+                rule: Box::new(program_owned(other_program)),
+            },
+            Rule::Any {
+                rules: vec![program_owned(other_program), program_owned(program)],
+            },
+        ],
+    };
+    rule.validate(&context, &payload(destination), false, &None, &None)
+        .unwrap();
+
+    // An account is still not found the second time it is checked.
+    let unknown = Keypair::new().pubkey();
+    let rule = Rule::Any {
+        rules: vec![program_owned(program), program_owned(program)],
+    };
+    assert_eq!(
+        rule.validate(&context, &payload(unknown), false, &None, &None),
+        Err(RuleError::MissingAccount { key: unknown })
+    );
+
+    // A program-owned account is not a wallet, even after its owner was looked up.
+    let rule = Rule::All {
+        rules: vec![
+            program_owned(program),
+            Rule::IsWallet {
+                field: PayloadKey::Destination.to_string(),
+            },
+        ],
+    };
+    assert_eq!(
+        rule.validate(&context, &payload(destination), false, &None, &None),
+        Err(RuleError::from(RuleSetError::IsWalletCheckFailed))
+    );
+
+    // A wallet is not owned by the program, even after its owner was looked up.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::IsWallet {
+                field: PayloadKey::Destination.to_string(),
+            },
+            Rule::Not {
+                rule: Box::new(program_owned(program)),
+            },
+        ],
+    };
+    rule.validate(&context, &payload(wallet), false, &None, &None)
+        .unwrap();
+}
+
+#[test]
+fn off_chain_validate_owner_looked_up_once() {
+    let program = Keypair::new().pubkey();
+    let destination = Keypair::new().pubkey();
+
+    let mut accounts = InMemoryValidationContext::new();
+    accounts.insert(
+        destination,
+        InMemoryAccount {
+            owner: program,
+            data: vec![1; 8],
+            is_signer: false,
+        },
+    );
+    let context = CountingContext {
+        inner: accounts,
+        owner_lookups: Cell::new(0),
+    };
+
+    let program_owned = || Rule::ProgramOwned {
+        program,
+        field: PayloadKey::Destination.to_string(),
+    };
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(destination),
+    )]);
+
+    // The destination is checked at the top level and inside `Not` and `IfThen` rules, which
+    // share the cache of the whole tree.
+    let rule = Rule::All {
+        rules: vec![
+            program_owned(),
+            Rule::Not {
+                rule: Box::new(Rule::Not {
+                    rule: Box::new(program_owned()),
+                }),
+            },
+            Rule::IfThen {
+                condition: Box::new(program_owned()),
+                then_rule: Box::new(program_owned()),
+                else_rule: None,
+            },
+        ],
+    };
+    rule.validate(&context, &payload, false, &None, &None)
+        .unwrap();
+    assert_eq!(context.owner_lookups.get(), 1);
+}
+
+#[test]
+fn off_chain_validate_max_depth() {
+    let context = InMemoryValidationContext::new();
+    let validate = |rule: Rule| rule.validate(&context, &Payload::default(), false, &None, &None);
+
+    // Wrap a Pass Rule in `depth` Not Rules.
+    let not_chain = |depth| {
+        (0..depth).fold(Rule::Pass, |rule, _| Rule::Not {
+            rule: Box::new(rule),
+        })
+    };
+
+    // Nest a Pass Rule as the `then_rule` of `depth` IfThen Rules.
+    let if_then_chain = |depth| {
+        (0..depth).fold(Rule::Pass, |rule, _| Rule::IfThen {
+            condition: Box::new(Rule::Pass),
+            then_rule: Box::new(rule),
+            else_rule: None,
+        })
+    };
+
+    // An even number of Not rules around a Pass passes, and an odd number fails.
+    assert_eq!(validate(not_chain(MAX_RULE_DEPTH)), Ok(()));
+    assert_eq!(
+        validate(not_chain(MAX_RULE_DEPTH - 1)),
+        Err(RuleError::from(RuleSetError::NotCheckFailed))
+    );
+
+    // IfThen rules count towards the depth too.
+    assert_eq!(validate(if_then_chain(MAX_RULE_DEPTH)), Ok(()));
+    assert_eq!(
+        validate(if_then_chain(MAX_RULE_DEPTH + 1)),
+        Err(RuleError::from(RuleSetError::RuleTreeTooDeep))
+    );
+}
+
+// A `ValidationContext` counting how often account owners are looked up.
+struct CountingContext {
+    inner: InMemoryValidationContext,
+    owner_lookups: Cell<usize>,
+}
+
+impl ValidationContext for CountingContext {
+    fn account_owner(&self, key: &Pubkey) -> Option<Pubkey> {
+        self.owner_lookups.set(self.owner_lookups.get() + 1);
+        self.inner.account_owner(key)
+    }
+
+    fn account_data_is_zeroed(&self, key: &Pubkey) -> Option<bool> {
+        self.inner.account_data_is_zeroed(key)
+    }
+
+    fn account_data_len(&self, key: &Pubkey) -> Option<usize> {
+        self.inner.account_data_len(key)
+    }
+
+    fn read_account_data(&self, key: &Pubkey, offset: usize, buf: &mut [u8]) -> bool {
+        self.inner.read_account_data(key, offset, buf)
+    }
+
+    fn is_signer(&self, key: &Pubkey) -> Option<bool> {
+        self.inner.is_signer(key)
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.inner.clock()
+    }
+
+    fn account_key_at(&self, index: usize) -> Option<Pubkey> {
+        self.inner.account_key_at(index)
+    }
+}
+
+#[test]
+fn off_chain_validate_additional_signer() {
+    let signer = Keypair::new().pubkey();
+    let rule = Rule::AdditionalSigner { account: signer };
+
+    // Validate fail when the context does not know about the account.
+    let mut context = InMemoryValidationContext::new();
+    assert_eq!(
+        rule.validate(&context, &Payload::new(), false, &None, &None),
+        Err(RuleError::MissingAccount { key: signer })
+    );
+
+    // Validate fail when the account does not sign.
+    context.insert(signer, InMemoryAccount::default());
+    assert_eq!(
+        rule.validate(&context, &Payload::new(), false, &None, &None),
+        Err(RuleError::from(RuleSetError::AdditionalSignerCheckFailed))
+    );
+
+    // Validate pass when the account signs.
+    context.insert(
+        signer,
+        InMemoryAccount {
+            is_signer: true,
+            ..InMemoryAccount::default()
+        },
+    );
+    rule.validate(&context, &Payload::new(), false, &None, &None)
+        .unwrap();
+}
+
+#[test]
+fn off_chain_validate_timestamp() {
+    let rule = Rule::Timestamp {
+        start: Some(100),
+        end: Some(200),
+    };
+
+    // Validate fail when the context has no clock.
+    let mut context = InMemoryValidationContext::new();
+    assert_eq!(
+        rule.validate(&context, &Payload::new(), false, &None, &None),
+        Err(RuleError::from(ProgramError::UnsupportedSysvar))
+    );
+
+    // Validate pass inside the window.
+    context.set_clock(Clock {
+        unix_timestamp: 150,
+        ..Clock::default()
+    });
+    rule.validate(&context, &Payload::new(), false, &None, &None)
+        .unwrap();
+
+    // Validate fail after the window.
+    context.set_clock(Clock {
+        unix_timestamp: 250,
+        ..Clock::default()
+    });
+    assert_eq!(
+        rule.validate(&context, &Payload::new(), false, &None, &None),
+        Err(RuleError::from(RuleSetError::TimestampCheckFailed))
+    );
+}
+
+#[test]
+fn program_owned_at_index_off_chain() {
+    let program = Keypair::new().pubkey();
+    let operation = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+
+    // Check the owner of the second account.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            operation.clone(),
+            Rule::ProgramOwnedAtIndex {
+                program,
+                account_index: 1,
+            },
+        )
+        .unwrap();
+
+    // Accounts are indexed in the order they are first inserted.
+    let mut context = InMemoryValidationContext::new();
+    let not_owned = Keypair::new().pubkey();
+    let owned = Keypair::new().pubkey();
+    for (key, owner) in [(not_owned, Keypair::new().pubkey()), (owned, program)] {
+        context.insert(
+            key,
+            InMemoryAccount {
+                owner,
+                data: vec![1; 8],
+                is_signer: false,
+            },
+        );
+    }
+
+    rule_set
+        .validate(&operation, &Payload::new(), &context)
+        .unwrap();
+
+    // Updating an account does not change its index.
+    context.insert(
+        not_owned,
+        InMemoryAccount {
+            owner: program,
+            data: vec![1; 8],
+            is_signer: false,
+        },
+    );
+    rule_set
+        .validate(&operation, &Payload::new(), &context)
+        .unwrap();
+
+    // There is no account at index 1 of an empty context.
+    assert_eq!(
+        rule_set.validate(
+            &operation,
+            &Payload::new(),
+            &InMemoryValidationContext::new()
+        ),
+        Err(RuleError::from(RuleSetError::MissingAccount))
+    );
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    DELEGATE, DELEGATE_TRANSFER, MIGRATION_TRANSFER, OWNER_TRANSFER, SALE_TRANSFER, TRANSFER,
+    UPDATE, WALLET_TO_WALLET_TRANSFER,
+};
+use utils::{Operation, TransferScenario};
+
+#[test]
+fn operation_names_match_token_metadata() {
+    // The exported names are the strings token-metadata validates with.
+    let transfer = |scenario| Operation::Transfer { scenario }.to_string();
+
+    assert_eq!(TRANSFER, Operation::TransferNamespace.to_string());
+    assert_eq!(UPDATE, Operation::UpdateNamespace.to_string());
+    assert_eq!(DELEGATE, Operation::DelegateNamespace.to_string());
+    assert_eq!(OWNER_TRANSFER, transfer(TransferScenario::Holder));
+    assert_eq!(
+        DELEGATE_TRANSFER,
+        transfer(TransferScenario::TransferDelegate)
+    );
+    assert_eq!(SALE_TRANSFER, transfer(TransferScenario::SaleDelegate));
+    assert_eq!(
+        MIGRATION_TRANSFER,
+        transfer(TransferScenario::MigrationDelegate)
+    );
+    assert_eq!(
+        WALLET_TO_WALLET_TRANSFER,
+        transfer(TransferScenario::WalletToWallet)
+    );
+}
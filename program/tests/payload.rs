@@ -0,0 +1,268 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    payload::{Payload, PayloadType, ProofInfo, SeedsVec, MAX_PROOF_DEPTH},
+    state::Rule,
+};
+use solana_program::pubkey::{MAX_SEEDS, MAX_SEED_LEN};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::PayloadKey;
+
+#[test]
+fn payload_typed_accessors() {
+    let destination = Keypair::new().pubkey();
+    let proof = ProofInfo::new(vec![[1; 32], [2; 32]]);
+
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(1)),
+        (
+            PayloadKey::DestinationProof.to_string(),
+            PayloadType::MerkleProof(proof.clone()),
+        ),
+    ]);
+
+    // Hit.
+    assert_eq!(
+        payload.get(&PayloadKey::Amount.to_string()),
+        Some(&PayloadType::Number(1))
+    );
+    assert_eq!(
+        payload.get_pubkey(&PayloadKey::Destination.to_string()),
+        Some(&destination)
+    );
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(1));
+    assert_eq!(
+        payload.get_merkle_proof(&PayloadKey::DestinationProof.to_string()),
+        Some(&proof)
+    );
+
+    // Miss.
+    assert_eq!(payload.get(&PayloadKey::Source.to_string()), None);
+    assert_eq!(payload.get_pubkey(&PayloadKey::Source.to_string()), None);
+    assert_eq!(payload.get_amount(&PayloadKey::Source.to_string()), None);
+    assert_eq!(
+        payload.get_merkle_proof(&PayloadKey::SourceProof.to_string()),
+        None
+    );
+
+    // Type mismatch.
+    assert_eq!(payload.get_pubkey(&PayloadKey::Amount.to_string()), None);
+    assert_eq!(
+        payload.get_amount(&PayloadKey::Destination.to_string()),
+        None
+    );
+    assert_eq!(
+        payload.get_merkle_proof(&PayloadKey::Destination.to_string()),
+        None
+    );
+}
+
+#[test]
+fn proof_info_try_new_depth_limit() {
+    let proof = vec![[1; 32]; MAX_PROOF_DEPTH];
+    assert_eq!(ProofInfo::try_new(proof.clone()), Ok(ProofInfo::new(proof)));
+
+    assert_eq!(
+        ProofInfo::try_new(vec![[1; 32]; MAX_PROOF_DEPTH + 1]),
+        Err(RuleSetError::ProofTooLong)
+    );
+}
+
+#[test]
+fn payload_check_size_limits() {
+    let key = PayloadKey::Destination.to_string();
+    let check = |value| Payload::from([(key.clone(), value)]).check_size();
+
+    // Values at each limit pass.
+    assert_eq!(
+        check(PayloadType::Bytes(vec![0; PayloadType::MAX_BYTES_LEN])),
+        Ok(())
+    );
+    assert_eq!(
+        check(PayloadType::Seeds(SeedsVec::new(vec![
+            vec![0; MAX_SEED_LEN];
+            MAX_SEEDS
+        ]))),
+        Ok(())
+    );
+    assert_eq!(
+        check(PayloadType::MerkleProof(ProofInfo::new(vec![
+            [0; 32];
+            MAX_PROOF_DEPTH
+        ]))),
+        Ok(())
+    );
+
+    // Values over each limit fail.
+    assert_eq!(
+        check(PayloadType::Bytes(vec![0; PayloadType::MAX_BYTES_LEN + 1])),
+        Err(RuleSetError::PayloadTooLarge)
+    );
+    assert_eq!(
+        check(PayloadType::Seeds(SeedsVec::new(vec![
+            vec![0];
+            MAX_SEEDS + 1
+        ]))),
+        Err(RuleSetError::PayloadTooLarge)
+    );
+    assert_eq!(
+        check(PayloadType::Seeds(SeedsVec::new(vec![vec![
+            0;
+            MAX_SEED_LEN
+                + 1
+        ]]))),
+        Err(RuleSetError::PayloadTooLarge)
+    );
+    assert_eq!(
+        check(PayloadType::MerkleProof(ProofInfo::new(vec![
+            [0; 32];
+            MAX_PROOF_DEPTH
+                + 1
+        ]))),
+        Err(RuleSetError::ProofTooLong)
+    );
+
+    // So does a `Payload` with too many entries, whatever their size.
+    let mut payload = Payload::new();
+    for index in 0..Payload::MAX_ENTRIES {
+        payload.insert(index.to_string(), PayloadType::Number(1));
+    }
+    assert_eq!(payload.check_size(), Ok(()));
+
+    payload.insert(Payload::MAX_ENTRIES.to_string(), PayloadType::Number(1));
+    assert_eq!(payload.check_size(), Err(RuleSetError::PayloadTooLarge));
+}
+
+#[test]
+fn payload_try_insert_rejects_duplicate_keys() {
+    let mut payload = Payload::new();
+
+    // First insert.
+    assert_eq!(
+        payload.try_insert(PayloadKey::Amount, PayloadType::Number(1)),
+        Ok(())
+    );
+
+    // Duplicate insert.
+    assert_eq!(
+        payload.try_insert(PayloadKey::Amount, PayloadType::Number(2)),
+        Err(RuleSetError::DuplicatePayloadKey)
+    );
+
+    // The original value is unchanged.
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(1));
+
+    // `from` keeps the last value for a duplicate key.
+    let payload = Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(1)),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(2)),
+    ]);
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(2));
+}
+
+#[test]
+fn payload_merge_without_overlap() {
+    let authority = Keypair::new().pubkey();
+    let mut payload = Payload::from([(
+        PayloadKey::Authority.to_string(),
+        PayloadType::Pubkey(authority),
+    )]);
+
+    payload.merge(
+        Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]),
+        false,
+    );
+
+    // Both the base and the merged values are present.
+    assert_eq!(payload.len(), 2);
+    assert_eq!(
+        payload.get_pubkey(&PayloadKey::Authority.to_string()),
+        Some(&authority)
+    );
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(1));
+}
+
+#[test]
+fn payload_merge_with_overwrite() {
+    let mut payload = Payload::from([
+        (PayloadKey::Amount.to_string(), PayloadType::Number(1)),
+        (PayloadKey::Holder.to_string(), PayloadType::Number(2)),
+    ]);
+
+    payload.merge(
+        Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(3))]),
+        true,
+    );
+
+    // The colliding key takes the merged value, and the other key is unchanged.
+    assert_eq!(payload.len(), 2);
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(3));
+    assert_eq!(payload.get_amount(&PayloadKey::Holder.to_string()), Some(2));
+}
+
+#[test]
+fn payload_merge_without_overwrite() {
+    let destination = Keypair::new().pubkey();
+    let mut payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]);
+
+    payload.merge(
+        Payload::from([
+            (PayloadKey::Amount.to_string(), PayloadType::Number(3)),
+            (
+                PayloadKey::Destination.to_string(),
+                PayloadType::Pubkey(destination),
+            ),
+        ]),
+        false,
+    );
+
+    // The colliding key keeps its original value, and the new key is added.
+    assert_eq!(payload.len(), 2);
+    assert_eq!(payload.get_amount(&PayloadKey::Amount.to_string()), Some(1));
+    assert_eq!(
+        payload.get_pubkey(&PayloadKey::Destination.to_string()),
+        Some(&destination)
+    );
+}
+
+#[test]
+fn payload_len_and_keys() {
+    let payload = Payload::new();
+    assert!(payload.is_empty());
+    assert_eq!(payload.len(), 0);
+    assert_eq!(payload.keys().next(), None);
+
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(Keypair::new().pubkey()),
+    )]);
+    assert!(!payload.is_empty());
+    assert_eq!(payload.len(), 1);
+    assert_eq!(
+        payload.keys().collect::<Vec<_>>(),
+        vec![&PayloadKey::Destination.to_string()]
+    );
+}
+
+#[test]
+fn payload_type_serialized_names() {
+    // The serialized names do not depend on the Rust variant names.
+    assert_eq!(
+        serde_json::to_string(&PayloadType::Number(5)).unwrap(),
+        r#"{"Number":5}"#
+    );
+    assert_eq!(serde_json::to_string(&Rule::Pass).unwrap(), r#""Pass""#);
+
+    // `Amount` is accepted as the name of `Number`.
+    assert_eq!(
+        serde_json::from_str::<PayloadType>(r#"{"Amount":5}"#).unwrap(),
+        PayloadType::Number(5)
+    );
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    pda::{
+        create_rule_set_address, find_rule_set_address, find_rule_set_addresses,
+        try_find_rule_set_address, validate_rule_set_name,
+    },
+    MAX_NAME_LENGTH,
+};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+
+#[test]
+fn find_rule_set_addresses_matches_individual_finds() {
+    let creator = Keypair::new().pubkey();
+    let names = (0..5)
+        .map(|i| format!("rule_set {}", i))
+        .collect::<Vec<_>>();
+
+    let addresses = find_rule_set_addresses(creator, &names);
+
+    assert_eq!(addresses.len(), names.len());
+    for (name, address) in names.iter().zip(addresses) {
+        assert_eq!(address, find_rule_set_address(creator, name.clone()));
+    }
+
+    assert!(find_rule_set_addresses(creator, &[]).is_empty());
+}
+
+#[test]
+fn create_rule_set_address_with_known_bump() {
+    let creator = Keypair::new().pubkey();
+    let name = "test rule_set".to_string();
+
+    let (rule_set_addr, bump) = find_rule_set_address(creator, name.clone());
+
+    // The found bump recreates the same address.
+    assert_eq!(
+        create_rule_set_address(creator, name.clone(), bump).unwrap(),
+        rule_set_addr
+    );
+
+    // Any other bump that yields a valid address yields a different one.
+    for other_bump in (0..bump).rev() {
+        if let Ok(address) = create_rule_set_address(creator, name.clone(), other_bump) {
+            assert_ne!(address, rule_set_addr);
+        }
+    }
+}
+
+#[test]
+fn rule_set_name_validation() {
+    assert_eq!(validate_rule_set_name(&"a".repeat(MAX_NAME_LENGTH)), Ok(()));
+    assert_eq!(
+        validate_rule_set_name(&"a".repeat(MAX_NAME_LENGTH + 1)),
+        Err(RuleSetError::NameTooLong)
+    );
+    assert_eq!(
+        validate_rule_set_name("test\0rule_set"),
+        Err(RuleSetError::InvalidName)
+    );
+    assert_eq!(
+        validate_rule_set_name("test\nrule_set"),
+        Err(RuleSetError::InvalidName)
+    );
+
+    // The checked find matches the unchecked one for a valid name.
+    let creator = Keypair::new().pubkey();
+    assert_eq!(
+        try_find_rule_set_address(creator, "test rule_set".to_string()),
+        Ok(find_rule_set_address(creator, "test rule_set".to_string()))
+    );
+    assert_eq!(
+        try_find_rule_set_address(creator, "a".repeat(MAX_NAME_LENGTH + 1)),
+        Err(RuleSetError::NameTooLong)
+    );
+}
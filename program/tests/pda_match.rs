@@ -96,6 +96,51 @@ async fn test_pda_match_assumed_owner() {
     // Check that error is what we expect.
     assert_custom_error!(err, RuleSetError::PDAMatchCheckFailed);
 
+    // --------------------------------
+    // Validate fail wrong seeds
+    // --------------------------------
+    // Use the correct PDA, but derivation seeds for a different RuleSet name.
+    let wrong_seeds = vec![
+        mpl_token_auth_rules::pda::PREFIX.as_bytes().to_vec(),
+        context.payer.pubkey().as_ref().to_vec(),
+        "wrong rule_set".as_bytes().to_vec(),
+    ];
+
+    // Store the payload of data to validate against the rule definition, using the wrong seeds.
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(rule_set_addr),
+        ),
+        (
+            PayloadKey::DestinationSeeds.to_string(),
+            PayloadType::Seeds(SeedsVec::new(wrong_seeds)),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(rule_set_addr, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PDAMatchCheckFailed);
+
     // --------------------------------
     // Validate pass
     // --------------------------------
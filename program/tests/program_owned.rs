@@ -10,8 +10,8 @@ use mpl_token_auth_rules::{
 };
 use solana_program_test::tokio;
 use solana_sdk::{
-    instruction::AccountMeta, signature::Signer, signer::keypair::Keypair, system_instruction,
-    transaction::Transaction,
+    account::Account, instruction::AccountMeta, signature::Signer, signer::keypair::Keypair,
+    system_instruction, transaction::Transaction,
 };
 use utils::{create_associated_token_account, create_mint, program_test, Operation, PayloadKey};
 
@@ -225,3 +225,253 @@ async fn program_owned() {
     // Validate transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn program_owned_source_and_destination() {
+    // Create source and destination accounts owned by two different programs.
+    let source_program = Keypair::new().pubkey();
+    let destination_program = Keypair::new().pubkey();
+    let source = Keypair::new().pubkey();
+    let destination = Keypair::new().pubkey();
+
+    let mut program_test = program_test();
+    for (account, owner) in [(source, source_program), (destination, destination_program)] {
+        program_test.add_account(
+            account,
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![1; 8],
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule checking the program owner of both the source and destination.
+    let overall_rule = Rule::All {
+        rules: vec![
+            Rule::ProgramOwned {
+                program: source_program,
+                field: PayloadKey::Source.to_string(),
+            },
+            Rule::ProgramOwned {
+                program: destination_program,
+                field: PayloadKey::Destination.to_string(),
+            },
+        ],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            overall_rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store a payload of data with the source and destination swapped.
+    let payload = Payload::from([
+        (
+            PayloadKey::Source.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(source),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![
+            AccountMeta::new_readonly(source, false),
+            AccountMeta::new_readonly(destination, false),
+        ])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Store a payload of data with the correct source and destination.
+    let payload = Payload::from([
+        (PayloadKey::Source.to_string(), PayloadType::Pubkey(source)),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![
+            AccountMeta::new_readonly(source, false),
+            AccountMeta::new_readonly(destination, false),
+        ])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn program_owned_with_data() {
+    // Create accounts owned by the same program with empty, short, and long data.
+    let program = Keypair::new().pubkey();
+    let empty = Keypair::new().pubkey();
+    let short = Keypair::new().pubkey();
+    let long = Keypair::new().pubkey();
+
+    let mut program_test = program_test();
+    for (account, data_len) in [(empty, 0), (short, 8), (long, 165)] {
+        program_test.add_account(
+            account,
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![1; data_len],
+                owner: program,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule.  The target must be owned by the program and hold at least 165 bytes.
+    let rule = Rule::ProgramOwnedWithData {
+        program,
+        field: PayloadKey::Destination.to_string(),
+        min_data_len: 165,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    for destination in [empty, short] {
+        // Store a payload of data with a destination account holding too little data.
+        let payload = Payload::from([(
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        )]);
+
+        // Create a `validate` instruction.
+        let validate_ix = ValidateBuilder::new()
+            .rule_set_pda(rule_set_addr)
+            .mint(mint)
+            .additional_rule_accounts(vec![AccountMeta::new_readonly(destination, false)])
+            .build(ValidateArgs::V1 {
+                operation: Operation::Transfer {
+                    scenario: utils::TransferScenario::Holder,
+                }
+                .to_string(),
+                payload,
+                update_rule_state: false,
+                rule_set_revision: None,
+            })
+            .unwrap()
+            .instruction();
+
+        // Fail to validate Transfer operation.
+        let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+        // Check that error is what we expect.
+        assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+    }
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Store a payload of data with a destination account holding enough data.
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(long),
+    )]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(long, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::tokio;
+use solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn program_owned_at_index() {
+    // Create an account owned by the program and one owned by a different program.
+    let program = Pubkey::new_unique();
+    let owned = Pubkey::new_unique();
+    let not_owned = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    for (key, owner) in [(owned, program), (not_owned, Pubkey::new_unique())] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![1; 8],
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The first additional rule account must be owned by the program.
+    let rule = Rule::ProgramOwnedAtIndex {
+        program,
+        account_index: 0,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Only the account at index 0 is checked, so the second account's owner does not matter.
+    let validate_ix = program_owned_at_index_validate_ix(rule_set_addr, vec![owned, not_owned]);
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail with the wrong owner
+    // --------------------------------
+    let validate_ix = program_owned_at_index_validate_ix(rule_set_addr, vec![not_owned, owned]);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+
+    // --------------------------------
+    // Validate fail with no account at the index
+    // --------------------------------
+    let validate_ix = program_owned_at_index_validate_ix(rule_set_addr, vec![]);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::MissingAccount);
+}
+
+// Create a `validate` instruction for a transfer, passing `accounts` as the additional rule
+// accounts.
+fn program_owned_at_index_validate_ix(rule_set_addr: Pubkey, accounts: Vec<Pubkey>) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(
+            accounts
+                .into_iter()
+                .map(|account| AccountMeta::new_readonly(account, false))
+                .collect(),
+        )
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::new(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
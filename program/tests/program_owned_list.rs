@@ -225,3 +225,67 @@ async fn program_owned_list() {
     // Validate transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn program_owned_list_second_program() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule.  The target must be owned by one of the two program IDs specified in the Rule.
+    let rule = Rule::ProgramOwnedList {
+        programs: vec![Keypair::new().pubkey(), mpl_token_auth_rules::ID],
+        field: PayloadKey::Destination.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new();
+
+    // Our destination key is going to be an account owned by the mpl-token-auth-rules program,
+    // which is the second program in the list.  Any one will do so for convenience we just use
+    // the `RuleSet`.
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(rule_set_addr),
+    )]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint.pubkey())
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(rule_set_addr, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
@@ -10,12 +10,12 @@ use mpl_token_auth_rules::{
 };
 use solana_program_test::tokio;
 use solana_sdk::{
-    instruction::AccountMeta, signature::Signer, signer::keypair::Keypair, system_instruction,
-    transaction::Transaction,
+    account::Account, instruction::AccountMeta, signature::Signer, signer::keypair::Keypair,
+    system_instruction, transaction::Transaction,
 };
 use utils::{
-    create_associated_token_account, create_mint, create_test_merkle_tree_from_one_leaf,
-    program_test, Operation, PayloadKey,
+    create_associated_token_account, create_mint, create_test_merkle_tree_from_leaves,
+    create_test_merkle_tree_from_one_leaf, program_test, Operation, PayloadKey,
 };
 
 #[tokio::test]
@@ -311,3 +311,96 @@ async fn program_owned_tree() {
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn program_owned_tree_three_programs() {
+    // Generate three program ids and an account owned by the middle one.
+    let programs = [
+        Keypair::new().pubkey(),
+        Keypair::new().pubkey(),
+        Keypair::new().pubkey(),
+    ];
+    let program_owned_account = Keypair::new().pubkey();
+
+    let mut program_test = program_test();
+    program_test.add_account(
+        program_owned_account,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![1; 8],
+            owner: programs[1],
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Generate a Merkle tree containing the three program ids, with a proof for the middle one.
+    let tree = create_test_merkle_tree_from_leaves(&programs, 1);
+
+    // Create a Rule.
+    let rule = Rule::ProgramOwnedTree {
+        root: tree.root,
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store the payload of data to validate against the rule definition.
+    let payload = Payload::from([
+        (
+            PayloadKey::Authority.to_string(),
+            PayloadType::Pubkey(program_owned_account),
+        ),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(tree.proof),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(
+            program_owned_account,
+            false,
+        )])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
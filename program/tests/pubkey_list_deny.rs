@@ -0,0 +1,106 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1, MAX_PUBKEY_LIST_LEN},
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn pubkey_list_deny() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The destination must not be one of the denied keys.
+    let denied_1 = Keypair::new().pubkey();
+    let denied_2 = Keypair::new().pubkey();
+
+    let rule = Rule::PubkeyListDeny {
+        pubkeys: vec![denied_1, denied_2],
+        field: PayloadKey::Destination.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail for a denied key
+    // --------------------------------
+    let validate_ix = pubkey_list_deny_validate_ix(rule_set_addr, denied_2);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyListDenyCheckFailed);
+
+    // --------------------------------
+    // Validate pass for any other key
+    // --------------------------------
+    let validate_ix = pubkey_list_deny_validate_ix(rule_set_addr, Keypair::new().pubkey());
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[test]
+fn pubkey_list_deny_too_long_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // Create a Rule with one more Pubkey than allowed.
+    let rule = Rule::PubkeyListDeny {
+        pubkeys: (0..=MAX_PUBKEY_LIST_LEN)
+            .map(|_| Keypair::new().pubkey())
+            .collect(),
+        field: PayloadKey::Destination.to_string(),
+    };
+
+    let err = rule_set
+        .add(Operation::TransferNamespace.to_string(), rule)
+        .unwrap_err();
+    assert_eq!(err, ProgramError::from(RuleSetError::PubkeyListTooLong));
+}
+
+// Create a `validate` instruction for a transfer to `destination`.
+fn pubkey_list_deny_validate_ix(rule_set_addr: Pubkey, destination: Pubkey) -> Instruction {
+    let payload = Payload::from([(
+        PayloadKey::Destination.to_string(),
+        PayloadType::Pubkey(destination),
+    )]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -6,8 +6,9 @@ use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
     payload::{Payload, PayloadType},
-    state::{Rule, RuleSetV1},
+    state::{Rule, RuleSetV1, MAX_PUBKEY_LIST_LEN},
 };
+use solana_program::program_error::ProgramError;
 use solana_program_test::tokio;
 use solana_sdk::{signature::Signer, signer::keypair::Keypair};
 use utils::{program_test, Operation, PayloadKey};
@@ -113,4 +114,75 @@ async fn test_pubkey_list_match() {
 
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate pass last element
+    // --------------------------------
+    // Store the payload of data to validate against the rule definition with the last Pubkey.
+    let payload = Payload::from([(
+        PayloadKey::Authority.to_string(),
+        PayloadType::Pubkey(target_3.pubkey()),
+    )]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[test]
+fn test_pubkey_list_max_len() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // A list at the maximum length can be added.
+    let rule = Rule::PubkeyListMatch {
+        pubkeys: (0..MAX_PUBKEY_LIST_LEN)
+            .map(|_| Keypair::new().pubkey())
+            .collect(),
+        field: PayloadKey::Authority.to_string(),
+    };
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // A list one longer than the maximum length is rejected.
+    let rule = Rule::PubkeyListMatch {
+        pubkeys: (0..=MAX_PUBKEY_LIST_LEN)
+            .map(|_| Keypair::new().pubkey())
+            .collect(),
+        field: PayloadKey::Authority.to_string(),
+    };
+    let err = rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::TransferDelegate,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, ProgramError::from(RuleSetError::PubkeyListTooLong));
 }
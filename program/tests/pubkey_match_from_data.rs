@@ -0,0 +1,194 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{account::Account, signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+// The offset of the update authority in the mock metadata account data, after a one byte key.
+const OFFSET: usize = 1;
+
+#[tokio::test]
+async fn pubkey_match_from_data() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule: The authority must be the update authority stored in the metadata account.
+    let rule = Rule::PubkeyMatchFromData {
+        field: PayloadKey::Authority.to_string(),
+        source_field: PayloadKey::Holder.to_string(),
+        offset: OFFSET,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Add a mock metadata account holding an update authority.
+    let update_authority = Keypair::new().pubkey();
+    let metadata = add_mock_account(&mut context, authority_data(&update_authority));
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let validate_ix = pubkey_match_from_data_ix(rule_set_addr, update_authority, metadata, true);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail with a different authority
+    // --------------------------------
+    let validate_ix =
+        pubkey_match_from_data_ix(rule_set_addr, Keypair::new().pubkey(), metadata, true);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyMatchCheckFailed);
+
+    // --------------------------------
+    // Validate fail with data too short to hold the authority
+    // --------------------------------
+    let mut data = authority_data(&update_authority);
+    data.pop();
+    let short = add_mock_account(&mut context, data);
+    let validate_ix = pubkey_match_from_data_ix(rule_set_addr, update_authority, short, true);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyMatchCheckFailed);
+
+    // --------------------------------
+    // Validate fail without the metadata account
+    // --------------------------------
+    let validate_ix = pubkey_match_from_data_ix(rule_set_addr, update_authority, metadata, false);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::MissingAccount);
+}
+
+#[tokio::test]
+async fn pubkey_match_from_data_offset_out_of_bounds() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a Rule reading past the end of any account data.
+    let rule = Rule::PubkeyMatchFromData {
+        field: PayloadKey::Authority.to_string(),
+        source_field: PayloadKey::Holder.to_string(),
+        offset: usize::MAX,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // The offset overflows rather than wrapping around to the start of the data.
+    let update_authority = Keypair::new().pubkey();
+    let metadata = add_mock_account(&mut context, authority_data(&update_authority));
+    let validate_ix = pubkey_match_from_data_ix(rule_set_addr, update_authority, metadata, true);
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyMatchCheckFailed);
+}
+
+// Account data holding `authority` at `OFFSET`.
+fn authority_data(authority: &Pubkey) -> Vec<u8> {
+    let mut data = vec![4; OFFSET];
+    data.extend_from_slice(authority.as_ref());
+    data
+}
+
+// Add an account holding `data`, returning its key.
+fn add_mock_account(context: &mut ProgramTestContext, data: Vec<u8>) -> Pubkey {
+    let key = Keypair::new().pubkey();
+    context.set_account(
+        &key,
+        &Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        }
+        .into(),
+    );
+    key
+}
+
+// Create a `validate` instruction comparing `authority` against the one stored in `metadata`,
+// passing the metadata account only if `with_account` is set.
+fn pubkey_match_from_data_ix(
+    rule_set_addr: Pubkey,
+    authority: Pubkey,
+    metadata: Pubkey,
+    with_account: bool,
+) -> Instruction {
+    let payload = Payload::from([
+        (
+            PayloadKey::Authority.to_string(),
+            PayloadType::Pubkey(authority),
+        ),
+        (
+            PayloadKey::Holder.to_string(),
+            PayloadType::Pubkey(metadata),
+        ),
+    ]);
+
+    let additional_rule_accounts = if with_account {
+        vec![AccountMeta::new_readonly(metadata, false)]
+    } else {
+        vec![]
+    };
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(additional_rule_accounts)
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -5,13 +5,17 @@ pub mod utils;
 use mpl_token_auth_rules::{
     error::RuleSetError,
     instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
-    payload::{Payload, PayloadType, ProofInfo},
-    state::{Rule, RuleSetV1},
+    merkle::hash_leaf,
+    payload::{Payload, PayloadType, ProofInfo, MAX_PROOF_DEPTH},
+    state::{from_msgpack_slice, Rule, RuleSetV1},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
 };
-use solana_program::pubkey::Pubkey;
 use solana_program_test::tokio;
 use solana_sdk::{signature::Signer, signer::keypair::Keypair};
-use utils::{program_test, Operation, PayloadKey};
+use utils::{create_test_merkle_tree_from_one_leaf, program_test, Operation, PayloadKey};
 
 #[tokio::test]
 async fn pubkey_tree_match() {
@@ -32,6 +36,7 @@ async fn pubkey_tree_match() {
         root: tree_root,
         pubkey_field: PayloadKey::Authority.to_string(),
         proof_field: PayloadKey::AuthorityProof.to_string(),
+        leaf_is_hashed: false,
     };
 
     // Create a RuleSet.
@@ -167,3 +172,333 @@ async fn pubkey_tree_match() {
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn pubkey_tree_match_proof_depth_limit() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create trees of the maximum depth and one level deeper for the same leaf.
+    let leaf = Keypair::new().pubkey();
+    let max_depth_tree = create_test_merkle_tree_from_one_leaf(&leaf, MAX_PROOF_DEPTH);
+    let too_deep_tree = create_test_merkle_tree_from_one_leaf(&leaf, MAX_PROOF_DEPTH + 1);
+
+    // Create a RuleSet with one operation per tree.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    for (scenario, tree) in [
+        (utils::TransferScenario::Holder, &max_depth_tree),
+        (utils::TransferScenario::TransferDelegate, &too_deep_tree),
+    ] {
+        rule_set
+            .add(
+                Operation::Transfer { scenario }.to_string(),
+                Rule::PubkeyTreeMatch {
+                    root: tree.root,
+                    pubkey_field: PayloadKey::Authority.to_string(),
+                    proof_field: PayloadKey::AuthorityProof.to_string(),
+                    leaf_is_hashed: false,
+                },
+            )
+            .unwrap();
+    }
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // --------------------------------
+    // Validate pass at the maximum depth
+    // --------------------------------
+    let payload = Payload::from([
+        (PayloadKey::Authority.to_string(), PayloadType::Pubkey(leaf)),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(max_depth_tree.proof),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate fail past the maximum depth
+    // --------------------------------
+    // The proof is correct for its tree, but too long.
+    let payload = Payload::from([
+        (PayloadKey::Authority.to_string(), PayloadType::Pubkey(leaf)),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(too_deep_tree.proof),
+        ),
+    ]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::TransferDelegate,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProofTooLong);
+}
+
+#[tokio::test]
+async fn pubkey_tree_match_from_account() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a tree whose only known member is the authority account.
+    let authority = Keypair::new().pubkey();
+    let tree = create_test_merkle_tree_from_one_leaf(&authority, 3);
+
+    // Create a Rule: The authority account must be a member of the Merkle tree.
+    let rule = Rule::PubkeyTreeMatchFromAccount {
+        root: tree.root,
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail without the account
+    // --------------------------------
+    // Claim the member leaf, but provide a different account.
+    let other = Keypair::new().pubkey();
+    let validate_ix = tree_match_from_account_ix(rule_set_addr, authority, &tree.proof, other);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::MissingAccount);
+
+    // --------------------------------
+    // Validate fail with a mismatched account
+    // --------------------------------
+    // Use the member's proof for an account that is not in the tree.
+    let validate_ix = tree_match_from_account_ix(rule_set_addr, other, &tree.proof, other);
+
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyTreeMatchCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let validate_ix = tree_match_from_account_ix(rule_set_addr, authority, &tree.proof, authority);
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+// Create a `validate` instruction claiming `leaf` with `proof`, and providing `account` as the
+// only additional rule account.
+fn tree_match_from_account_ix(
+    rule_set_addr: Pubkey,
+    leaf: Pubkey,
+    proof: &ProofInfo,
+    account: Pubkey,
+) -> Instruction {
+    let payload = Payload::from([
+        (PayloadKey::Authority.to_string(), PayloadType::Pubkey(leaf)),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(proof.clone()),
+        ),
+    ]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(account, false)])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
+
+#[tokio::test]
+async fn pubkey_tree_match_hashed_leaf() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create one tree storing the authority as a raw leaf, and one storing it hashed.
+    let authority = Keypair::new().pubkey();
+    let raw_tree = create_test_merkle_tree_from_one_leaf(&authority, 3);
+    let hashed_tree =
+        create_test_merkle_tree_from_one_leaf(&Pubkey::from(hash_leaf(&authority)), 3);
+
+    // Create a RuleSet with one operation for each tree and leaf convention.
+    let cases = [
+        (utils::TransferScenario::Holder, &raw_tree, false, true),
+        (
+            utils::TransferScenario::TransferDelegate,
+            &raw_tree,
+            true,
+            false,
+        ),
+        (
+            utils::TransferScenario::SaleDelegate,
+            &hashed_tree,
+            true,
+            true,
+        ),
+        (
+            utils::TransferScenario::MigrationDelegate,
+            &hashed_tree,
+            false,
+            false,
+        ),
+    ];
+
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    for (scenario, tree, leaf_is_hashed, _) in &cases {
+        rule_set
+            .add(
+                Operation::Transfer {
+                    scenario: scenario.clone(),
+                }
+                .to_string(),
+                Rule::PubkeyTreeMatch {
+                    root: tree.root,
+                    pubkey_field: PayloadKey::Authority.to_string(),
+                    proof_field: PayloadKey::AuthorityProof.to_string(),
+                    leaf_is_hashed: *leaf_is_hashed,
+                },
+            )
+            .unwrap();
+    }
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate each case
+    // --------------------------------
+    // Each proof is correct for its tree, so it only verifies when the leaf convention matches.
+    for (scenario, tree, _, passes) in cases {
+        let payload = Payload::from([
+            (
+                PayloadKey::Authority.to_string(),
+                PayloadType::Pubkey(authority),
+            ),
+            (
+                PayloadKey::AuthorityProof.to_string(),
+                PayloadType::MerkleProof(tree.proof.clone()),
+            ),
+        ]);
+
+        // Create a `validate` instruction.
+        let validate_ix = ValidateBuilder::new()
+            .rule_set_pda(rule_set_addr)
+            .mint(Keypair::new().pubkey())
+            .additional_rule_accounts(vec![])
+            .build(ValidateArgs::V1 {
+                operation: Operation::Transfer { scenario }.to_string(),
+                payload,
+                update_rule_state: false,
+                rule_set_revision: None,
+            })
+            .unwrap()
+            .instruction();
+
+        if passes {
+            process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+        } else {
+            let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+            // Check that error is what we expect.
+            assert_custom_error!(err, RuleSetError::PubkeyTreeMatchCheckFailed);
+        }
+    }
+}
+
+#[test]
+fn pubkey_tree_match_without_leaf_is_hashed_deserializes() {
+    let rule = Rule::PubkeyTreeMatch {
+        root: [1; 32],
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+        leaf_is_hashed: false,
+    };
+
+    // The Rule serializes as a map from its name to an array of its four fields, the last of
+    // which is `false`.
+    let mut data = rmp_serde::to_vec(&rule).unwrap();
+    assert_eq!(data.pop(), Some(0xc2));
+
+    // Drop the last field to get the Rule as serialized before `leaf_is_hashed` was added.
+    let name = b"PubkeyTreeMatch";
+    let array_loc = data
+        .windows(name.len())
+        .position(|window| window == name)
+        .unwrap()
+        + name.len();
+    assert_eq!(data[array_loc], 0x94);
+    data[array_loc] = 0x93;
+
+    // The missing field defaults to raw leaves.
+    assert_eq!(from_msgpack_slice::<Rule>(&data), Ok(rule));
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType, ProofInfo},
+    state::{Rule, RuleSetV1, MAX_TREE_ROOTS},
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{create_test_merkle_tree_from_leaves, program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn pubkey_tree_match_any() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create two trees, for example one per marketplace category.
+    let first_leaves = (0..4).map(|_| Keypair::new().pubkey()).collect::<Vec<_>>();
+    let second_leaves = (0..4).map(|_| Keypair::new().pubkey()).collect::<Vec<_>>();
+    let first_tree = create_test_merkle_tree_from_leaves(&first_leaves, 0);
+    let second_tree = create_test_merkle_tree_from_leaves(&second_leaves, 2);
+
+    // Create a Rule: The provided leaf node must be a member of one of the trees.
+    let rule = Rule::PubkeyTreeMatchAny {
+        roots: vec![first_tree.root, second_tree.root],
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+        root_index_field: PayloadKey::RootIndex.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // A proof for the second tree does not prove membership in the first.
+    let validate_ix = tree_match_any_ix(
+        rule_set_addr,
+        mint,
+        second_leaves[2],
+        second_tree.proof.clone(),
+        0,
+    );
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyTreeMatchCheckFailed);
+
+    // An index past the last root selects no tree.
+    let validate_ix = tree_match_any_ix(
+        rule_set_addr,
+        mint,
+        second_leaves[2],
+        second_tree.proof.clone(),
+        2,
+    );
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::PubkeyTreeMatchCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // The proof proves membership in the second tree.
+    let validate_ix =
+        tree_match_any_ix(rule_set_addr, mint, second_leaves[2], second_tree.proof, 1);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // A member of the first tree also passes with its own root.
+    let validate_ix = tree_match_any_ix(rule_set_addr, mint, first_leaves[0], first_tree.proof, 0);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[test]
+fn pubkey_tree_match_any_root_limit() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Pubkey::new_unique());
+
+    // The maximum number of roots is accepted.
+    let rule = Rule::PubkeyTreeMatchAny {
+        roots: vec![[1; 32]; MAX_TREE_ROOTS],
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+        root_index_field: PayloadKey::RootIndex.to_string(),
+    };
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // One root more than the maximum is rejected.
+    let rule = Rule::PubkeyTreeMatchAny {
+        roots: vec![[1; 32]; MAX_TREE_ROOTS + 1],
+        pubkey_field: PayloadKey::Authority.to_string(),
+        proof_field: PayloadKey::AuthorityProof.to_string(),
+        root_index_field: PayloadKey::RootIndex.to_string(),
+    };
+    let err = rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::TransferDelegate,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, ProgramError::from(RuleSetError::TooManyTreeRoots));
+}
+
+// Create a `validate` instruction for a `PubkeyTreeMatchAny` rule with the given leaf, proof and
+// root index.
+fn tree_match_any_ix(
+    rule_set_addr: Pubkey,
+    mint: Pubkey,
+    leaf: Pubkey,
+    proof: ProofInfo,
+    root_index: u64,
+) -> Instruction {
+    let payload = Payload::from([
+        (PayloadKey::Authority.to_string(), PayloadType::Pubkey(leaf)),
+        (
+            PayloadKey::AuthorityProof.to_string(),
+            PayloadType::MerkleProof(proof),
+        ),
+        (
+            PayloadKey::RootIndex.to_string(),
+            PayloadType::Number(root_index),
+        ),
+    ]);
+
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
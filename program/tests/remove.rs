@@ -0,0 +1,50 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::state::{CompareOp, Rule, RuleSetV1};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn remove_operation() {
+    let amount_check = Rule::Amount {
+        amount: 1,
+        operator: CompareOp::Eq,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    // Create a RuleSet with two operations.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+    rule_set
+        .add(
+            Operation::DelegateNamespace.to_string(),
+            amount_check.clone(),
+        )
+        .unwrap();
+    let original_size = rule_set.serialized_size().unwrap();
+
+    // Removing an operation returns its Rule.
+    let removed = rule_set.remove(&Operation::DelegateNamespace.to_string());
+    assert_eq!(removed, Some(amount_check));
+    assert_eq!(
+        rule_set.get(&Operation::DelegateNamespace.to_string()),
+        None
+    );
+    assert_eq!(
+        rule_set.operations().collect::<Vec<_>>(),
+        vec![&Operation::TransferNamespace.to_string()]
+    );
+
+    // The serialized RuleSet shrinks.
+    assert!(rule_set.serialized_size().unwrap() < original_size);
+
+    // Removing it again returns nothing.
+    assert_eq!(
+        rule_set.remove(&Operation::DelegateNamespace.to_string()),
+        None
+    );
+}
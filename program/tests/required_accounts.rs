@@ -0,0 +1,128 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::pubkey::Pubkey;
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn program_owned_requires_target_account() {
+    // Create a RuleSet where the destination must be owned by this program.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Pubkey::new_unique());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::ProgramOwned {
+                program: mpl_token_auth_rules::ID,
+                field: PayloadKey::Destination.to_string(),
+            },
+        )
+        .unwrap();
+
+    let destination = Pubkey::new_unique();
+    let payload = Payload::from([
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (PayloadKey::Amount.to_string(), PayloadType::Number(1)),
+    ]);
+
+    // Only the destination account is needed, not the program.
+    let operation = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+    assert_eq!(
+        rule_set.required_accounts(&operation, &payload),
+        vec![destination]
+    );
+
+    // Nothing is needed when the destination is missing from the payload, or for an operation
+    // without a Rule.
+    assert!(rule_set
+        .required_accounts(&operation, &Payload::default())
+        .is_empty());
+    assert!(rule_set
+        .required_accounts(&Operation::TransferNamespace.to_string(), &payload)
+        .is_empty());
+}
+
+#[test]
+fn required_accounts_of_nested_rules() {
+    let signer = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+
+    // Create a RuleSet where the owner transfer falls back to its namespace.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Pubkey::new_unique());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Namespace,
+        )
+        .unwrap();
+    rule_set
+        .add(
+            Operation::TransferNamespace.to_string(),
+            Rule::All {
+                rules: vec![
+                    Rule::AdditionalSigner { account: signer },
+                    Rule::Any {
+                        rules: vec![
+                            Rule::ProgramOwnedList {
+                                programs: vec![mpl_token_auth_rules::ID],
+                                field: format!(
+                                    "{}|{}",
+                                    PayloadKey::Source.to_string(),
+                                    PayloadKey::Destination.to_string()
+                                ),
+                            },
+                            Rule::IsWallet {
+                                field: PayloadKey::Destination.to_string(),
+                            },
+                        ],
+                    },
+                    Rule::PubkeyMatch {
+                        pubkey: Pubkey::new_unique(),
+                        field: PayloadKey::Authority.to_string(),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+
+    let payload = Payload::from([
+        (PayloadKey::Source.to_string(), PayloadType::Pubkey(source)),
+        (
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(destination),
+        ),
+        (
+            PayloadKey::Authority.to_string(),
+            PayloadType::Pubkey(Pubkey::new_unique()),
+        ),
+    ]);
+
+    // Each account is listed once, and the `PubkeyMatch` authority is not an account.
+    assert_eq!(
+        rule_set.required_accounts(
+            &Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            &payload
+        ),
+        vec![signer, source, destination]
+    );
+}
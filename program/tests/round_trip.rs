@@ -0,0 +1,147 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_auth_rules::{
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use std::collections::{HashMap, HashSet};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn rule_set_rmp_round_trip() {
+    // Create a RuleSet with nested composite rules.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Any {
+                rules: vec![
+                    Rule::AdditionalSigner {
+                        account: Keypair::new().pubkey(),
+                    },
+                    Rule::Amount {
+                        amount: 5,
+                        operator: CompareOp::LtEq,
+                        field: PayloadKey::Amount.to_string(),
+                    },
+                ],
+            },
+            Rule::Not {
+                rule: Box::new(Rule::PubkeyMatch {
+                    pubkey: Keypair::new().pubkey(),
+                    field: PayloadKey::Destination.to_string(),
+                }),
+            },
+        ],
+    };
+
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Namespace)
+        .unwrap();
+
+    // RuleSet -> rmp bytes -> RuleSet.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    let deserialized: RuleSetV1 = rmp_serde::from_slice(&serialized_rule_set).unwrap();
+
+    assert_eq!(deserialized, rule_set);
+}
+
+#[test]
+fn rule_set_serialization_is_independent_of_insertion_order() {
+    let owner = Keypair::new().pubkey();
+    let programs: Vec<_> = (0..16).map(|_| Keypair::new().pubkey()).collect();
+
+    let operations = vec![
+        (Operation::TransferNamespace.to_string(), Rule::Namespace),
+        (
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        ),
+        (
+            Operation::Transfer {
+                scenario: utils::TransferScenario::TransferDelegate,
+            }
+            .to_string(),
+            Rule::ProgramOwnedSet {
+                programs: programs.iter().copied().collect(),
+                field: PayloadKey::Destination.to_string(),
+            },
+        ),
+    ];
+
+    // Add the operations in order, and the same operations in reverse order with the set of
+    // programs also built in reverse.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    for (operation, rule) in operations.iter().cloned() {
+        rule_set.add(operation, rule).unwrap();
+    }
+
+    let mut reversed_rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    for (operation, rule) in operations.into_iter().rev() {
+        let rule = match rule {
+            Rule::ProgramOwnedSet { field, .. } => Rule::ProgramOwnedSet {
+                programs: programs.iter().rev().copied().collect::<HashSet<_>>(),
+                field,
+            },
+            rule => rule,
+        };
+        reversed_rule_set.add(operation, rule).unwrap();
+    }
+
+    // Both serialize to the same bytes.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    let mut serialized_reversed_rule_set = Vec::new();
+    reversed_rule_set
+        .serialize(&mut Serializer::new(&mut serialized_reversed_rule_set))
+        .unwrap();
+
+    assert_eq!(serialized_rule_set, serialized_reversed_rule_set);
+}
+
+#[test]
+fn payload_from_payload_key_map() {
+    // `PayloadKey`s can key a map of values.
+    let values = HashMap::from([
+        (PayloadKey::Amount, PayloadType::Number(1)),
+        (
+            PayloadKey::Destination,
+            PayloadType::Pubkey(Keypair::new().pubkey()),
+        ),
+    ]);
+
+    let mut payload = Payload::new();
+    for (key, value) in &values {
+        payload.insert(key.to_string(), value.clone());
+    }
+
+    // Payload -> Borsh bytes -> Payload.
+    let deserialized = Payload::try_from_slice(&payload.try_to_vec().unwrap()).unwrap();
+    assert_eq!(deserialized, payload);
+    assert_eq!(
+        deserialized.get(&PayloadKey::Amount.to_string()),
+        values.get(&PayloadKey::Amount)
+    );
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::{RuleError, RuleSetError},
+    instruction::{builders::CreateOrUpdateBuilder, CreateOrUpdateArgs, InstructionBuilder},
+    payload::Payload,
+    state::{InMemoryValidationContext, Rule, RuleSetV1, MAX_RULE_DEPTH},
+};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_program::program_error::ProgramError;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation};
+
+#[test]
+fn add_max_depth_not_chain() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // A chain of `MAX_RULE_DEPTH` Not rules can be added.
+    let rule = not_chain(MAX_RULE_DEPTH);
+    assert_eq!(rule.assert_depth(), Ok(()));
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), rule)
+        .unwrap();
+}
+
+#[test]
+fn add_too_deep_not_chain_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // A chain of 17 Not rules is one too deep.
+    let rule = not_chain(MAX_RULE_DEPTH + 1);
+    let err = rule_set
+        .add(Operation::TransferNamespace.to_string(), rule)
+        .unwrap_err();
+
+    // Check that error is what we expect.
+    assert_eq!(err, ProgramError::from(RuleSetError::RuleTreeTooDeep));
+}
+
+#[test]
+fn too_deep_in_any_branch_fails() {
+    // Only the deepest branch counts towards the depth.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Pass,
+            Rule::Any {
+                rules: vec![Rule::Pass, not_chain(MAX_RULE_DEPTH - 1)],
+            },
+        ],
+    };
+
+    assert_eq!(
+        rule.assert_depth(),
+        Err(ProgramError::from(RuleSetError::RuleTreeTooDeep))
+    );
+}
+
+#[test]
+fn validate_too_deep_fails() {
+    // Validation checks the depth before recursing into the tree.
+    let err = not_chain(MAX_RULE_DEPTH + 2)
+        .validate(
+            &InMemoryValidationContext::new(),
+            &Payload::default(),
+            false,
+            &None,
+            &None,
+        )
+        .unwrap_err();
+
+    assert_eq!(err, RuleError::from(RuleSetError::RuleTreeTooDeep));
+}
+
+#[tokio::test]
+async fn create_too_deep_rule_set_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a RuleSet, inserting the Rule directly so that `add` does not reject it.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.operations.insert(
+        Operation::TransferNamespace.to_string(),
+        not_chain(MAX_RULE_DEPTH + 1),
+    );
+
+    // Serialize the RuleSet using RMP serde.
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        "test rule_set".to_string(),
+    );
+
+    // Create a `create_or_update` instruction.
+    let create_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::V1 {
+            serialized_rule_set,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let create_tx = Transaction::new_signed_with_payer(
+        &[create_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(create_tx)
+        .await
+        .expect_err("Creation should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleTreeTooDeep);
+}
+
+// Wrap a Pass Rule in `depth` Not Rules.
+fn not_chain(depth: usize) -> Rule {
+    (0..depth).fold(Rule::Pass, |rule, _| Rule::Not {
+        rule: Box::new(rule),
+    })
+}
@@ -0,0 +1,123 @@
+#![cfg(all(feature = "test-bpf", feature = "log-rule-path"))]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use solana_program::instruction::InstructionError;
+use solana_program_test::tokio;
+use solana_sdk::{
+    signature::Signer,
+    signer::keypair::Keypair,
+    transaction::{Transaction, TransactionError},
+};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn nested_failure_path_is_logged() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule whose second branch contains two failing Amount rules.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Pass,
+            Rule::Any {
+                rules: vec![
+                    Rule::Amount {
+                        amount: 1,
+                        operator: CompareOp::Lt,
+                        field: PayloadKey::Amount.to_string(),
+                    },
+                    Rule::Amount {
+                        amount: 1,
+                        operator: CompareOp::Gt,
+                        field: PayloadKey::Amount.to_string(),
+                    },
+                ],
+            },
+        ],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store a payload of data with an amount that fails both Amount Rules.
+    let payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]);
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let validate_tx = Transaction::new_signed_with_payer(
+        &[validate_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Simulate the transaction, keeping the program logs.
+    let simulation = context
+        .banks_client
+        .simulate_transaction(validate_tx)
+        .await
+        .unwrap();
+
+    // Check that error is what we expect.  `Any` returns its last failure.
+    assert_eq!(
+        simulation.result.unwrap(),
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RuleSetError::AmountCheckFailed as u32)
+        ))
+    );
+
+    // Check that the path to the failing rule was logged.
+    let log_messages = simulation.simulation_details.unwrap().logs;
+    assert!(
+        log_messages
+            .iter()
+            .any(|log| log.ends_with("All[1].Any[1].Amount failed")),
+        "{:#?}",
+        log_messages
+    );
+}
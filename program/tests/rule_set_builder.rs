@@ -0,0 +1,104 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    state::{CompareOp, Rule, RuleSetV1},
+    MAX_NAME_LENGTH,
+};
+use rmp_serde::Serializer;
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{Operation, PayloadKey};
+
+#[test]
+fn builder_matches_add() {
+    let owner = Keypair::new().pubkey();
+    let holder = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+    let amount_check = Rule::Amount {
+        amount: 5,
+        operator: CompareOp::LtEq,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    // Create a RuleSet with `new` and `add`.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    rule_set.add(holder.clone(), amount_check.clone()).unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Namespace)
+        .unwrap();
+
+    // Create the same RuleSet with the builder.
+    let built_rule_set = RuleSetV1::builder()
+        .name("test rule_set")
+        .owner(owner)
+        .operation(holder, amount_check)
+        .operation(Operation::TransferNamespace.to_string(), Rule::Namespace)
+        .build()
+        .unwrap();
+
+    assert_eq!(built_rule_set, rule_set);
+
+    // Both serialize to the same bytes.
+    assert_eq!(serialize(&built_rule_set), serialize(&rule_set));
+}
+
+#[test]
+fn builder_errors() {
+    let owner = Keypair::new().pubkey();
+
+    // The name and owner are required.
+    assert_eq!(
+        RuleSetV1::builder().owner(owner).build(),
+        Err(RuleSetError::EmptyName)
+    );
+    assert_eq!(
+        RuleSetV1::builder().name("").owner(owner).build(),
+        Err(RuleSetError::EmptyName)
+    );
+    assert_eq!(
+        RuleSetV1::builder().name("test rule_set").build(),
+        Err(RuleSetError::MissingOwner)
+    );
+    assert_eq!(
+        RuleSetV1::builder()
+            .name("test rule_set")
+            .owner(Pubkey::default())
+            .build(),
+        Err(RuleSetError::MissingOwner)
+    );
+
+    // The name must also be valid for a PDA.
+    assert_eq!(
+        RuleSetV1::builder()
+            .name("a".repeat(MAX_NAME_LENGTH + 1))
+            .owner(owner)
+            .build(),
+        Err(RuleSetError::NameTooLong)
+    );
+
+    // Errors from `add` are returned by `build`.
+    assert_eq!(
+        RuleSetV1::builder()
+            .name("test rule_set")
+            .owner(owner)
+            .operation(Operation::TransferNamespace.to_string(), Rule::Pass)
+            .operation(Operation::TransferNamespace.to_string(), Rule::Pass)
+            .build(),
+        Err(RuleSetError::ValueOccupied)
+    );
+}
+
+// Serialize a RuleSet using RMP serde.
+fn serialize(rule_set: &RuleSetV1) -> Vec<u8> {
+    let mut serialized_rule_set = Vec::new();
+    rule_set
+        .serialize(&mut Serializer::new(&mut serialized_rule_set))
+        .unwrap();
+    serialized_rule_set
+}
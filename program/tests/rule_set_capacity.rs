@@ -0,0 +1,66 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    state::{CompareOp, MergeStrategy, Rule, RuleSetV1},
+};
+use solana_program::program_error::ProgramError;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+
+#[test]
+fn add_up_to_max_operations() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    assert_eq!(rule_set.remaining_capacity(), RuleSetV1::MAX_OPERATIONS);
+
+    // Fill the RuleSet up to the cap.
+    for i in 0..RuleSetV1::MAX_OPERATIONS {
+        rule_set.add(format!("Operation{}", i), Rule::Pass).unwrap();
+    }
+    assert_eq!(rule_set.remaining_capacity(), 0);
+
+    // Adding one more operation fails.
+    let err = rule_set
+        .add("Operation".to_string(), Rule::Pass)
+        .unwrap_err();
+    assert_eq!(err, ProgramError::from(RuleSetError::RuleSetTooLarge));
+    assert_eq!(rule_set.get("Operation"), None);
+}
+
+#[test]
+fn add_over_max_serialized_size_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+
+    // A Rule whose field name alone does not fit in an account.
+    let rule = Rule::Amount {
+        amount: 1,
+        operator: CompareOp::Eq,
+        field: "a".repeat(RuleSetV1::MAX_SERIALIZED_SIZE),
+    };
+
+    let err = rule_set.add("Operation".to_string(), rule).unwrap_err();
+    assert_eq!(err, ProgramError::from(RuleSetError::RuleSetTooLarge));
+    assert_eq!(rule_set.remaining_capacity(), RuleSetV1::MAX_OPERATIONS);
+}
+
+#[test]
+fn merge_over_max_operations_fails() {
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), Keypair::new().pubkey());
+    for i in 0..RuleSetV1::MAX_OPERATIONS - 1 {
+        rule_set.add(format!("Operation{}", i), Rule::Pass).unwrap();
+    }
+
+    // An overlay with one existing operation and two new ones, one more than there is room for.
+    let mut overlay = RuleSetV1::new("overlay rule_set".to_string(), Keypair::new().pubkey());
+    for operation in ["Operation0", "New0", "New1"] {
+        overlay.add(operation.to_string(), Rule::Pass).unwrap();
+    }
+    let original = rule_set.clone();
+
+    let err = rule_set
+        .merge(overlay, MergeStrategy::Overwrite)
+        .unwrap_err();
+    assert_eq!(err, RuleSetError::RuleSetTooLarge);
+    assert_eq!(rule_set, original);
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::BorshDeserialize;
+use mpl_token_auth_rules::state::{
+    CompareOp, Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, RULE_SET_SERIALIZED_HEADER_LEN,
+};
+use solana_program::keccak;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[test]
+fn hash_is_stable() {
+    let owner = Keypair::new().pubkey();
+    let transfer = Operation::TransferNamespace.to_string();
+    let update = Operation::UpdateNamespace.to_string();
+    let amount = |amount| Rule::Amount {
+        amount,
+        operator: CompareOp::Lt,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    // Equal RuleSets have the same hash, whatever order their operations were added in.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    rule_set.add(transfer.clone(), amount(1)).unwrap();
+    rule_set.add(update.clone(), Rule::Pass).unwrap();
+
+    let mut same_rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    same_rule_set.add(update.clone(), Rule::Pass).unwrap();
+    same_rule_set.add(transfer.clone(), amount(1)).unwrap();
+
+    assert_eq!(rule_set.hash(), same_rule_set.hash());
+
+    // Changing one Rule changes the hash.
+    let mut other_rule_set = RuleSetV1::new("test rule_set".to_string(), owner);
+    other_rule_set.add(transfer, amount(2)).unwrap();
+    other_rule_set.add(update, Rule::Pass).unwrap();
+
+    assert_ne!(rule_set.hash(), other_rule_set.hash());
+}
+
+#[tokio::test]
+async fn hash_matches_on_chain_revision() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+    let expected_hash = rule_set.hash().unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    // Hash the latest revision, skipping its version byte.
+    let header = RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    let revision_map =
+        RuleSetRevisionMapV1::try_from_slice(&data[header.rev_map_version_location + 1..]).unwrap();
+    let latest = *revision_map.rule_set_revisions.last().unwrap();
+
+    assert_eq!(
+        keccak::hash(&data[latest + 1..header.rev_map_version_location]).to_bytes(),
+        expected_hash
+    );
+}
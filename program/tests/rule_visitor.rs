@@ -0,0 +1,128 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::state::{Rule, RuleSetV1, RuleVisitor};
+use solana_program::pubkey::Pubkey;
+use utils::{Operation, PayloadKey};
+
+// Collects every `Pubkey` referenced by a visited rule tree.
+#[derive(Default)]
+struct PubkeyCollector {
+    pubkeys: Vec<Pubkey>,
+}
+
+impl RuleVisitor for PubkeyCollector {
+    fn visit_leaf(&mut self, rule: &Rule) {
+        match rule {
+            Rule::AdditionalSigner { account } => self.pubkeys.push(*account),
+            Rule::PubkeyMatch { pubkey, .. } => self.pubkeys.push(*pubkey),
+            Rule::ProgramOwned { program, .. } => self.pubkeys.push(*program),
+            Rule::PubkeyListMatch { pubkeys, .. } => self.pubkeys.extend(pubkeys),
+            Rule::ProgramOwnedList { programs, .. } => self.pubkeys.extend(programs),
+            _ => (),
+        }
+    }
+}
+
+// Records the name of each visited rule, marking composites.
+#[derive(Default)]
+struct NameRecorder {
+    names: Vec<String>,
+}
+
+impl RuleVisitor for NameRecorder {
+    fn visit_composite(&mut self, rule: &Rule) {
+        self.names.push(format!("{}(", rule.name()));
+    }
+
+    fn visit_leaf(&mut self, rule: &Rule) {
+        self.names.push(rule.name().to_string());
+    }
+}
+
+#[test]
+fn visitor_collects_nested_pubkeys() {
+    let keys: Vec<Pubkey> = (0..7).map(|_| Pubkey::new_unique()).collect();
+
+    // Create a RuleSet with `Pubkey`s at several depths of nesting.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), keys[0]);
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::All {
+                rules: vec![
+                    Rule::AdditionalSigner { account: keys[1] },
+                    Rule::Any {
+                        rules: vec![
+                            Rule::PubkeyMatch {
+                                pubkey: keys[2],
+                                field: PayloadKey::Destination.to_string(),
+                            },
+                            Rule::Not {
+                                rule: Box::new(Rule::ProgramOwned {
+                                    program: keys[3],
+                                    field: PayloadKey::Destination.to_string(),
+                                }),
+                            },
+                        ],
+                    },
+                ],
+            },
+        )
+        .unwrap();
+    rule_set
+        .add(
+            Operation::TransferNamespace.to_string(),
+            Rule::IfThen {
+                condition: Box::new(Rule::PubkeyListMatch {
+                    pubkeys: vec![keys[4], keys[5]],
+                    field: PayloadKey::Authority.to_string(),
+                }),
+                then_rule: Box::new(Rule::Pass),
+                else_rule: Some(Box::new(Rule::ProgramOwnedList {
+                    programs: vec![keys[6]],
+                    field: PayloadKey::Destination.to_string(),
+                })),
+            },
+        )
+        .unwrap();
+
+    // Collect the `Pubkey`s of every operation in one pass per operation.
+    let mut collector = PubkeyCollector::default();
+    for operation in rule_set.operations() {
+        rule_set.get(operation).unwrap().visit(&mut collector);
+    }
+
+    // Operations are visited in sorted order, and each tree depth first.
+    assert_eq!(
+        collector.pubkeys,
+        [&keys[4..], &keys[1..4]].concat(),
+        "every referenced Pubkey is collected"
+    );
+}
+
+#[test]
+fn visitor_order() {
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Any {
+                rules: vec![Rule::Pass, Rule::Namespace],
+            },
+            Rule::Not {
+                rule: Box::new(Rule::Pass),
+            },
+        ],
+    };
+
+    let mut recorder = NameRecorder::default();
+    rule.visit(&mut recorder);
+
+    assert_eq!(
+        recorder.names,
+        ["All(", "Any(", "Pass", "Namespace", "Not(", "Pass"]
+    );
+}
@@ -0,0 +1,274 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{
+        builders::{CreateOrUpdateBuilder, SetOwnerBuilder, ValidateBuilder},
+        CreateOrUpdateArgs, InstructionBuilder, SetOwnerArgs, ValidateArgs,
+    },
+    payload::Payload,
+    state::{Rule, RuleSetHeader, RuleSetV1, RULE_SET_SERIALIZED_HEADER_LEN},
+};
+use solana_program::{borsh::try_from_slice_unchecked, instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{
+    signature::Signer, signer::keypair::Keypair, system_instruction, transaction::Transaction,
+};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn set_owner_transfers_update_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Set new owner
+    // --------------------------------
+    // Create and fund the new owner so it can pay for updates.
+    let new_owner = Keypair::new();
+    let fund_tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &new_owner.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(fund_tx)
+        .await
+        .unwrap();
+
+    // Create a `set_owner` instruction.
+    let set_owner_ix = SetOwnerBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(SetOwnerArgs::V1 {
+            new_owner: new_owner.pubkey(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let set_owner_tx = Transaction::new_signed_with_payer(
+        &[set_owner_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    context
+        .banks_client
+        .process_transaction(set_owner_tx)
+        .await
+        .unwrap();
+
+    // Check the new owner is stored in the header.
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    let header: RuleSetHeader =
+        try_from_slice_unchecked(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    assert_eq!(header.owner, new_owner.pubkey());
+    assert_eq!(header.creator, context.payer.pubkey());
+
+    // --------------------------------
+    // Validate pass after the transfer
+    // --------------------------------
+    let validate_ix = transfer_validate_ix(rule_set_addr);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Update fail with old owner
+    // --------------------------------
+    // Create a RuleSet still owned by the old owner, with an operation added so the transaction
+    // differs from the one that created the RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+
+    // Create a `create_or_update` instruction.
+    let update_ix = CreateOrUpdateBuilder::new()
+        .payer(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap())
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let update_tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Fail to update the RuleSet.
+    let err = context
+        .banks_client
+        .process_transaction(update_tx)
+        .await
+        .expect_err("update should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetOwnerMismatch);
+
+    // --------------------------------
+    // Update pass with new owner
+    // --------------------------------
+    // Create a RuleSet owned by the new owner, keeping the same name.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), new_owner.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Create a `create_or_update` instruction.
+    let update_ix = CreateOrUpdateBuilder::new()
+        .payer(new_owner.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap())
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let update_tx = Transaction::new_signed_with_payer(
+        &[update_ix],
+        Some(&new_owner.pubkey()),
+        &[&new_owner],
+        context.last_blockhash,
+    );
+
+    // Update the RuleSet.
+    context
+        .banks_client
+        .process_transaction(update_tx)
+        .await
+        .unwrap();
+
+    // --------------------------------
+    // Validate pass after the new owner's update
+    // --------------------------------
+    // The PDA is still derived from the creator, not from the owner in the new revision.
+    let validate_ix = transfer_validate_ix(rule_set_addr);
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn set_owner_to_default_pubkey_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Set default pubkey as new owner
+    // --------------------------------
+    // Create a `set_owner` instruction.
+    let set_owner_ix = SetOwnerBuilder::new()
+        .owner(context.payer.pubkey())
+        .rule_set_pda(rule_set_addr)
+        .build(SetOwnerArgs::V1 {
+            new_owner: Pubkey::default(),
+        })
+        .unwrap()
+        .instruction();
+
+    // Add it to a transaction.
+    let set_owner_tx = Transaction::new_signed_with_payer(
+        &[set_owner_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Process the transaction.
+    let err = context
+        .banks_client
+        .process_transaction(set_owner_tx)
+        .await
+        .expect_err("set_owner should fail");
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::InvalidNewOwner);
+}
+
+// Create a `validate` instruction for the `Transfer` operation.
+fn transfer_validate_ix(rule_set_addr: Pubkey) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn simulate_logs_failure_and_passes() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule whose second branch contains two failing Amount rules.
+    let rule = Rule::All {
+        rules: vec![
+            Rule::Pass,
+            Rule::Any {
+                rules: vec![
+                    Rule::Amount {
+                        amount: 1,
+                        operator: CompareOp::Lt,
+                        field: PayloadKey::Amount.to_string(),
+                    },
+                    Rule::Amount {
+                        amount: 1,
+                        operator: CompareOp::Gt,
+                        field: PayloadKey::Amount.to_string(),
+                    },
+                ],
+            },
+        ],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Simulate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store a payload of data with an amount that fails both Amount Rules.
+    let payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]);
+
+    // Create a `validate` instruction in simulate mode.
+    let simulate_ix = validate_v2_ix(rule_set_addr, mint, payload.clone(), true);
+
+    // Add it to a transaction.
+    let simulate_tx = Transaction::new_signed_with_payer(
+        &[simulate_ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    // Simulate the transaction, keeping the program logs.
+    let simulation = context
+        .banks_client
+        .simulate_transaction(simulate_tx)
+        .await
+        .unwrap();
+
+    // Check that the instruction succeeded even though the Rule fails.
+    assert_eq!(simulation.result.unwrap(), Ok(()));
+
+    // Check that each rule result was logged.
+    let amount_err = ProgramError::from(RuleSetError::AmountCheckFailed);
+    let log_messages = simulation.simulation_details.unwrap().logs;
+    for expected in [
+        "All[0].Pass passed".to_string(),
+        format!("All[1].Any[0].Amount failed: {}", amount_err),
+        format!("All[1].Any[1].Amount failed: {}", amount_err),
+        "All[1].Any failed".to_string(),
+        "All failed".to_string(),
+    ] {
+        assert!(
+            log_messages.iter().any(|log| log.ends_with(&expected)),
+            "missing {:?} in {:#?}",
+            expected,
+            log_messages
+        );
+    }
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create the same `validate` instruction without simulate mode.
+    let validate_ix = validate_v2_ix(rule_set_addr, mint, payload, false);
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.  `Any` returns its last failure.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+}
+
+// Create a `validate` instruction using `ValidateArgs::V2`.
+fn validate_v2_ix(
+    rule_set_addr: Pubkey,
+    mint: Pubkey,
+    payload: Payload,
+    simulate: bool,
+) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V2 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+            simulate,
+        })
+        .unwrap()
+        .instruction()
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use borsh::BorshDeserialize;
+use mpl_token_auth_rules::{
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+    pda::find_rule_set_address,
+    state::{
+        Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, RULE_SET_BUMP_LOCATION,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_SERIALIZED_HEADER_LEN,
+    },
+};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn stored_bump_matches_found_bump() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Check the stored bump
+    // --------------------------------
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    // The bump follows the header and matches the one found off-chain.
+    let (found_addr, found_bump) =
+        find_rule_set_address(context.payer.pubkey(), "test rule_set".to_string());
+    assert_eq!(found_addr, rule_set_addr);
+    assert_eq!(data[RULE_SET_BUMP_LOCATION], found_bump);
+
+    // The first revision starts after the bump.
+    let header = RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    let revision_map =
+        RuleSetRevisionMapV1::try_from_slice(&data[header.rev_map_version_location + 1..]).unwrap();
+    assert_eq!(
+        revision_map.rule_set_revisions,
+        vec![RULE_SET_FIRST_REVISION_LOCATION]
+    );
+
+    // --------------------------------
+    // Validate pass using the stored bump
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
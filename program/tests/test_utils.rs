@@ -0,0 +1,76 @@
+#![cfg(all(feature = "test-bpf", feature = "test-utils"))]
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+    test_utils, TRANSFER,
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{signature::Signer, transaction::TransactionError};
+
+#[tokio::test]
+async fn create_and_validate_with_test_utils() {
+    let mut context = test_utils::program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet allowing transfers of at most one token.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            TRANSFER,
+            Rule::Amount {
+                amount: 1,
+                operator: CompareOp::LtEq,
+                field: "Amount".to_string(),
+            },
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_pda = test_utils::create_rule_set(&mut context, &rule_set)
+        .await
+        .unwrap();
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    let mint = Pubkey::new_unique();
+    let validate_ix = test_utils::validate_ix(
+        rule_set_pda,
+        mint,
+        TRANSFER,
+        Payload::from([("Amount".to_string(), PayloadType::Number(1))]),
+        vec![],
+    );
+
+    test_utils::process_instructions(&mut context, &[validate_ix], &[])
+        .await
+        .unwrap();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    let validate_ix = test_utils::validate_ix(
+        rule_set_pda,
+        mint,
+        TRANSFER,
+        Payload::from([("Amount".to_string(), PayloadType::Number(2))]),
+        vec![],
+    );
+
+    let err = test_utils::process_instructions(&mut context, &[validate_ix], &[])
+        .await
+        .unwrap_err();
+
+    match err {
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        )) => assert_eq!(code, RuleSetError::AmountCheckFailed as u32),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
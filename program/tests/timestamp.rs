@@ -0,0 +1,256 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    payload::Payload,
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation};
+
+#[tokio::test]
+async fn test_timestamp_window() {
+    let mut context = program_test().start_with_context().await;
+
+    // Get the current on-chain time.
+    let start_time = context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule that is only valid for a window in the future.
+    let rule = Rule::Timestamp {
+        start: Some(start_time + 100),
+        end: Some(start_time + 200),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // --------------------------------
+    // Validate fail before window
+    // --------------------------------
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix.clone(), vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::TimestampCheckFailed);
+
+    // --------------------------------
+    // Validate pass in window
+    // --------------------------------
+    // Move the clock into the window.
+    set_clock(&mut context, 10, start_time + 150).await;
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix.clone(), vec![], None).await;
+
+    // --------------------------------
+    // Validate fail after window
+    // --------------------------------
+    // Move the clock past the window.
+    set_clock(&mut context, 20, start_time + 300).await;
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::TimestampCheckFailed);
+}
+
+#[tokio::test]
+async fn test_timestamp_open_window() {
+    let mut context = program_test().start_with_context().await;
+
+    // Get the current on-chain time.
+    let start_time = context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule with no end, that has already started.
+    let rule = Rule::Timestamp {
+        start: Some(start_time - 100),
+        end: None,
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn test_timestamp_with_and_without_clock_account() {
+    let mut context = program_test().start_with_context().await;
+
+    // Get the current on-chain time.
+    let start_time = context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a Rule that has already started and has not ended.
+    let rule = Rule::Timestamp {
+        start: Some(start_time - 100),
+        end: Some(start_time + 100),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass without the Clock account
+    // --------------------------------
+    // Time-based Rules read the `Clock` with the sysvar syscall, so no account is needed.
+    let validate_ix = timestamp_validate_ix(rule_set_addr, vec![]);
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // --------------------------------
+    // Validate pass with the Clock account
+    // --------------------------------
+    // Passing the `Clock` sysvar anyway, as some clients do, does not change the result.
+    let validate_ix = timestamp_validate_ix(
+        rule_set_addr,
+        vec![AccountMeta::new_readonly(sysvar::clock::ID, false)],
+    );
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+// Create a `validate` instruction for a transfer with the given additional rule accounts.
+fn timestamp_validate_ix(rule_set_addr: Pubkey, accounts: Vec<AccountMeta>) -> Instruction {
+    ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(accounts)
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction()
+}
+
+// Warp to a new slot, so that repeated transactions get a new blockhash, and then set the clock.
+async fn set_clock(context: &mut ProgramTestContext, slot: u64, unix_timestamp: i64) {
+    context.warp_to_slot(slot).unwrap();
+
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
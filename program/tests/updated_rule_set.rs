@@ -2,21 +2,22 @@
 
 pub mod utils;
 
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use mpl_token_auth_rules::{
     error::RuleSetError,
-    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    instruction::{builders::ValidateBuilder, create_or_update, InstructionBuilder, ValidateArgs},
     payload::{Payload, PayloadType},
     state::{
-        CompareOp, Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1, RULE_SET_LIB_VERSION,
-        RULE_SET_REV_MAP_VERSION, RULE_SET_SERIALIZED_HEADER_LEN,
+        from_msgpack_slice, CompareOp, Rule, RuleSetHeader, RuleSetRevisionMapV1, RuleSetV1,
+        RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_LIB_VERSION, RULE_SET_REV_MAP_VERSION,
+        RULE_SET_SERIALIZED_HEADER_LEN,
     },
 };
 use rmp_serde::Serializer;
 use serde::Serialize;
 use solana_program::instruction::AccountMeta;
 use solana_program_test::tokio;
-use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use solana_sdk::{signature::Signer, signer::keypair::Keypair, transaction::Transaction};
 use utils::{cmp_slice, program_test, Operation, PayloadKey};
 
 #[tokio::test]
@@ -232,7 +233,7 @@ async fn test_update_ruleset_data_integrity() {
         .data;
 
     // Check all the RuleSets, saving their start locations for later use.
-    let mut offsets = vec![RULE_SET_SERIALIZED_HEADER_LEN];
+    let mut offsets = vec![RULE_SET_FIRST_REVISION_LOCATION];
     for n in 0..rule_sets.len() {
         // Offset n is the `RuleSet` lib version location.
         let rule_set_version_loc = offsets[n];
@@ -291,8 +292,8 @@ async fn test_update_ruleset_data_integrity() {
         "The buffer doesn't match the serialized revision map.",
     );
 
-    // Create header using the known location of the revision map version location.
-    let header = RuleSetHeader::new(rev_map_version_loc);
+    // Create header using the known location of the revision map version location and the owner.
+    let header = RuleSetHeader::new(rev_map_version_loc, context.payer.pubkey());
 
     // Borsh serialize the header.
     let mut serialized_header = Vec::new();
@@ -591,3 +592,280 @@ async fn test_correct_rule_set_is_used_after_update() {
     // Validate Transfer operation.
     process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 }
+
+#[tokio::test]
+async fn test_update_adds_operation() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet with only a Transfer operation.
+    let mut first_rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    first_rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, first_rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate fail for operation not yet in RuleSet
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate` instruction for an Update operation.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate Update operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix.clone(), vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::OperationNotFound);
+
+    // --------------------------------
+    // Update RuleSet to add an operation
+    // --------------------------------
+    // Create a new RuleSet with both Transfer and Update operations.
+    let mut second_rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    second_rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+    second_rule_set
+        .add(
+            Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // Put the updated RuleSet on chain.
+    let _rule_set_addr =
+        create_rule_set_on_chain!(&mut context, second_rule_set, "test rule_set".to_string()).await;
+
+    // Move forward to avoid duplicate transactions.
+    context.warp_to_slot(3).unwrap();
+
+    // --------------------------------
+    // Validate pass for the new operation
+    // --------------------------------
+    // Validate Update operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn test_create_or_update_helper_creates_then_updates() {
+    let mut context = program_test().start_with_context().await;
+
+    // Find RuleSet PDA.
+    let (rule_set_addr, _rule_set_bump) = mpl_token_auth_rules::pda::find_rule_set_address(
+        context.payer.pubkey(),
+        "test rule_set".to_string(),
+    );
+
+    // Create two revisions of a RuleSet, the second adding an Update operation.
+    let mut first_rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    first_rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    let mut second_rule_set = first_rule_set.clone();
+    second_rule_set
+        .add(
+            Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            Rule::Pass,
+        )
+        .unwrap();
+
+    // --------------------------------
+    // Send the same instruction for both revisions
+    // --------------------------------
+    let mut serialized_rule_sets = vec![];
+    for rule_set in [&first_rule_set, &second_rule_set] {
+        // Serialize the RuleSet using RMP serde.
+        let mut serialized_rule_set = Vec::new();
+        rule_set
+            .serialize(&mut Serializer::new(&mut serialized_rule_set))
+            .unwrap();
+        serialized_rule_sets.push(serialized_rule_set.clone());
+
+        // The first instruction creates the PDA, and the second updates it.
+        let create_or_update_ix = create_or_update(
+            context.payer.pubkey(),
+            rule_set_addr,
+            serialized_rule_set,
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_or_update_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    // --------------------------------
+    // Validate the on chain data
+    // --------------------------------
+    // Get the `RuleSet` PDA data.
+    let data = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+
+    // Check that both revisions are stored, one after the other.
+    let header = RuleSetHeader::try_from_slice(&data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+    let revision_map =
+        RuleSetRevisionMapV1::try_from_slice(&data[header.rev_map_version_location + 1..]).unwrap();
+
+    let second_loc = RULE_SET_FIRST_REVISION_LOCATION + 1 + serialized_rule_sets[0].len();
+    assert_eq!(
+        revision_map.rule_set_revisions,
+        vec![RULE_SET_FIRST_REVISION_LOCATION, second_loc]
+    );
+
+    // Check that the latest revision is the second RuleSet.
+    assert_eq!(
+        from_msgpack_slice::<RuleSetV1>(&data[second_loc + 1..header.rev_map_version_location]),
+        Ok(second_rule_set)
+    );
+
+    // --------------------------------
+    // Validate pass for the new operation
+    // --------------------------------
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Update {
+                scenario: utils::UpdateScenario::MetadataAuth,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn test_update_grows_account_for_larger_rule_set_and_back() {
+    let mut context = program_test().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let operation = Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string();
+
+    // A small RuleSet, and a much larger one holding a long list of `Pubkey`s.
+    let mut small_rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    small_rule_set.add(operation.clone(), Rule::Pass).unwrap();
+
+    let mut large_rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    large_rule_set
+        .add(
+            operation.clone(),
+            Rule::PubkeyListMatch {
+                pubkeys: (0..15).map(|_| Keypair::new().pubkey()).collect(),
+                field: PayloadKey::Destination.to_string(),
+            },
+        )
+        .unwrap();
+
+    // --------------------------------
+    // Create the small RuleSet, then update it to the large one and back
+    // --------------------------------
+    let mut data_lens = vec![];
+    for (slot, rule_set) in [&small_rule_set, &large_rule_set, &small_rule_set]
+        .into_iter()
+        .enumerate()
+    {
+        // Move forward to avoid duplicate transactions if RuleSets are same.
+        context.warp_to_slot(slot as u64 + 2).unwrap();
+
+        let rule_set_addr =
+            create_rule_set_on_chain!(&mut context, rule_set.clone(), "test rule_set".to_string())
+                .await;
+
+        let account = context
+            .banks_client
+            .get_account(rule_set_addr)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The account is resized to hold the new revision, and stays rent exempt.
+        assert!(rent.is_exempt(account.lamports, account.data.len()));
+        data_lens.push(account.data.len());
+
+        // The latest revision is the RuleSet just written.
+        let header =
+            RuleSetHeader::try_from_slice(&account.data[..RULE_SET_SERIALIZED_HEADER_LEN]).unwrap();
+        let revision_map = RuleSetRevisionMapV1::try_from_slice(
+            &account.data[header.rev_map_version_location + 1..],
+        )
+        .unwrap();
+        let latest = *revision_map.rule_set_revisions.last().unwrap();
+        assert_eq!(
+            from_msgpack_slice::<RuleSetV1>(
+                &account.data[latest + 1..header.rev_map_version_location]
+            )
+            .as_ref(),
+            Ok(rule_set)
+        );
+    }
+
+    // The large revision grows the account by more than the small one.  Going back to the small
+    // RuleSet still grows the account, because every earlier revision is kept.
+    assert!(data_lens[1] - data_lens[0] > data_lens[2] - data_lens[1]);
+    assert!(data_lens[2] > data_lens[1]);
+}
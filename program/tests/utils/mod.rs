@@ -8,14 +8,21 @@ use mpl_token_auth_rules::{
 };
 use rmp_serde::Serializer;
 use serde::Serialize;
-use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
 use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, program_pack::Pack, signature::Signer,
-    signer::keypair::Keypair, system_instruction, transaction::Transaction,
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    program_pack::Pack,
+    signature::Signer,
+    signer::keypair::{keypair_from_seed, Keypair},
+    system_instruction,
+    transaction::Transaction,
 };
 use std::fmt::Display;
 
+pub use mpl_token_auth_rules::payload::PayloadKey;
+
 // --------------------------------
 // RuleSet operations and scenarios
 // from token-metadata
@@ -136,64 +143,54 @@ impl ToString for Operation {
     }
 }
 
-// Payload key type from token-metadata.
-#[repr(C)]
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum PayloadKey {
-    /// The amount being transferred.
-    Amount,
-    /// The authority of an operation, e.g. the delegate of token.
-    Authority,
-    /// Seeds for a PDA authority of the operation, e.g. when the authority is a PDA.
-    AuthoritySeeds,
-    /// Merkle proof for the source of the operation, e.g. when the authority is a member
-    /// of a Merkle tree.
-    AuthorityProof,
-    /// Delegate for an operation.
-    Delegate,
-    /// Seeds for a PDA delegate of the operation.
-    DelegateSeeds,
-    /// The destination of the operation, e.g. the recipient of a transfer.
-    Destination,
-    /// Seeds for a PDA destination of the operation, e.g. when the recipient is a PDA.
-    DestinationSeeds,
-    /// A token holder.
-    Holder,
-    /// The source of the operation, e.g. the owner initiating a transfer.
-    Source,
-    /// Seeds for a PDA source of the operation, e.g. when the source is a PDA.
-    SourceSeeds,
-    /// Merkle proof for the source of the operation, e.g. when the source is a member
-    /// of a Merkle tree.
-    SourceProof,
-    /// Merkle proof for the destination of the operation, e.g. when the distination
-    /// is a member of a Merkle tree.
-    DestinationProof,
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new("mpl_token_auth_rules", mpl_token_auth_rules::id(), None)
 }
 
-impl ToString for PayloadKey {
-    fn to_string(&self) -> String {
-        match self {
-            PayloadKey::Amount => "Amount",
-            PayloadKey::Authority => "Authority",
-            PayloadKey::AuthoritySeeds => "AuthoritySeeds",
-            PayloadKey::AuthorityProof => "AuthorityProof",
-            PayloadKey::Delegate => "Delegate",
-            PayloadKey::DelegateSeeds => "DelegateSeeds",
-            PayloadKey::SourceProof => "SourceProof",
-            PayloadKey::Destination => "Destination",
-            PayloadKey::DestinationSeeds => "DestinationSeeds",
-            PayloadKey::DestinationProof => "DestinationProof",
-            PayloadKey::Holder => "Holder",
-            PayloadKey::Source => "Source",
-            PayloadKey::SourceSeeds => "SourceSeeds",
-        }
-        .to_string()
-    }
+// A `RuleSet` PDA written before the header held the frozen flag and the owner.  It was created
+// with a `Transfer:Owner` operation and then updated to also hold a `Delegate:Transfer`
+// operation, both with a `Pass` rule, by `legacy_rule_set_owner`.
+pub const LEGACY_RULE_SET: &[u8] = include_bytes!("../fixtures/legacy_rule_set.bin");
+
+pub const LEGACY_RULE_SET_NAME: &str = "legacy rule_set";
+
+// The locations of the two revisions and of the revision map version in `LEGACY_RULE_SET`.
+pub const LEGACY_REVISIONS: [usize; 2] = [9, 98];
+pub const LEGACY_REV_MAP_VERSION_LOCATION: usize = 210;
+
+// The owner that wrote `LEGACY_RULE_SET`.
+pub fn legacy_rule_set_owner() -> Keypair {
+    keypair_from_seed(&[7; 32]).unwrap()
 }
 
-pub fn program_test() -> ProgramTest {
-    ProgramTest::new("mpl_token_auth_rules", mpl_token_auth_rules::id(), None)
+// Add `LEGACY_RULE_SET` at its PDA and fund its owner, returning the owner and the PDA.
+pub fn add_legacy_rule_set(context: &mut ProgramTestContext) -> (Keypair, Pubkey) {
+    let owner = legacy_rule_set_owner();
+    let (rule_set_addr, _) = mpl_token_auth_rules::pda::find_rule_set_address(
+        owner.pubkey(),
+        LEGACY_RULE_SET_NAME.to_string(),
+    );
+
+    context.set_account(
+        &rule_set_addr,
+        &Account {
+            lamports: Rent::default().minimum_balance(LEGACY_RULE_SET.len()),
+            data: LEGACY_RULE_SET.to_vec(),
+            owner: mpl_token_auth_rules::ID,
+            ..Account::default()
+        }
+        .into(),
+    );
+    context.set_account(
+        &owner.pubkey(),
+        &Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        }
+        .into(),
+    );
+
+    (owner, rule_set_addr)
 }
 
 #[macro_export]
@@ -671,3 +668,38 @@ pub fn create_test_merkle_tree_from_one_leaf(leaf: &Pubkey, levels: usize) -> Me
         proof: ProofInfo::new(proof),
     }
 }
+
+pub fn create_test_merkle_tree_from_leaves(leaves: &[Pubkey], index: usize) -> MerkleTree {
+    // Start the tree level with the callers' leaves.
+    let mut level: Vec<[u8; 32]> = leaves.iter().map(|leaf| leaf.to_bytes()).collect();
+    let mut index = index;
+    let mut proof = vec![];
+
+    while level.len() > 1 {
+        // Duplicate the last node if the level has an odd number of nodes.
+        if level.len() % 2 == 1 {
+            level.push(*level.last().unwrap());
+        }
+
+        // The sibling of the current node is the next element of the proof.
+        proof.push(level[index ^ 1]);
+
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                if pair[0] <= pair[1] {
+                    solana_program::keccak::hashv(&[&[0x01], &pair[0], &pair[1]]).0
+                } else {
+                    solana_program::keccak::hashv(&[&[0x01], &pair[1], &pair[0]]).0
+                }
+            })
+            .collect();
+
+        index /= 2;
+    }
+
+    MerkleTree {
+        root: level[0],
+        proof: ProofInfo::new(proof),
+    }
+}
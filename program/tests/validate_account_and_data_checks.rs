@@ -4,15 +4,18 @@ pub mod utils;
 
 use mpl_token_auth_rules::{
     error::RuleSetError,
-    instruction::{builders::ValidateBuilder, InstructionBuilder, ValidateArgs},
+    instruction::{builders::ValidateBuilder, validate, InstructionBuilder, ValidateArgs},
     payload::Payload,
-    state::{Rule, RuleSetV1},
+    state::{
+        Key, Rule, RuleSetV1, RULE_SET_BUMP_LOCATION, RULE_SET_FIRST_REVISION_LOCATION,
+        RULE_SET_LIB_VERSION,
+    },
 };
 
-use solana_program::program_error::ProgramError;
-use solana_program::system_instruction;
+use solana_program::{program_error::ProgramError, system_instruction, system_program};
 use solana_program_test::{tokio, BanksClientError};
 use solana_sdk::{
+    account::Account,
     signature::Signer,
     signer::keypair::Keypair,
     transaction::{Transaction, TransactionError},
@@ -89,6 +92,14 @@ async fn validate_update_rule_state_payer_not_signer_panics() {
 async fn validate_update_rule_state_payer_not_provided_fails() {
     let mut context = program_test().start_with_context().await;
 
+    // Create a Rule that updates rule state, as a Pass would skip the state checks.
+    let rule_authority = Keypair::new();
+    let rule = Rule::Frequency {
+        authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
+    };
+
     // Create a RuleSet.
     let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
     rule_set
@@ -97,7 +108,7 @@ async fn validate_update_rule_state_payer_not_provided_fails() {
                 scenario: utils::TransferScenario::Holder,
             }
             .to_string(),
-            Rule::Pass,
+            rule,
         )
         .unwrap();
 
@@ -108,8 +119,6 @@ async fn validate_update_rule_state_payer_not_provided_fails() {
     // Create a Keypair to simulate a token mint address.
     let mint = Keypair::new().pubkey();
 
-    let rule_authority = Keypair::new();
-
     let (rule_set_state_pda, _rule_set_state_pda_bump) =
         mpl_token_auth_rules::pda::find_rule_set_state_address(
             context.payer.pubkey(),
@@ -158,12 +167,24 @@ async fn validate_update_rule_state_payer_not_provided_fails() {
 async fn validate_rule_set_with_wallet_fails() {
     let mut context = program_test().start_with_context().await;
 
+    // Create a funded wallet, which is owned by the system program.
+    let wallet = Keypair::new().pubkey();
+    context.set_account(
+        &wallet,
+        &Account {
+            lamports: 1_000_000_000,
+            owner: system_program::ID,
+            ..Account::default()
+        }
+        .into(),
+    );
+
     // Create a Keypair to simulate a token mint address.
     let mint = Keypair::new().pubkey();
 
     // Create a `validate` instruction.
     let validate_ix = ValidateBuilder::new()
-        .rule_set_pda(Keypair::new().pubkey())
+        .rule_set_pda(wallet)
         .mint(mint)
         .additional_rule_accounts(vec![])
         .build(ValidateArgs::V1 {
@@ -182,7 +203,7 @@ async fn validate_rule_set_with_wallet_fails() {
     let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::IncorrectOwner);
+    assert_custom_error!(err, RuleSetError::Uninitialized);
 }
 
 #[tokio::test]
@@ -219,7 +240,7 @@ async fn validate_rule_set_with_uninitialized_pda_fails() {
     let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::IncorrectOwner);
+    assert_custom_error!(err, RuleSetError::Uninitialized);
 }
 
 #[tokio::test]
@@ -269,7 +290,7 @@ async fn validate_rule_set_with_zero_data_fails() {
     let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 
     // Check that error is what we expect.
-    assert_custom_error!(err, RuleSetError::DataIsEmpty);
+    assert_custom_error!(err, RuleSetError::Uninitialized);
 }
 
 #[tokio::test]
@@ -320,13 +341,57 @@ async fn validate_rule_set_with_incorrect_data_fails() {
     // Fail to validate operation.
     let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
 
-    // Check that error is what we expect.  This happens to be how data with all zeros fails to
-    // deserialize.
-    assert_custom_error!(err, RuleSetError::UnsupportedRuleSetRevMapVersion);
+    // Check that error is what we expect.  Data with all zeros has an `Uninitialized` key.
+    assert_custom_error!(err, RuleSetError::Uninitialized);
 }
 
 #[tokio::test]
-async fn validate_update_rule_state_wrong_state_pda_fails() {
+async fn validate_rule_set_with_wrong_key_fails() {
+    // Overwrite the `Key` at the start of the header.
+    let err = validate_corrupted_rule_set(0, Key::Frequency as u8).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::Uninitialized);
+}
+
+#[tokio::test]
+async fn validate_rule_set_not_program_owned_fails() {
+    // Hand a valid `RuleSet` over to another program.
+    let err = validate_modified_rule_set(|account| account.owner = Keypair::new().pubkey()).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::Uninitialized);
+}
+
+#[tokio::test]
+async fn validate_rule_set_with_wrong_lib_version_fails() {
+    // Overwrite the lib version at the start of the first `RuleSet` revision, which directly
+    // follows the header and the stored bump.
+    let err =
+        validate_corrupted_rule_set(RULE_SET_FIRST_REVISION_LOCATION, RULE_SET_LIB_VERSION + 1)
+            .await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::InvalidVersion);
+}
+
+#[tokio::test]
+async fn validate_rule_set_with_wrong_bump_fails() {
+    // Overwrite the stored bump so that it no longer derives the `RuleSet` PDA.  The canonical
+    // bump is the highest one that is off the curve, so it is never zero in practice.
+    let err = validate_corrupted_rule_set(RULE_SET_BUMP_LOCATION, 0).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DerivedKeyInvalid);
+}
+
+// Create a `RuleSet`, overwrite one byte of its PDA data, and return the `validate` error.
+async fn validate_corrupted_rule_set(index: usize, value: u8) -> BanksClientError {
+    validate_modified_rule_set(|account| account.data[index] = value).await
+}
+
+// Create a `RuleSet`, modify its PDA account, and return the `validate` error.
+async fn validate_modified_rule_set(modify: impl FnOnce(&mut Account)) -> BanksClientError {
     let mut context = program_test().start_with_context().await;
 
     // Create a RuleSet.
@@ -345,10 +410,70 @@ async fn validate_update_rule_state_wrong_state_pda_fails() {
     let rule_set_addr =
         create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
 
+    // Modify the RuleSet PDA account.
+    let mut rule_set_account = context
+        .banks_client
+        .get_account(rule_set_addr)
+        .await
+        .unwrap()
+        .unwrap();
+    modify(&mut rule_set_account);
+    context.set_account(&rule_set_addr, &rule_set_account.into());
+
     // Create a Keypair to simulate a token mint address.
     let mint = Keypair::new().pubkey();
 
+    // Create a `validate` instruction.
+    let validate_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(ValidateArgs::V1 {
+            operation: Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            payload: Payload::default(),
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate operation.
+    process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await
+}
+
+#[tokio::test]
+async fn validate_update_rule_state_wrong_state_pda_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a Rule that updates rule state, as a Pass would skip the state checks.
     let rule_authority = Keypair::new();
+    let rule = Rule::Frequency {
+        authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
 
     // Find RuleSet state PDA using WRONG NAME for seed.
     let (rule_set_state_pda, _rule_set_state_pda_bump) =
@@ -390,6 +515,14 @@ async fn validate_update_rule_state_wrong_state_pda_fails() {
 async fn validate_update_rule_state_state_pda_not_provided_fails() {
     let mut context = program_test().start_with_context().await;
 
+    // Create a Rule that updates rule state, as a Pass would skip the state checks.
+    let rule_authority = Keypair::new();
+    let rule = Rule::Frequency {
+        authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
+    };
+
     // Create a RuleSet.
     let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
     rule_set
@@ -398,7 +531,7 @@ async fn validate_update_rule_state_state_pda_not_provided_fails() {
                 scenario: utils::TransferScenario::Holder,
             }
             .to_string(),
-            Rule::Pass,
+            rule,
         )
         .unwrap();
 
@@ -409,8 +542,6 @@ async fn validate_update_rule_state_state_pda_not_provided_fails() {
     // Create a Keypair to simulate a token mint address.
     let mint = Keypair::new().pubkey();
 
-    let rule_authority = Keypair::new();
-
     // Create a `validate` instruction with `update_rule_state` set to true.
     let validate_ix = ValidateBuilder::new()
         .rule_set_pda(rule_set_addr)
@@ -456,6 +587,8 @@ async fn validate_update_rule_state_incorrect_auth() {
     let rule_authority = Keypair::new();
     let rule = Rule::Frequency {
         authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
     };
 
     // Create a RuleSet.
@@ -519,6 +652,8 @@ async fn validate_update_rule_state_missing_auth() {
     let rule_authority = Keypair::new();
     let rule = Rule::Frequency {
         authority: rule_authority.pubkey(),
+        period: 3600,
+        name: String::new(),
     };
 
     // Create a RuleSet.
@@ -572,3 +707,39 @@ async fn validate_update_rule_state_missing_auth() {
     // Check that error is what we expect.
     assert_custom_error!(err, RuleSetError::MissingAccount);
 }
+
+#[test]
+fn validate_with_program_id_override() {
+    let rule_set_pda = Keypair::new().pubkey();
+    let mint = Keypair::new().pubkey();
+    let args = ValidateArgs::V1 {
+        operation: Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        }
+        .to_string(),
+        payload: Payload::default(),
+        update_rule_state: false,
+        rule_set_revision: None,
+    };
+
+    // The instruction targets this program by default.
+    let default_ix = validate(rule_set_pda, mint, vec![], args.clone(), None);
+    assert_eq!(default_ix.program_id, mpl_token_auth_rules::ID);
+
+    // A custom program ID only changes the instruction's target.
+    let program_id = Keypair::new().pubkey();
+    let custom_ix = validate(rule_set_pda, mint, vec![], args.clone(), Some(program_id));
+    assert_eq!(custom_ix.program_id, program_id);
+    assert_eq!(custom_ix.accounts, default_ix.accounts);
+    assert_eq!(custom_ix.data, default_ix.data);
+
+    // The builder supports the same override.
+    let builder_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_pda)
+        .mint(mint)
+        .additional_rule_accounts(vec![])
+        .build(args)
+        .unwrap()
+        .instruction_with_program_id(program_id);
+    assert_eq!(builder_ix, custom_ix);
+}
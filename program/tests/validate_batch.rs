@@ -0,0 +1,224 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{builders::ValidateBatchBuilder, InstructionBuilder, ValidateBatchArgs},
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Operation, Rule, RuleSetV1},
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{
+    instruction::AccountMeta, signature::Signer, signer::keypair::Keypair, transaction::Transaction,
+};
+use utils::{program_test, PayloadKey};
+
+#[tokio::test]
+async fn validate_batch() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create rules.
+    let additional_signer = Keypair::new();
+    let adtl_signer_rule = Rule::AdditionalSigner {
+        account: additional_signer.pubkey(),
+    };
+
+    let amount_check = Rule::Amount {
+        amount: 10,
+        operator: CompareOp::Lt,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(Operation::Transfer, Rule::Pass).unwrap();
+    rule_set.add(Operation::Delegate, amount_check).unwrap();
+    rule_set
+        .add(Operation::SaleTransfer, adtl_signer_rule)
+        .unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Create a `validate_batch` instruction for all three operations.
+    let validate_ix = ValidateBatchBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(
+            additional_signer.pubkey(),
+            true,
+        )])
+        .build(ValidateBatchArgs::V1 {
+            operations: vec![
+                (Operation::Transfer.to_string(), Payload::default()),
+                (
+                    Operation::Delegate.to_string(),
+                    Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(2))]),
+                ),
+                (Operation::SaleTransfer.to_string(), Payload::default()),
+            ],
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Validate all operations.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![&additional_signer], None).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Create a `validate_batch` instruction where the `Delegate` amount is too large.
+    let validate_ix = ValidateBatchBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(
+            additional_signer.pubkey(),
+            true,
+        )])
+        .build(ValidateBatchArgs::V1 {
+            operations: vec![
+                (Operation::Transfer.to_string(), Payload::default()),
+                (
+                    Operation::Delegate.to_string(),
+                    Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(20))]),
+                ),
+                (Operation::SaleTransfer.to_string(), Payload::default()),
+            ],
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+
+    // Fail to validate operations.
+    let err =
+        process_failing_validate_ix!(&mut context, validate_ix, vec![&additional_signer], None)
+            .await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+}
+
+#[tokio::test]
+async fn validate_batch_logs_operation_results() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a RuleSet where only the `Delegate` operation can fail.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(Operation::Transfer, Rule::Pass).unwrap();
+    rule_set
+        .add(
+            Operation::Delegate,
+            Rule::Amount {
+                amount: 10,
+                operator: CompareOp::Lt,
+                field: PayloadKey::Amount.to_string(),
+            },
+        )
+        .unwrap();
+    rule_set.add(Operation::SaleTransfer, Rule::Pass).unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // Three operations, where the middle one fails.
+    let operations = vec![
+        (Operation::Transfer.to_string(), Payload::default()),
+        (
+            Operation::Delegate.to_string(),
+            Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(20))]),
+        ),
+        (Operation::SaleTransfer.to_string(), Payload::default()),
+    ];
+
+    // --------------------------------
+    // Simulate pass
+    // --------------------------------
+    // Every operation is validated and the instruction succeeds.
+    let simulate_ix = validate_batch_v2_ix(rule_set_addr, operations.clone(), true);
+    let (result, log_messages) = simulate_ix_logs(&mut context, simulate_ix).await;
+
+    assert_eq!(result, Ok(()));
+    assert_logged(&log_messages, "Operation results: [true, false, true]");
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Validation stops at the failing operation, logging the results up to it.
+    let validate_ix = validate_batch_v2_ix(rule_set_addr, operations, false);
+    let (result, log_messages) = simulate_ix_logs(&mut context, validate_ix).await;
+
+    assert!(result.is_err());
+    assert_logged(&log_messages, "Operation results: [true, false]");
+}
+
+// Create a `validate_batch` instruction using `ValidateBatchArgs::V2`.
+fn validate_batch_v2_ix(
+    rule_set_addr: Pubkey,
+    operations: Vec<(String, Payload)>,
+    simulate: bool,
+) -> Instruction {
+    ValidateBatchBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(Keypair::new().pubkey())
+        .additional_rule_accounts(vec![])
+        .build(ValidateBatchArgs::V2 {
+            operations,
+            update_rule_state: false,
+            rule_set_revision: None,
+            simulate,
+        })
+        .unwrap()
+        .instruction()
+}
+
+// Simulate a transaction holding `ix`, returning its result and the program logs.
+async fn simulate_ix_logs(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+) -> (
+    Result<(), solana_sdk::transaction::TransactionError>,
+    Vec<String>,
+) {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+
+    let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+    (
+        simulation.result.unwrap(),
+        simulation.simulation_details.unwrap().logs,
+    )
+}
+
+// Check that a log message ends with `expected`.
+fn assert_logged(log_messages: &[String], expected: &str) {
+    assert!(
+        log_messages.iter().any(|log| log.ends_with(expected)),
+        "missing {:?} in {:#?}",
+        expected,
+        log_messages
+    );
+}
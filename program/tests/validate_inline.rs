@@ -0,0 +1,157 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{validate_inline, CreateOrUpdateArgs, MAX_INLINE_RULE_SET_LEN},
+    payload::{Payload, PayloadType},
+    state::{Rule, RuleSetV1},
+};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{account::Account, signature::Signer};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn validate_inline_program_owned() {
+    // Create accounts owned by the expected program and by another program.
+    let program = Pubkey::new_unique();
+    let program_owned = Pubkey::new_unique();
+    let other_owned = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    for (key, owner) in [
+        (program_owned, program),
+        (other_owned, Pubkey::new_unique()),
+    ] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: 1_000_000_000,
+                data: vec![1; 8],
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Create a ProgramOwned Rule.
+    let rule = Rule::ProgramOwned {
+        program,
+        field: PayloadKey::Destination.to_string(),
+    };
+
+    // Create a RuleSet, which is only serialized and never put on chain.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(
+            Operation::Transfer {
+                scenario: utils::TransferScenario::Holder,
+            }
+            .to_string(),
+            rule,
+        )
+        .unwrap();
+
+    let CreateOrUpdateArgs::V1 {
+        serialized_rule_set,
+    } = CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap();
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Store the account owned by another program as the destination.
+    let validate_ix = validate_inline(
+        serialized_rule_set.clone(),
+        Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        }
+        .to_string(),
+        Payload::from([(
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(other_owned),
+        )]),
+        vec![AccountMeta::new_readonly(other_owned, false)],
+    );
+
+    // Fail to validate Transfer operation.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::ProgramOwnedCheckFailed);
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Store the account owned by the expected program as the destination.
+    let validate_ix = validate_inline(
+        serialized_rule_set,
+        Operation::Transfer {
+            scenario: utils::TransferScenario::Holder,
+        }
+        .to_string(),
+        Payload::from([(
+            PayloadKey::Destination.to_string(),
+            PayloadType::Pubkey(program_owned),
+        )]),
+        vec![AccountMeta::new_readonly(program_owned, false)],
+    );
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+}
+
+#[tokio::test]
+async fn validate_inline_too_large_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Send more bytes than an inline RuleSet can have.
+    let validate_ix = validate_inline(
+        vec![0; MAX_INLINE_RULE_SET_LEN + 1],
+        Operation::TransferNamespace.to_string(),
+        Payload::default(),
+        vec![],
+    );
+
+    // Fail to validate.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::RuleSetTooLarge);
+}
+
+#[tokio::test]
+async fn validate_inline_trailing_bytes_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    // Create a RuleSet, which is only serialized and never put on chain.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set
+        .add(Operation::TransferNamespace.to_string(), Rule::Pass)
+        .unwrap();
+
+    let CreateOrUpdateArgs::V1 {
+        mut serialized_rule_set,
+    } = CreateOrUpdateArgs::from_rule_set(&rule_set).unwrap();
+
+    // Append bytes after the serialized RuleSet.
+    serialized_rule_set.extend_from_slice(&[1, 2, 3]);
+
+    let validate_ix = validate_inline(
+        serialized_rule_set,
+        Operation::TransferNamespace.to_string(),
+        Payload::default(),
+        vec![],
+    );
+
+    // Fail to validate.
+    let err = process_failing_validate_ix!(&mut context, validate_ix, vec![], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::DataTypeMismatch);
+}
@@ -0,0 +1,141 @@
+#![cfg(feature = "test-bpf")]
+
+pub mod utils;
+
+use mpl_token_auth_rules::{
+    error::RuleSetError,
+    instruction::{
+        builders::ValidateBuilder, InstructionBuilder, ValidateArgs, ValidateInstructionBuilder,
+    },
+    payload::{Payload, PayloadType},
+    state::{CompareOp, Rule, RuleSetV1},
+};
+use solana_program::instruction::AccountMeta;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Signer, signer::keypair::Keypair};
+use utils::{program_test, Operation, PayloadKey};
+
+#[tokio::test]
+async fn validate_instruction_builder_transfer() {
+    let mut context = program_test().start_with_context().await;
+
+    // --------------------------------
+    // Create RuleSet
+    // --------------------------------
+    // Additional signer.
+    let adtl_signer = Keypair::new();
+
+    // Create some rules.
+    let adtl_signer_rule = Rule::AdditionalSigner {
+        account: adtl_signer.pubkey(),
+    };
+
+    let amount_rule = Rule::Amount {
+        amount: 1,
+        operator: CompareOp::LtEq,
+        field: PayloadKey::Amount.to_string(),
+    };
+
+    let overall_rule = Rule::All {
+        rules: vec![adtl_signer_rule, amount_rule],
+    };
+
+    // Create a RuleSet.
+    let mut rule_set = RuleSetV1::new("test rule_set".to_string(), context.payer.pubkey());
+    rule_set.add(transfer(), overall_rule).unwrap();
+
+    // Put the RuleSet on chain.
+    let rule_set_addr =
+        create_rule_set_on_chain!(&mut context, rule_set, "test rule_set".to_string()).await;
+
+    // --------------------------------
+    // Validate pass
+    // --------------------------------
+    // Create a Keypair to simulate a token mint address.
+    let mint = Keypair::new().pubkey();
+
+    // Store the payload of data to validate against the rule definition.
+    let payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(1))]);
+
+    // Create a `validate` instruction with the additional signer.
+    let validate_ix = ValidateInstructionBuilder::new()
+        .rule_set(rule_set_addr)
+        .mint(mint)
+        .operation(transfer())
+        .payload(payload.clone())
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(adtl_signer.pubkey(), true)])
+        .build()
+        .unwrap();
+
+    // It is the same instruction as one built from `ValidateArgs`.
+    let expected_ix = ValidateBuilder::new()
+        .rule_set_pda(rule_set_addr)
+        .mint(mint)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(adtl_signer.pubkey(), true)])
+        .build(ValidateArgs::V1 {
+            operation: transfer(),
+            payload,
+            update_rule_state: false,
+            rule_set_revision: None,
+        })
+        .unwrap()
+        .instruction();
+    assert_eq!(validate_ix, expected_ix);
+
+    // Validate Transfer operation.
+    process_passing_validate_ix!(&mut context, validate_ix, vec![&adtl_signer], None).await;
+
+    // --------------------------------
+    // Validate fail
+    // --------------------------------
+    // Store an amount over the limit.
+    let payload = Payload::from([(PayloadKey::Amount.to_string(), PayloadType::Number(2))]);
+
+    let validate_ix = ValidateInstructionBuilder::new()
+        .rule_set(rule_set_addr)
+        .mint(mint)
+        .operation(transfer())
+        .payload(payload)
+        .additional_rule_accounts(vec![AccountMeta::new_readonly(adtl_signer.pubkey(), true)])
+        .build()
+        .unwrap();
+
+    // Fail to validate Transfer operation.
+    let err =
+        process_failing_validate_ix!(&mut context, validate_ix, vec![&adtl_signer], None).await;
+
+    // Check that error is what we expect.
+    assert_custom_error!(err, RuleSetError::AmountCheckFailed);
+}
+
+#[test]
+fn validate_instruction_builder_defaults() {
+    let rule_set = Keypair::new().pubkey();
+    let mint = Keypair::new().pubkey();
+
+    // The rule set, mint and operation are required.
+    let mut builder = ValidateInstructionBuilder::new();
+    assert!(builder.build().is_err());
+    builder.rule_set(rule_set).mint(mint);
+    assert!(builder.build().is_err());
+
+    // The optional accounts are passed as the program ID, and the instruction goes to the
+    // canonical program.
+    let validate_ix = builder.operation(transfer()).build().unwrap();
+    assert_eq!(validate_ix.program_id, mpl_token_auth_rules::ID);
+    assert!(validate_ix.accounts[3..]
+        .iter()
+        .all(|account| account.pubkey == mpl_token_auth_rules::ID));
+
+    // Another deployment of the program can be targeted.
+    let program = Keypair::new().pubkey();
+    let validate_ix = builder.auth_rules_program(program).build().unwrap();
+    assert_eq!(validate_ix.program_id, program);
+}
+
+fn transfer() -> String {
+    Operation::Transfer {
+        scenario: utils::TransferScenario::Holder,
+    }
+    .to_string()
+}